@@ -0,0 +1,440 @@
+// =============================================================================
+// User Configuration
+// =============================================================================
+//
+// Typed representation of config.json, shared by the `load_config` /
+// `save_config` IPC commands and the startup code.
+//
+// KEY DESIGN POINTS:
+// - Keys are camelCase on disk and over IPC, matching the frontend
+//   (e.g. `geminiApiKey`)
+// - Every field has a default, so older config files (or ones written by a
+//   newer version) still load
+// - Keys this struct doesn't know about (window position, theme settings
+//   owned by the frontend, ...) are kept in `extra` and written back
+//   unchanged, so saving from the backend never drops frontend settings
+// - The file carries a `version`; older shapes are upgraded by `migrate`
+//   before deserializing, and saving always writes the current version
+//
+// =============================================================================
+
+use crate::shared::{
+    Anchor, AppProfile, InputState, SharedState, ShortcutBinding, ShortcutKeyMode, SnapEdges,
+    StartupCursorMode, DEFAULT_ACTIVITY_INTERVAL_MS, DEFAULT_CURSOR_EMIT_INTERVAL_MS,
+    DEFAULT_IDLE_TIMEOUT_SECS, DEFAULT_MAX_DELTA_PER_EVENT, DEFAULT_MAX_POLL_TIMEOUT_MS,
+    DEFAULT_POLL_TIMEOUT_MS,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Version of the config.json shape written by this build
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Keys older versions (and older frontends) stored the Gemini key under
+const LEGACY_API_KEY_NAMES: [&str; 2] = ["apiKey", "gemini_api_key"];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppConfig {
+    /// Shape version of the file this was read from; files without one
+    /// predate versioning and count as 0
+    pub version: u32,
+
+    /// Name of the active character theme (e.g. "fox")
+    pub theme: String,
+
+    /// API key for the Gemini chat backend
+    pub gemini_api_key: String,
+
+    /// Shortcut bindings detected by the input thread.
+    /// Stored as "shortcutBindings" because the frontend already uses
+    /// "shortcuts" for its global-shortcut settings.
+    #[serde(rename = "shortcutBindings")]
+    pub shortcuts: Vec<ShortcutBinding>,
+
+    /// Shortcut actions the backend must not handle itself (e.g. "quit"),
+    /// leaving them to the frontend
+    pub frontend_only_actions: Vec<String>,
+
+    /// Shortcuts to disable while particular apps are in the foreground
+    pub app_profiles: Vec<AppProfile>,
+
+    /// How letter and digit shortcut keys are matched: "physical" (by key
+    /// position, as on US QWERTY) or "logical" (by the character the active
+    /// keyboard layout types)
+    pub shortcut_key_mode: String,
+
+    /// How the first cursor position after startup is sent: "jump" (as a
+    /// cursor-jump the character snaps to), "emit" (as a regular cursor
+    /// move) or "suppress" (not at all)
+    pub startup_cursor_mode: String,
+
+    /// Multiplier for relative pointer movement (1.0 = raw device speed)
+    pub sensitivity: f32,
+
+    /// Smooth the reported cursor position instead of following raw input
+    pub cursor_smoothing: bool,
+
+    /// Also emit cursor-motion events carrying the pointer velocity
+    pub cursor_velocity: bool,
+
+    /// Report the cursor this many milliseconds ahead of where it is,
+    /// along its current velocity, to hide IPC latency (0 = off)
+    pub cursor_prediction_ms: u64,
+
+    /// Largest relative movement (per axis, in pixels) accepted from one
+    /// device report; bigger jumps are capped as hardware glitches
+    /// (0 = no cap)
+    pub max_delta_per_event: u32,
+
+    /// Minimum milliseconds between two cursor-pos events (0 = unthrottled)
+    pub cursor_emit_interval_ms: u64,
+
+    /// Milliseconds between cursor-trail samples while the cursor moves
+    /// (0 = no trail)
+    pub cursor_trail_interval_ms: u64,
+
+    /// Only report cursor moves within this many pixels of the character
+    /// (0 = report every move)
+    pub attention_radius: u32,
+
+    /// Snap cursor reports within this many pixels of a screen edge onto
+    /// the edge and flag them `at_edge` (0 = off)
+    pub edge_dead_zone: u32,
+
+    /// Minimum milliseconds between two activity events (0 = one per input)
+    pub activity_interval_ms: u64,
+
+    /// Snap the character to a monitor edge when it is dropped within this
+    /// many pixels of it (0 = no snapping)
+    pub snap_threshold: u32,
+
+    /// Which edges snap: any of "left", "right", "top", "bottom"
+    pub snap_edges: Vec<String>,
+
+    /// Seconds without input before the character falls asleep (0 = never)
+    pub idle_timeout_seconds: u64,
+
+    /// Linux: milliseconds the input loop waits for input right after
+    /// activity before waking anyway (for heartbeats, idle checks, ...)
+    pub poll_timeout_ms: u64,
+
+    /// Linux: the longest that wait grows to while the user is away
+    pub max_poll_timeout_ms: u64,
+
+    /// Include the actual key in KeyDown/KeyUp events. Off by default so
+    /// nothing the user types leaves the input thread unless they opt in.
+    pub emit_keystrokes: bool,
+
+    /// Linux only: stop shortcut keys from also reaching the focused app.
+    /// Grabs the keyboards and re-injects every other key through uinput,
+    /// so it needs write access to /dev/uinput. Off by default.
+    pub consume_shortcuts: bool,
+
+    /// Keep the most recent emitted events in memory so `recent_events`
+    /// can return a trace for bug reports. Off by default to avoid the
+    /// per-event copy.
+    pub debug_events: bool,
+
+    /// Allow the `trigger_shortcut` command in release builds (it always
+    /// works in debug builds). Off by default so nothing else running in
+    /// the webview can fire shortcuts.
+    pub simulated_shortcuts: bool,
+
+    /// Let clicks pass through the character to whatever is below it.
+    /// Set by the `set_click_through` command.
+    pub click_through: bool,
+
+    /// Corner the character is pinned to ("top-left", "top-right",
+    /// "bottom-left" or "bottom-right"), set by `set_anchor`. Null leaves
+    /// the window wherever it was dragged.
+    pub anchor: Option<String>,
+
+    /// Whether input monitoring was paused (`toggle_pause`) when the app
+    /// last ran. Restored at startup.
+    pub paused: bool,
+
+    /// Any other keys, preserved as-is
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            theme: "fox".to_string(),
+            gemini_api_key: String::new(),
+            shortcuts: ShortcutBinding::defaults(),
+            frontend_only_actions: Vec::new(),
+            app_profiles: Vec::new(),
+            shortcut_key_mode: "physical".to_string(),
+            startup_cursor_mode: "jump".to_string(),
+            sensitivity: 1.0,
+            cursor_smoothing: false,
+            cursor_velocity: false,
+            cursor_prediction_ms: 0,
+            max_delta_per_event: DEFAULT_MAX_DELTA_PER_EVENT as u32,
+            cursor_emit_interval_ms: DEFAULT_CURSOR_EMIT_INTERVAL_MS,
+            cursor_trail_interval_ms: 0,
+            attention_radius: 0,
+            edge_dead_zone: 0,
+            activity_interval_ms: DEFAULT_ACTIVITY_INTERVAL_MS,
+            snap_threshold: 0,
+            snap_edges: ["left", "right", "top", "bottom"]
+                .map(String::from)
+                .to_vec(),
+            idle_timeout_seconds: DEFAULT_IDLE_TIMEOUT_SECS,
+            poll_timeout_ms: DEFAULT_POLL_TIMEOUT_MS,
+            max_poll_timeout_ms: DEFAULT_MAX_POLL_TIMEOUT_MS,
+            emit_keystrokes: false,
+            consume_shortcuts: false,
+            debug_events: false,
+            simulated_shortcuts: false,
+            click_through: false,
+            anchor: None,
+            paused: false,
+            extra: serde_json::Map::new(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// Resolves the config.json path in the platform-specific config dir
+    pub fn path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+        app_handle
+            .path()
+            .app_config_dir()
+            .map(|dir| dir.join("config.json"))
+            .map_err(|e| format!("Could not resolve config directory: {}", e))
+    }
+
+    /// Reads config.json, upgrading older shapes, or None if it is missing
+    /// or not JSON
+    pub fn load(app_handle: &AppHandle) -> Option<AppConfig> {
+        let config_path = Self::path(app_handle).ok()?;
+        let content = fs::read_to_string(&config_path).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(raw) => {
+                log::info!("[Config] Loaded from {:?}", config_path);
+                Some(Self::migrate(raw))
+            }
+            Err(e) => {
+                log::warn!("[Config] Ignoring malformed {:?}: {}", config_path, e);
+                None
+            }
+        }
+    }
+
+    /// Upgrades a config written by an older version, one version at a
+    /// time, then deserializes it. Files from a newer version are read
+    /// as-is: unknown keys land in `extra` and missing ones get defaults.
+    pub fn migrate(mut raw: Value) -> AppConfig {
+        let Some(fields) = raw.as_object_mut() else {
+            log::warn!("[Config] Ignoring config that is not a JSON object");
+            return AppConfig::default();
+        };
+
+        let mut version = fields
+            .get("version")
+            .and_then(Value::as_u64)
+            .map_or(0, |v| v.min(u32::MAX as u64) as u32);
+        if version > CONFIG_VERSION {
+            log::warn!(
+                "[Config] Config is version {}, newer than this build ({}); unknown settings are kept as-is",
+                version,
+                CONFIG_VERSION
+            );
+        }
+
+        while version < CONFIG_VERSION {
+            match version {
+                // 0 -> 1: the API key had other names, and shortcut
+                // bindings weren't stored yet
+                0 => {
+                    for legacy in LEGACY_API_KEY_NAMES {
+                        if let Some(key) = fields.remove(legacy) {
+                            fields.entry("geminiApiKey").or_insert(key);
+                        }
+                    }
+                    if !fields.contains_key("shortcutBindings") {
+                        let defaults = serde_json::to_value(ShortcutBinding::defaults())
+                            .unwrap_or_else(|_| Value::Array(Vec::new()));
+                        fields.insert("shortcutBindings".to_string(), defaults);
+                    }
+                }
+                _ => unreachable!("no migration from config version {}", version),
+            }
+            version += 1;
+            log::info!("[Config] Migrated config to version {}", version);
+        }
+        fields.insert("version".to_string(), Value::from(version));
+
+        serde_json::from_value(raw).unwrap_or_else(|e| {
+            log::warn!("[Config] Ignoring config with invalid settings: {}", e);
+            AppConfig::default()
+        })
+    }
+
+    /// Writes the config as pretty-printed JSON, creating the directory
+    pub fn save(&self, app_handle: &AppHandle) -> Result<(), String> {
+        let config_path = Self::path(app_handle)?;
+
+        // Ensure the parent directory exists
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Could not create {:?}: {}", parent, e))?;
+        }
+
+        // Whatever shape this was read from, it is written in this one
+        let current = AppConfig {
+            version: CONFIG_VERSION,
+            ..self.clone()
+        };
+        let json = serde_json::to_string_pretty(&current).map_err(|e| e.to_string())?;
+        fs::write(&config_path, json)
+            .map_err(|e| format!("Could not write {:?}: {}", config_path, e))?;
+        log::info!("[Config] Saved to {:?}", config_path);
+        Ok(())
+    }
+
+    /// Copies the settings the input thread uses into its state
+    pub fn apply(&self, state: &mut InputState) {
+        state.shortcuts = self.shortcut_bindings();
+        state.frontend_only_actions = self.frontend_only_actions.clone();
+        state.app_profiles = self.app_profiles.clone();
+        state.shortcut_key_mode = ShortcutKeyMode::from_name(&self.shortcut_key_mode)
+            .unwrap_or_else(|| {
+                log::warn!(
+                    "[Config] Unknown shortcutKeyMode {:?}, using physical",
+                    self.shortcut_key_mode
+                );
+                ShortcutKeyMode::Physical
+            });
+        state.startup_cursor_mode = StartupCursorMode::from_name(&self.startup_cursor_mode)
+            .unwrap_or_else(|| {
+                log::warn!(
+                    "[Config] Unknown startupCursorMode {:?}, using jump",
+                    self.startup_cursor_mode
+                );
+                StartupCursorMode::Jump
+            });
+        state.emit_keystrokes = self.emit_keystrokes;
+        state.consume_shortcuts = self.consume_shortcuts;
+        state.click_through = self.click_through;
+        state.window_anchor = self
+            .anchor
+            .as_deref()
+            .and_then(Anchor::from_name)
+            .filter(Anchor::is_corner);
+        state.simulated_shortcuts = self.simulated_shortcuts;
+        state.idle_timeout = Duration::from_secs(self.idle_timeout_seconds);
+        // Zero would make the loop spin; poll() takes at most u16 ms
+        state.poll_timeout = Duration::from_millis(self.poll_timeout_ms.clamp(1, u16::MAX as u64));
+        state.max_poll_timeout =
+            Duration::from_millis(self.max_poll_timeout_ms.clamp(1, u16::MAX as u64));
+        state.attention_radius = self.attention_radius;
+        state.edge_dead_zone = self.edge_dead_zone;
+        state.snap_threshold = self.snap_threshold;
+        state.snap_edges = SnapEdges::from_names(&self.snap_edges);
+        state.sensitivity = if self.sensitivity.is_finite() && self.sensitivity > 0.0 {
+            self.sensitivity
+        } else {
+            1.0
+        };
+        state.max_delta_per_event = self.max_delta_per_event.min(i32::MAX as u32) as i32;
+        state.set_cursor_smoothing(self.cursor_smoothing);
+        state.cursor_velocity = self.cursor_velocity;
+        state.cursor_prediction = Duration::from_millis(self.cursor_prediction_ms);
+        state.cursor_emit_interval = Duration::from_millis(self.cursor_emit_interval_ms);
+        state.cursor_trail_interval = Duration::from_millis(self.cursor_trail_interval_ms);
+    }
+
+    /// Copies the settings that live on SharedState itself, because they
+    /// are read while input_state is locked
+    pub fn apply_shared(&self, state: &SharedState) {
+        state.set_debug_events(self.debug_events);
+        state.set_activity_interval(Duration::from_millis(self.activity_interval_ms));
+    }
+
+    /// The shortcut bindings to use: the configured ones, minus any naming
+    /// an unknown key or modifier, or the defaults if none are configured
+    pub fn shortcut_bindings(&self) -> Vec<ShortcutBinding> {
+        if self.shortcuts.is_empty() {
+            return ShortcutBinding::defaults();
+        }
+        self.shortcuts
+            .iter()
+            .filter(|binding| {
+                let valid = binding.is_valid();
+                if !valid {
+                    log::warn!("[Config] Ignoring invalid shortcut binding: {:?}", binding);
+                }
+                valid
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn unversioned_config_is_migrated() {
+        let config = AppConfig::migrate(json!({
+            "theme": "cat",
+            "apiKey": "secret",
+            "characterName": "Mia",
+        }));
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.theme, "cat");
+        assert_eq!(config.gemini_api_key, "secret");
+        assert_eq!(config.shortcuts, ShortcutBinding::defaults());
+        // Frontend-owned keys survive, legacy ones don't linger
+        assert_eq!(config.extra.get("characterName"), Some(&json!("Mia")));
+        assert!(!config.extra.contains_key("apiKey"));
+
+        // A current key wins over a legacy one
+        let config = AppConfig::migrate(json!({
+            "geminiApiKey": "new",
+            "gemini_api_key": "old",
+        }));
+        assert_eq!(config.gemini_api_key, "new");
+    }
+
+    #[test]
+    fn newer_config_is_read_as_is() {
+        let config = AppConfig::migrate(json!({
+            "version": CONFIG_VERSION + 1,
+            "theme": "cat",
+            "futureSetting": true,
+        }));
+        assert_eq!(config.version, CONFIG_VERSION + 1);
+        assert_eq!(config.theme, "cat");
+        assert_eq!(config.extra.get("futureSetting"), Some(&json!(true)));
+    }
+
+    #[test]
+    fn startup_cursor_mode_is_applied() {
+        let mut state = InputState::new(1920, 1080);
+        let mut config = AppConfig::default();
+        config.apply(&mut state);
+        assert_eq!(state.startup_cursor_mode, StartupCursorMode::Jump);
+
+        config.startup_cursor_mode = "Suppress".to_string();
+        config.apply(&mut state);
+        assert_eq!(state.startup_cursor_mode, StartupCursorMode::Suppress);
+
+        // Unknown names fall back to the default
+        config.startup_cursor_mode = "teleport".to_string();
+        config.apply(&mut state);
+        assert_eq!(state.startup_cursor_mode, StartupCursorMode::Jump);
+    }
+}
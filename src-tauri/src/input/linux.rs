@@ -1,29 +1,79 @@
 #[cfg(target_os = "linux")]
-use crate::shared::{InputState, KeyCode, OutputEvent, SharedState};
+use crate::shared::{
+    DeviceInfo, EventRateLimiter, EventSink, InputState, KeyCode, Monitors, OutputEvent, Rect,
+    SharedState, CURSOR_TICK, MOUSE_BUTTON_MIDDLE, MOUSE_BUTTON_RIGHT,
+};
 #[cfg(target_os = "linux")]
-use evdev::{Device, InputEventKind, Key, RelativeAxisType};
+use evdev::uinput::{VirtualDevice, VirtualDeviceBuilder};
+#[cfg(target_os = "linux")]
+use evdev::{
+    AbsoluteAxisType, AttributeSet, Device, InputEvent, InputEventKind, Key, LedType, PropType,
+    RelativeAxisType, Synchronization,
+};
 #[cfg(target_os = "linux")]
 use nix::libc;
 #[cfg(target_os = "linux")]
 use nix::poll::{poll, PollFd, PollFlags};
 #[cfg(target_os = "linux")]
+use std::collections::{HashMap, HashSet};
+#[cfg(target_os = "linux")]
 use std::fs;
 #[cfg(target_os = "linux")]
-use std::io::Read;
+use std::io::{Read, Write};
 #[cfg(target_os = "linux")]
 use std::os::fd::{AsRawFd, BorrowedFd};
 #[cfg(target_os = "linux")]
-use std::path::Path;
+use std::os::unix::net::UnixStream;
+#[cfg(target_os = "linux")]
+use std::path::{Path, PathBuf};
+#[cfg(target_os = "linux")]
+use std::sync::{Arc, Mutex, OnceLock};
+#[cfg(target_os = "linux")]
+use std::time::{Duration, Instant, SystemTime};
+#[cfg(target_os = "linux")]
+use wayland_client::protocol::{wl_output, wl_registry};
+#[cfg(target_os = "linux")]
+use wayland_client::{Connection, Dispatch, QueueHandle, WEnum};
+#[cfg(target_os = "linux")]
+use x11rb::connection::Connection as _;
 #[cfg(target_os = "linux")]
-use std::sync::Arc;
+use x11rb::protocol::xproto::{self, ConnectionExt as _};
 #[cfg(target_os = "linux")]
-use tauri::{AppHandle, Emitter};
+use x11rb::rust_connection::RustConnection;
+
+#[cfg(all(target_os = "linux", feature = "touchpad"))]
+use input::event::gesture::{
+    GestureEvent, GestureEventCoordinates, GesturePinchEvent, GesturePinchEventTrait,
+    GestureSwipeEvent,
+};
+#[cfg(all(target_os = "linux", feature = "touchpad"))]
+use input::event::pointer::{Axis, ButtonState, PointerEvent, PointerScrollEvent};
+#[cfg(all(target_os = "linux", feature = "touchpad"))]
+use input::{Event, Libinput, LibinputInterface};
+#[cfg(all(target_os = "linux", feature = "touchpad"))]
+use std::os::fd::OwnedFd;
+#[cfg(all(target_os = "linux", feature = "touchpad"))]
+use std::os::unix::fs::OpenOptionsExt;
 
 #[cfg(target_os = "linux")]
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum DeviceType {
     Mouse,
     Keyboard,
+    /// Touchscreen or drawing tablet reporting absolute X/Y positions
+    AbsPointer,
+}
+
+#[cfg(target_os = "linux")]
+impl DeviceType {
+    /// Name used for DeviceInfo::kind
+    fn kind(self) -> &'static str {
+        match self {
+            DeviceType::Mouse => "mouse",
+            DeviceType::Keyboard => "keyboard",
+            DeviceType::AbsPointer => "absolute-pointer",
+        }
+    }
 }
 
 #[cfg(target_os = "linux")]
@@ -31,48 +81,666 @@ struct OpenDevice {
     device: Device,
     device_type: DeviceType,
     path: String,
+    /// Name the kernel reports for the device, e.g. "Logitech G305"
+    name: String,
+    /// Whether the name marks it as a software device (see VIRTUAL_DEVICE_PATTERNS)
+    is_virtual: bool,
+    rate_limiter: EventRateLimiter,
+    /// Axis ranges and last raw position, for AbsPointer devices only
+    abs_mapping: Option<AbsMapping>,
+    /// Battery level in the last DeviceInfo event (None until one is sent)
+    reported_battery: Option<Option<u8>>,
+    /// Whether we hold an exclusive grab on it (see update_grab)
+    grabbed: bool,
+    /// Set when grabbing it failed, so it isn't retried on every iteration
+    grab_failed: bool,
+    /// Hi-res wheel units short of a whole notch, carried between frames
+    scroll_remainder: (i32, i32),
+    /// Set after a gap in reading: motion stamped before it is dropped from
+    /// the next read (see STALE_MOTION_GAP)
+    stale_before: Option<SystemTime>,
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for OpenDevice {
+    fn drop(&mut self) {
+        // Closing the fd releases the grab anyway; this makes it explicit
+        // for every way the input loop can end, including a panic
+        if self.grabbed {
+            let _ = self.device.ungrab();
+        }
+    }
+}
+
+/// Range of one absolute axis, as reported by EVIOCGABS
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy)]
+struct AbsAxisRange {
+    minimum: i32,
+    maximum: i32,
+    /// Units per millimetre (0 if the device doesn't report it)
+    resolution: i32,
+}
+
+/// Maps an absolute pointer's raw axis values onto the screen
+#[cfg(target_os = "linux")]
+struct AbsMapping {
+    x: AbsAxisRange,
+    y: AbsAxisRange,
+    raw_x: i32,
+    raw_y: i32,
+}
+
+#[cfg(target_os = "linux")]
+impl AbsMapping {
+    /// Reads the X/Y axis ranges and current position from the device
+    fn from_device(device: &Device) -> Option<Self> {
+        let abs_state = device.get_abs_state().ok()?;
+        let x = abs_state[AbsoluteAxisType::ABS_X.0 as usize];
+        let y = abs_state[AbsoluteAxisType::ABS_Y.0 as usize];
+        if x.maximum <= x.minimum || y.maximum <= y.minimum {
+            return None;
+        }
+        Some(Self {
+            x: AbsAxisRange {
+                minimum: x.minimum,
+                maximum: x.maximum,
+                resolution: x.resolution,
+            },
+            y: AbsAxisRange {
+                minimum: y.minimum,
+                maximum: y.maximum,
+                resolution: y.resolution,
+            },
+            raw_x: x.value,
+            raw_y: y.value,
+        })
+    }
+
+    /// Scales the last raw position into screen coordinates.
+    ///
+    /// When both axes report a resolution, the device's physical aspect
+    /// ratio is preserved by mapping only the centred part of the surface
+    /// that matches the screen's shape (like a tablet's "force proportions"
+    /// mode); positions outside it clamp to the screen edge.
+    fn to_screen(&self, screen_width: i32, screen_height: i32) -> (i32, i32) {
+        let mut span_x = (self.x.maximum - self.x.minimum) as f64;
+        let mut span_y = (self.y.maximum - self.y.minimum) as f64;
+
+        if self.x.resolution > 0 && self.y.resolution > 0 {
+            let width_mm = span_x / self.x.resolution as f64;
+            let height_mm = span_y / self.y.resolution as f64;
+            let screen_aspect = screen_width as f64 / screen_height as f64;
+            if width_mm / height_mm > screen_aspect {
+                span_x = height_mm * screen_aspect * self.x.resolution as f64;
+            } else {
+                span_y = width_mm / screen_aspect * self.y.resolution as f64;
+            }
+        }
+
+        let start_x =
+            self.x.minimum as f64 + ((self.x.maximum - self.x.minimum) as f64 - span_x) / 2.0;
+        let start_y =
+            self.y.minimum as f64 + ((self.y.maximum - self.y.minimum) as f64 - span_y) / 2.0;
+
+        let x = (self.raw_x as f64 - start_x) / span_x * screen_width as f64;
+        let y = (self.raw_y as f64 - start_y) / span_y * screen_height as f64;
+        (
+            (x as i32).clamp(0, screen_width - 1),
+            (y as i32).clamp(0, screen_height - 1),
+        )
+    }
 }
 
+/// Screen size used when no backend reports a layout
+#[cfg(target_os = "linux")]
+const FALLBACK_SCREEN_SIZE: (i32, i32) = (1920, 1080);
+
 #[cfg(target_os = "linux")]
 pub fn detect_screen_size() -> (i32, i32) {
-    if let Ok(output) = std::process::Command::new("wlr-randr").output() {
-        if let Some(size) = parse_randr_output(&String::from_utf8_lossy(&output.stdout)) {
-            return size;
+    detect_layout()
+        .as_deref()
+        .and_then(primary_monitor)
+        .map_or(FALLBACK_SCREEN_SIZE, |m| (m.width, m.height))
+}
+
+#[cfg(target_os = "linux")]
+pub fn detect_monitors() -> Monitors {
+    match detect_layout() {
+        Some(monitors) => Monitors {
+            rects: monitors.iter().map(Monitor::rect).collect(),
+        },
+        None => Monitors::single(FALLBACK_SCREEN_SIZE.0, FALLBACK_SCREEN_SIZE.1),
+    }
+}
+
+/// A source of the monitor layout. Autodetection tries them in declaration
+/// order; FOXY_SCREEN_BACKEND forces one, to test a specific path or to work
+/// around one that reports the wrong layout.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScreenBackend {
+    /// SCREEN_WIDTH/SCREEN_HEIGHT as a single monitor, when both are set
+    Env,
+    /// The compositor's wl_output globals
+    Wayland,
+    /// Parsed `wlr-randr` or `xrandr` output
+    Randr,
+    /// The X server's root window size, as a single monitor
+    X11,
+}
+
+#[cfg(target_os = "linux")]
+impl ScreenBackend {
+    const AUTODETECT: [ScreenBackend; 4] = [Self::Env, Self::Wayland, Self::Randr, Self::X11];
+
+    /// Parses a FOXY_SCREEN_BACKEND value
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "env" => Some(Self::Env),
+            "wayland" => Some(Self::Wayland),
+            "randr" | "xrandr" | "wlr-randr" => Some(Self::Randr),
+            "x11" | "x11query" => Some(Self::X11),
+            _ => None,
+        }
+    }
+
+    /// The backend FOXY_SCREEN_BACKEND asks for, if it names a known one
+    fn forced() -> Option<Self> {
+        let name = std::env::var("FOXY_SCREEN_BACKEND").ok()?;
+        let backend = Self::from_name(&name);
+        if backend.is_none() {
+            log::warn!("[Input] Ignoring unknown FOXY_SCREEN_BACKEND={:?}", name);
+        }
+        backend
+    }
+
+    /// The monitors this backend reports, or None if it has none
+    fn monitors(self) -> Option<Vec<Monitor>> {
+        let monitors = match self {
+            Self::Env => env_screen_size().map(|(w, h)| vec![Monitor::single(w, h)])?,
+            Self::Wayland => wayland_outputs()?
+                .iter()
+                .map(|o| Monitor {
+                    x: o.x,
+                    y: o.y,
+                    width: o.width,
+                    height: o.height,
+                    primary: false,
+                    scale: o.scale as f64,
+                })
+                .collect(),
+            Self::Randr => randr_monitors(),
+            Self::X11 => x11_screen_size().map(|(w, h)| vec![Monitor::single(w, h)])?,
+        };
+        (!monitors.is_empty()).then_some(monitors)
+    }
+}
+
+/// How many physical pixels make up one logical pixel of the desktop, for
+/// Ready: GDK_SCALE when set, else the primary monitor's scale (fractional
+/// from wlr-randr, whole numbers from wl_output, 1.0 from xrandr and X11)
+#[cfg(target_os = "linux")]
+fn detect_scale_factor() -> f64 {
+    let gdk_scale = std::env::var("GDK_SCALE")
+        .ok()
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|&scale| scale.is_finite() && scale > 0.0);
+    if let Some(scale) = gdk_scale {
+        return scale;
+    }
+    detect_layout()
+        .as_deref()
+        .and_then(primary_monitor)
+        .map_or(1.0, |monitor| monitor.scale)
+}
+
+/// The monitor layout from the forced backend, else from the first backend
+/// that reports one
+#[cfg(target_os = "linux")]
+fn detect_layout() -> Option<Vec<Monitor>> {
+    if let Some(backend) = ScreenBackend::forced() {
+        let monitors = backend.monitors();
+        if monitors.is_none() {
+            log::warn!(
+                "[Input] Forced screen backend {:?} found no monitors",
+                backend
+            );
+        }
+        return monitors;
+    }
+    ScreenBackend::AUTODETECT
+        .into_iter()
+        .find_map(ScreenBackend::monitors)
+}
+
+/// SCREEN_WIDTH x SCREEN_HEIGHT, if both are set to positive numbers
+#[cfg(target_os = "linux")]
+fn env_screen_size() -> Option<(i32, i32)> {
+    let read = |name| {
+        std::env::var(name)
+            .ok()?
+            .trim()
+            .parse::<i32>()
+            .ok()
+            .filter(|&v| v > 0)
+    };
+    Some((read("SCREEN_WIDTH")?, read("SCREEN_HEIGHT")?))
+}
+
+/// Size of the X server's default screen, spanning all monitors
+#[cfg(target_os = "linux")]
+fn x11_screen_size() -> Option<(i32, i32)> {
+    let (connection, screen) = x11rb::connect(None).ok()?;
+    let root = connection.setup().roots.get(screen)?;
+    Some((root.width_in_pixels as i32, root.height_in_pixels as i32))
+}
+
+// =============================================================================
+// Wayland Output Query
+// =============================================================================
+//
+// Reads monitor geometry straight from the compositor's wl_output globals.
+// Unlike wlr-randr this works on every compositor (GNOME included) and avoids
+// spawning a process. The result is cached because it needs a round-trip to
+// the compositor; call `invalidate_screen_cache` when the layout may have
+// changed (e.g. after resume).
+//
+// =============================================================================
+
+/// One monitor as advertised by a wl_output global
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Default)]
+struct WaylandOutput {
+    /// Position in the compositor's global space
+    x: i32,
+    y: i32,
+    /// Size of the current mode in pixels, after applying the output's rotation
+    width: i32,
+    height: i32,
+    /// Integer scale factor advertised by the compositor
+    scale: i32,
+    /// Whether the transform rotates the output by 90° or 270°
+    rotated: bool,
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+struct WaylandOutputState {
+    outputs: Vec<WaylandOutput>,
+}
+
+#[cfg(target_os = "linux")]
+impl Dispatch<wl_registry::WlRegistry, ()> for WaylandOutputState {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let wl_registry::Event::Global {
+            name,
+            interface,
+            version,
+        } = event
+        {
+            if interface == "wl_output" {
+                // The user data is this output's index in `state.outputs`
+                let index = state.outputs.len();
+                state.outputs.push(WaylandOutput {
+                    scale: 1,
+                    ..Default::default()
+                });
+                registry.bind::<wl_output::WlOutput, _, _>(name, version.min(2), qh, index);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Dispatch<wl_output::WlOutput, usize> for WaylandOutputState {
+    fn event(
+        state: &mut Self,
+        _: &wl_output::WlOutput,
+        event: wl_output::Event,
+        index: &usize,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(output) = state.outputs.get_mut(*index) else {
+            return;
+        };
+        match event {
+            wl_output::Event::Geometry {
+                x, y, transform, ..
+            } => {
+                output.x = x;
+                output.y = y;
+                output.rotated = matches!(
+                    transform,
+                    WEnum::Value(
+                        wl_output::Transform::_90
+                            | wl_output::Transform::_270
+                            | wl_output::Transform::Flipped90
+                            | wl_output::Transform::Flipped270
+                    )
+                );
+            }
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                ..
+            } if flags.contains(wl_output::Mode::Current) => {
+                output.width = width;
+                output.height = height;
+            }
+            wl_output::Event::Scale { factor } => output.scale = factor,
+            _ => {}
+        }
+    }
+}
+
+/// Pointer protocols a Wayland compositor advertises. None of them can
+/// replace /dev/input: relative-pointer and pointer-constraints only deliver
+/// motion while the pointer is over one of our own Wayland surfaces (and
+/// the window is an XWayland client, so there are none), and
+/// wlr-virtual-pointer can only inject input, not observe it. They are
+/// detected so the missing-permission error can say so rather than leave
+/// users looking for a setting that doesn't exist.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default)]
+struct WaylandPointerProtocols {
+    /// zwp_relative_pointer_manager_v1
+    relative_pointer: bool,
+    /// zwp_pointer_constraints_v1
+    pointer_constraints: bool,
+    /// zwlr_virtual_pointer_manager_v1 (wlroots-based compositors only)
+    virtual_pointer: bool,
+}
+
+#[cfg(target_os = "linux")]
+impl Dispatch<wl_registry::WlRegistry, ()> for WaylandPointerProtocols {
+    fn event(
+        state: &mut Self,
+        _: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_registry::Event::Global { interface, .. } = event {
+            match interface.as_str() {
+                "zwp_relative_pointer_manager_v1" => state.relative_pointer = true,
+                "zwp_pointer_constraints_v1" => state.pointer_constraints = true,
+                "zwlr_virtual_pointer_manager_v1" => state.virtual_pointer = true,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Which pointer protocols the compositor offers, or None without a
+/// Wayland session
+#[cfg(target_os = "linux")]
+fn wayland_pointer_protocols() -> Option<WaylandPointerProtocols> {
+    let connection = Connection::connect_to_env().ok()?;
+    let mut queue = connection.new_event_queue();
+    let _registry = connection.display().get_registry(&queue.handle(), ());
+    let mut protocols = WaylandPointerProtocols::default();
+    queue.roundtrip(&mut protocols).ok()?;
+    Some(protocols)
+}
+
+#[cfg(target_os = "linux")]
+static WAYLAND_OUTPUT_CACHE: Mutex<Option<Vec<WaylandOutput>>> = Mutex::new(None);
+
+/// Returns the compositor's outputs, or None if there is no Wayland session
+/// (or it reported no usable outputs). Cached after the first success.
+#[cfg(target_os = "linux")]
+fn wayland_outputs() -> Option<Vec<WaylandOutput>> {
+    let mut cache = WAYLAND_OUTPUT_CACHE.lock().unwrap();
+    if cache.is_none() {
+        *cache = query_wayland_outputs();
+    }
+    cache.clone()
+}
+
+/// Forgets the cached Wayland output layout so the next query re-reads it
+#[cfg(target_os = "linux")]
+fn invalidate_screen_cache() {
+    *WAYLAND_OUTPUT_CACHE.lock().unwrap() = None;
+}
+
+#[cfg(target_os = "linux")]
+fn query_wayland_outputs() -> Option<Vec<WaylandOutput>> {
+    let connection = Connection::connect_to_env().ok()?;
+    let mut queue = connection.new_event_queue();
+    let qh = queue.handle();
+    let _registry = connection.display().get_registry(&qh, ());
+
+    let mut state = WaylandOutputState::default();
+    // First round-trip delivers the globals, the second their properties
+    queue.roundtrip(&mut state).ok()?;
+    queue.roundtrip(&mut state).ok()?;
+
+    let outputs: Vec<WaylandOutput> = state
+        .outputs
+        .into_iter()
+        .filter(|o| o.width > 0 && o.height > 0)
+        .map(|mut o| {
+            if o.rotated {
+                std::mem::swap(&mut o.width, &mut o.height);
+            }
+            o
+        })
+        .collect();
+
+    if outputs.is_empty() {
+        None
+    } else {
+        log::info!("[Input] Wayland outputs: {:?}", outputs);
+        Some(outputs)
+    }
+}
+
+// =============================================================================
+// randr Output Parsing (X11 / wlroots Fallback)
+// =============================================================================
+//
+// When the Wayland query isn't available, monitors come from `wlr-randr` or
+// `xrandr`. The two print different formats:
+//
+//     HDMI-1 connected primary 2560x1440+0+0 (normal left ...) 597mm x 336mm
+//     DP-1 connected 1920x1080+2560+0 (normal left ...) 527mm x 296mm
+//
+//     DP-1 "Dell Inc. DELL U2719D"
+//       Enabled: yes
+//       Modes:
+//         2560x1440 px, 59.951000 Hz (preferred, current)
+//       Position: 0,0
+//
+// xrandr marks the primary output; wlr-randr has no such notion, so there
+// the monitor at the origin is treated as primary.
+//
+// =============================================================================
+
+/// One display as reported by xrandr or wlr-randr, in global coordinates
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Monitor {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    primary: bool,
+    /// Desktop scale factor (1.0 where the source doesn't report one)
+    scale: f64,
+}
+
+#[cfg(target_os = "linux")]
+impl Monitor {
+    /// A lone monitor at the origin
+    fn single(width: i32, height: i32) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width,
+            height,
+            primary: true,
+            scale: 1.0,
+        }
+    }
+
+    fn rect(&self) -> Rect {
+        Rect {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
         }
     }
-    if let Ok(output) = std::process::Command::new("xrandr").output() {
-        if let Some(size) = parse_randr_output(&String::from_utf8_lossy(&output.stdout)) {
-            return size;
+}
+
+/// Monitors from wlr-randr, or xrandr if that finds none
+#[cfg(target_os = "linux")]
+fn randr_monitors() -> Vec<Monitor> {
+    for command in ["wlr-randr", "xrandr"] {
+        if let Ok(output) = std::process::Command::new(command).output() {
+            let monitors = parse_randr_output(&String::from_utf8_lossy(&output.stdout));
+            if !monitors.is_empty() {
+                return monitors;
+            }
         }
     }
-    (1920, 1080)
+    Vec::new()
+}
+
+/// The monitor marked primary, else the one at the origin, else the first
+#[cfg(target_os = "linux")]
+fn primary_monitor(monitors: &[Monitor]) -> Option<&Monitor> {
+    monitors
+        .iter()
+        .find(|m| m.primary)
+        .or_else(|| monitors.iter().find(|m| m.x == 0 && m.y == 0))
+        .or_else(|| monitors.first())
+}
+
+/// A wlr-randr output block while it is being read
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+struct RandrBlock {
+    /// Size of the current mode
+    size: Option<(i32, i32)>,
+    /// From the "Position:" line
+    position: (i32, i32),
+    /// False once an "Enabled: no" line is seen
+    disabled: bool,
+    /// From the "Scale:" line
+    scale: Option<f64>,
+}
+
+#[cfg(target_os = "linux")]
+impl RandrBlock {
+    /// The monitor this block describes, if it is enabled and has a mode
+    fn monitor(&self) -> Option<Monitor> {
+        let (width, height) = self.size.filter(|_| !self.disabled)?;
+        Some(Monitor {
+            x: self.position.0,
+            y: self.position.1,
+            width,
+            height,
+            primary: false,
+            scale: self.scale.unwrap_or(1.0),
+        })
+    }
 }
 
+/// Parses every active monitor out of xrandr or wlr-randr output
 #[cfg(target_os = "linux")]
-fn parse_randr_output(output: &str) -> Option<(i32, i32)> {
+fn parse_randr_output(output: &str) -> Vec<Monitor> {
+    let mut monitors = Vec::new();
+    let mut block: Option<RandrBlock> = None;
+
     for line in output.lines() {
-        if line.contains(" connected") || line.contains("current") {
-            for word in line.split_whitespace() {
-                if let Some((w, h)) = word.split_once('x') {
-                    if let (Ok(width), Ok(height)) = (
-                        w.trim_matches(|c: char| !c.is_ascii_digit()).parse::<i32>(),
-                        h.trim_matches(|c: char| !c.is_ascii_digit()).parse::<i32>(),
-                    ) {
-                        if width > 0 && height > 0 {
-                            return Some((width, height));
-                        }
-                    }
+        let indented = line.starts_with(char::is_whitespace);
+        let trimmed = line.trim();
+
+        // xrandr: "<name> connected [primary] WxH+X+Y ..."
+        if !indented && line.contains(" connected") {
+            let geometry = line.split_whitespace().find_map(parse_randr_geometry);
+            if let Some((width, height, x, y)) = geometry {
+                monitors.push(Monitor {
+                    x,
+                    y,
+                    width,
+                    height,
+                    primary: line.split_whitespace().any(|word| word == "primary"),
+                    scale: 1.0,
+                });
+            }
+            continue;
+        }
+
+        // wlr-randr: an unindented output name starts a new block
+        if !indented && !trimmed.is_empty() {
+            monitors.extend(block.take().and_then(|b| b.monitor()));
+            if !line.starts_with("Screen ") && !line.contains("disconnected") {
+                block = Some(RandrBlock::default());
+            }
+            continue;
+        }
+
+        let Some(block) = block.as_mut() else {
+            continue;
+        };
+        if let Some(value) = trimmed.strip_prefix("Enabled:") {
+            block.disabled = value.trim() == "no";
+        } else if let Some(value) = trimmed.strip_prefix("Position:") {
+            if let Some((x, y)) = value.trim().split_once(',') {
+                if let (Ok(x), Ok(y)) = (x.trim().parse(), y.trim().parse()) {
+                    block.position = (x, y);
                 }
             }
+        } else if let Some(value) = trimmed.strip_prefix("Scale:") {
+            block.scale = value.trim().parse().ok().filter(|&scale: &f64| scale > 0.0);
+        } else if trimmed.contains("current") {
+            block.size = trimmed.split_whitespace().next().and_then(parse_randr_size);
         }
     }
-    None
+    monitors.extend(block.and_then(|b| b.monitor()));
+
+    monitors
+}
+
+/// Parses an xrandr geometry token, "WxH+X+Y", into (w, h, x, y)
+#[cfg(target_os = "linux")]
+fn parse_randr_geometry(word: &str) -> Option<(i32, i32, i32, i32)> {
+    let (size, offsets) = word.split_at(word.find(['+', '-'])?);
+    let (width, height) = parse_randr_size(size)?;
+
+    // Offsets keep their sign: "+1920+0", or "-1920+0" left of the origin
+    let (x, y) = offsets.split_at(offsets[1..].find(['+', '-'])? + 1);
+    Some((width, height, x.parse().ok()?, y.parse().ok()?))
+}
+
+/// Parses a mode size like "2560x1440" (also "1920x1080i") into (w, h)
+#[cfg(target_os = "linux")]
+fn parse_randr_size(word: &str) -> Option<(i32, i32)> {
+    let (w, h) = word.split_once('x')?;
+    let h = h.trim_end_matches(|c: char| !c.is_ascii_digit());
+    match (w.parse::<i32>(), h.parse::<i32>()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Some((width, height)),
+        _ => None,
+    }
 }
 
 #[cfg(target_os = "linux")]
 pub fn check_fullscreen() -> bool {
     let active_window_output = std::process::Command::new("xprop")
-        .args(&["-root", "_NET_ACTIVE_WINDOW"])
+        .args(["-root", "_NET_ACTIVE_WINDOW"])
         .output();
 
     let window_id = match active_window_output {
@@ -89,7 +757,7 @@ pub fn check_fullscreen() -> bool {
     };
 
     let state_output = std::process::Command::new("xprop")
-        .args(&["-id", &window_id, "_NET_WM_STATE"])
+        .args(["-id", &window_id, "_NET_WM_STATE"])
         .output();
 
     match state_output {
@@ -101,6 +769,106 @@ pub fn check_fullscreen() -> bool {
     }
 }
 
+/// WM_CLASS of the active X11 window. Under Wayland this only sees
+/// XWayland windows, like `check_fullscreen`.
+#[cfg(target_os = "linux")]
+pub fn foreground_app() -> Option<String> {
+    let (connection, screen) = x11rb::connect(None).ok()?;
+    let root = connection.setup().roots.get(screen)?.root;
+    let active_atom = connection
+        .intern_atom(true, b"_NET_ACTIVE_WINDOW")
+        .ok()?
+        .reply()
+        .ok()?
+        .atom;
+    let window = connection
+        .get_property(false, root, active_atom, xproto::AtomEnum::WINDOW, 0, 1)
+        .ok()?
+        .reply()
+        .ok()?
+        .value32()?
+        .next()
+        .filter(|&window| window != 0)?;
+    let class = connection
+        .get_property(
+            false,
+            window,
+            xproto::AtomEnum::WM_CLASS,
+            xproto::AtomEnum::STRING,
+            0,
+            256,
+        )
+        .ok()?
+        .reply()
+        .ok()?;
+    parse_wm_class(&class.value)
+}
+
+/// The class half of a WM_CLASS value ("instance\0class\0")
+#[cfg(target_os = "linux")]
+fn parse_wm_class(value: &[u8]) -> Option<String> {
+    value
+        .split(|&byte| byte == 0)
+        .filter(|part| !part.is_empty())
+        .nth(1)
+        .map(|class| String::from_utf8_lossy(class).into_owned())
+}
+
+/// Maps each physical letter/digit key to the one the X11 keymap types
+/// with it. evdev key codes are X keycodes minus 8. Empty without an X
+/// server (XWayland mirrors the compositor's layout).
+#[cfg(target_os = "linux")]
+pub fn keyboard_layout() -> HashMap<KeyCode, KeyCode> {
+    let mut layout = HashMap::new();
+    let Ok((connection, _)) = x11rb::connect(None) else {
+        return layout;
+    };
+    let setup = connection.setup();
+    let (min, max) = (setup.min_keycode, setup.max_keycode);
+    let Some(mapping) = connection
+        .get_keyboard_mapping(min, max - min + 1)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+    else {
+        return layout;
+    };
+
+    let per_keycode = mapping.keysyms_per_keycode.max(1) as usize;
+    for (index, keysyms) in mapping.keysyms.chunks(per_keycode).enumerate() {
+        let evdev_code = (min as usize + index).saturating_sub(8) as u16;
+        let Some(physical) = map_key_code(Key::new(evdev_code)) else {
+            continue;
+        };
+        if let (true, Some(logical)) = (physical.is_character(), keysym_key(keysyms)) {
+            layout.insert(physical, logical);
+        }
+    }
+    layout
+}
+
+/// The letter or digit a keycode types, from its keysyms (unshifted, then
+/// shifted: AZERTY types digits with Shift)
+#[cfg(target_os = "linux")]
+fn keysym_key(keysyms: &[u32]) -> Option<KeyCode> {
+    keysyms
+        .iter()
+        .take(2)
+        .filter_map(|&keysym| char::from_u32(keysym))
+        .filter(char::is_ascii_alphanumeric)
+        .find_map(KeyCode::from_char)
+}
+
+#[cfg(target_os = "linux")]
+pub fn has_input_permission() -> bool {
+    let Ok(entries) = fs::read_dir("/dev/input") else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        entry.file_name().to_string_lossy().starts_with("event")
+            && fs::File::open(entry.path()).is_ok()
+    })
+}
+
 #[cfg(target_os = "linux")]
 fn map_key_code(key: Key) -> Option<KeyCode> {
     match key {
@@ -112,11 +880,56 @@ fn map_key_code(key: Key) -> Option<KeyCode> {
         Key::KEY_RIGHTALT => Some(KeyCode::RightAlt),
         Key::KEY_LEFTMETA => Some(KeyCode::LeftMeta),
         Key::KEY_RIGHTMETA => Some(KeyCode::RightMeta),
-        Key::KEY_F => Some(KeyCode::F),
+        Key::KEY_A => Some(KeyCode::A),
+        Key::KEY_B => Some(KeyCode::B),
+        Key::KEY_C => Some(KeyCode::C),
         Key::KEY_D => Some(KeyCode::D),
+        Key::KEY_E => Some(KeyCode::E),
+        Key::KEY_F => Some(KeyCode::F),
+        Key::KEY_G => Some(KeyCode::G),
+        Key::KEY_H => Some(KeyCode::H),
+        Key::KEY_I => Some(KeyCode::I),
+        Key::KEY_J => Some(KeyCode::J),
+        Key::KEY_K => Some(KeyCode::K),
+        Key::KEY_L => Some(KeyCode::L),
+        Key::KEY_M => Some(KeyCode::M),
+        Key::KEY_N => Some(KeyCode::N),
+        Key::KEY_O => Some(KeyCode::O),
+        Key::KEY_P => Some(KeyCode::P),
+        Key::KEY_Q => Some(KeyCode::Q),
+        Key::KEY_R => Some(KeyCode::R),
         Key::KEY_S => Some(KeyCode::S),
-        Key::KEY_C => Some(KeyCode::C),
-        Key::KEY_A => Some(KeyCode::A),
+        Key::KEY_T => Some(KeyCode::T),
+        Key::KEY_U => Some(KeyCode::U),
+        Key::KEY_V => Some(KeyCode::V),
+        Key::KEY_W => Some(KeyCode::W),
+        Key::KEY_X => Some(KeyCode::X),
+        Key::KEY_Y => Some(KeyCode::Y),
+        Key::KEY_Z => Some(KeyCode::Z),
+        Key::KEY_0 => Some(KeyCode::Digit0),
+        Key::KEY_1 => Some(KeyCode::Digit1),
+        Key::KEY_2 => Some(KeyCode::Digit2),
+        Key::KEY_3 => Some(KeyCode::Digit3),
+        Key::KEY_4 => Some(KeyCode::Digit4),
+        Key::KEY_5 => Some(KeyCode::Digit5),
+        Key::KEY_6 => Some(KeyCode::Digit6),
+        Key::KEY_7 => Some(KeyCode::Digit7),
+        Key::KEY_8 => Some(KeyCode::Digit8),
+        Key::KEY_9 => Some(KeyCode::Digit9),
+        Key::KEY_F1 => Some(KeyCode::F1),
+        Key::KEY_F2 => Some(KeyCode::F2),
+        Key::KEY_F3 => Some(KeyCode::F3),
+        Key::KEY_F4 => Some(KeyCode::F4),
+        Key::KEY_F5 => Some(KeyCode::F5),
+        Key::KEY_F6 => Some(KeyCode::F6),
+        Key::KEY_F7 => Some(KeyCode::F7),
+        Key::KEY_F8 => Some(KeyCode::F8),
+        Key::KEY_F9 => Some(KeyCode::F9),
+        Key::KEY_F10 => Some(KeyCode::F10),
+        Key::KEY_F11 => Some(KeyCode::F11),
+        Key::KEY_F12 => Some(KeyCode::F12),
+        Key::KEY_CAPSLOCK => Some(KeyCode::CapsLock),
+        Key::KEY_NUMLOCK => Some(KeyCode::NumLock),
         _ => None,
     }
 }
@@ -124,205 +937,1696 @@ fn map_key_code(key: Key) -> Option<KeyCode> {
 #[cfg(target_os = "linux")]
 fn discover_devices() -> Vec<OpenDevice> {
     let mut devices = Vec::new();
-    let input_dir = Path::new("/dev/input");
 
-    println!("[Input] Scanning /dev/input/ for devices...");
-
-    let entries = match fs::read_dir(input_dir) {
-        Ok(entries) => entries,
-        Err(e) => {
-            eprintln!("[Input] Error reading /dev/input/: {}", e);
-            return devices;
-        }
-    };
+    log::info!("[Input] Scanning /dev/input/ for devices...");
 
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if let Some(name) = path.file_name() {
-            if !name.to_string_lossy().starts_with("event") {
-                continue;
+    for path in list_event_nodes() {
+        match probe_device(&path) {
+            Ok(Some(open_device)) => {
+                log::info!(
+                    "[Input] {:?}: VALID ({:?}: {})",
+                    path,
+                    open_device.device_type,
+                    open_device.name
+                );
+                devices.push(open_device);
+            }
+            Ok(None) => {
+                log::debug!("[Input] {:?}: IGNORED (Not Mouse/Keyboard)", path);
             }
-        }
-
-        print!("[Input] Checking {:?}... ", path);
-        match Device::open(&path) {
-            Ok(device) => match classify_device(&device) {
-                Some(dtype) => {
-                    println!("VALID ({:?})", dtype);
-                    devices.push(OpenDevice {
-                        device,
-                        device_type: dtype,
-                        path: path.to_string_lossy().to_string(),
-                    });
-                }
-                None => {
-                    println!("IGNORED (Not Mouse/Keyboard)");
-                }
-            },
             Err(e) => {
-                println!("FAILED to open: {}", e);
+                log::warn!("[Input] {:?}: FAILED to open: {}", path, e);
             }
         }
     }
+    drop_redundant_virtual(&mut devices);
     devices
 }
 
+/// The running input loop's devices, for list_devices. None while no loop
+/// runs.
 #[cfg(target_os = "linux")]
-fn classify_device(device: &Device) -> Option<DeviceType> {
-    let supported_keys = device.supported_keys();
-    let supported_axes = device.supported_relative_axes();
+static LOOP_DEVICES: Mutex<Option<Vec<DeviceInfo>>> = Mutex::new(None);
 
-    if let Some(axes) = supported_axes {
-        if axes.contains(RelativeAxisType::REL_X) && axes.contains(RelativeAxisType::REL_Y) {
-            return Some(DeviceType::Mouse);
-        }
-    }
+/// Records the input loop's device list for list_devices. A device that
+/// stops being readable is dropped from the loop, so the rest are readable.
+#[cfg(target_os = "linux")]
+fn publish_devices(devices: &[OpenDevice]) {
+    let snapshot = devices
+        .iter()
+        .map(|device| DeviceInfo {
+            path: device.path.clone(),
+            name: device.name.clone(),
+            kind: device.device_type.kind().to_string(),
+            readable: true,
+        })
+        .collect();
+    *LOOP_DEVICES.lock().unwrap_or_else(|e| e.into_inner()) = Some(snapshot);
+}
 
-    if let Some(keys) = supported_keys {
-        if keys.contains(Key::KEY_A) && keys.contains(Key::KEY_S) {
-            return Some(DeviceType::Keyboard);
+/// The devices the running input loop reads. Before it starts (or after it
+/// stops), opens every device it would use and probes it with a read.
+#[cfg(target_os = "linux")]
+pub fn list_devices() -> Vec<DeviceInfo> {
+    if let Some(devices) = LOOP_DEVICES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+    {
+        return devices;
+    }
+    let mut devices = Vec::new();
+    for path in list_event_nodes() {
+        if let Ok(Some(open_device)) = probe_device(&path) {
+            devices.push(open_device);
         }
     }
-    None
+    drop_redundant_virtual(&mut devices);
+    devices
+        .iter_mut()
+        .map(|device| DeviceInfo {
+            path: device.path.clone(),
+            name: device.name.clone(),
+            kind: device.device_type.kind().to_string(),
+            readable: is_readable(device),
+        })
+        .collect()
 }
 
+/// Paths of all /dev/input/event* nodes currently present
 #[cfg(target_os = "linux")]
-fn process_device_events(
-    open_device: &mut OpenDevice,
-    state: &mut InputState,
-    app_handle: &AppHandle,
-) {
-    let events: Vec<_> = match open_device.device.fetch_events() {
-        Ok(events) => events.collect(),
-        Err(e) if e.raw_os_error() == Some(libc::EAGAIN) => return,
+fn list_event_nodes() -> Vec<PathBuf> {
+    let entries = match fs::read_dir("/dev/input") {
+        Ok(entries) => entries,
         Err(e) => {
-            eprintln!("Error reading {}: {}", open_device.path, e);
-            return;
+            log::warn!("[Input] Error reading /dev/input/: {}", e);
+            return Vec::new();
         }
     };
 
-    let mut total_dx = 0;
-    let mut total_dy = 0;
-
-    for event in events {
-        match event.kind() {
-            InputEventKind::RelAxis(axis) => match axis {
-                RelativeAxisType::REL_X => total_dx += event.value(),
-                RelativeAxisType::REL_Y => total_dy += event.value(),
-                _ => {}
-            },
-            InputEventKind::Key(key) => {
-                let is_pressed = event.value() == 1;
-                let is_released = event.value() == 0;
-
-                // Modifiers
-                if let Some(shared_key) = map_key_code(key) {
-                    if is_pressed {
-                        state.held_modifiers.insert(shared_key);
-                    } else if is_released {
-                        state.held_modifiers.remove(&shared_key);
-                    }
-                    // Shortcuts
-                    if is_pressed {
-                        if let Some(shortcut) = state.check_shortcut(shared_key) {
-                            let _ = app_handle.emit(
-                                "shortcut",
-                                OutputEvent::Shortcut {
-                                    name: shortcut.to_string(),
-                                },
-                            );
-                        }
-                    }
-                }
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("event"))
+        })
+        .collect()
+}
 
-                if is_pressed {
-                    // Clicks
-                    match key {
-                        Key::BTN_LEFT => {
-                            let _ = app_handle.emit(
-                                "click",
-                                OutputEvent::Click {
-                                    button: "left".into(),
-                                    x: state.cursor_x,
-                                    y: state.cursor_y,
-                                },
-                            );
-                        }
-                        Key::BTN_RIGHT => {
-                            let _ = app_handle.emit(
-                                "click",
-                                OutputEvent::Click {
-                                    button: "right".into(),
-                                    x: state.cursor_x,
-                                    y: state.cursor_y,
-                                },
-                            );
-                        }
-                        Key::BTN_MIDDLE => {
-                            let _ = app_handle.emit(
-                                "click",
-                                OutputEvent::Click {
-                                    button: "middle".into(),
-                                    x: state.cursor_x,
-                                    y: state.cursor_y,
-                                },
-                            );
+/// Opens an event node, returning it if it's a device we track
+#[cfg(target_os = "linux")]
+fn probe_device(path: &Path) -> std::io::Result<Option<OpenDevice>> {
+    let device = Device::open(path)?;
+    // Our own re-injection keyboard: reading it would see every key twice
+    if device.name() == Some(FORWARD_KEYBOARD_NAME) {
+        return Ok(None);
+    }
+    let Some(device_type) = classify_device(&device) else {
+        return Ok(None);
+    };
+    let abs_mapping = if device_type == DeviceType::AbsPointer {
+        AbsMapping::from_device(&device)
+    } else {
+        None
+    };
+    let name = device.name().unwrap_or("Unknown device").to_string();
+    let is_virtual = is_virtual_device_name(&name);
+    Ok(Some(OpenDevice {
+        device,
+        device_type,
+        path: path.to_string_lossy().to_string(),
+        name,
+        is_virtual,
+        rate_limiter: EventRateLimiter::new(),
+        abs_mapping,
+        reported_battery: None,
+        grabbed: false,
+        grab_failed: false,
+        scroll_remainder: (0, 0),
+        stale_before: None,
+    }))
+}
+
+/// Case-insensitive name fragments of software devices that mirror input
+/// from real hardware (XWayland's pointer, virtual aggregates, uinput
+/// injectors). Reading one alongside the physical mouse counts every motion
+/// twice, so the cursor moves at double speed.
+#[cfg(target_os = "linux")]
+const VIRTUAL_DEVICE_PATTERNS: &[&str] = &[
+    "virtual",
+    "xwayland",
+    "uinput",
+    "ydotool",
+    "power button",
+    "sleep button",
+    "video bus",
+];
+
+#[cfg(target_os = "linux")]
+fn is_virtual_device_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    VIRTUAL_DEVICE_PATTERNS
+        .iter()
+        .any(|pattern| name.contains(pattern))
+}
+
+/// Closes virtual pointing devices while a physical one is open, returning
+/// their paths. Virtual pointers are still used when they're all there is
+/// (e.g. inside a VM). Keyboards are left alone: a duplicate key press is
+/// already ignored by the held-key tracking, and key remappers like keyd
+/// grab the physical keyboard so only their virtual one delivers keys.
+#[cfg(target_os = "linux")]
+fn drop_redundant_virtual<D: TrackedDevice>(devices: &mut Vec<D>) -> Vec<PathBuf> {
+    let is_pointer = |d: &D| d.device_type() != DeviceType::Keyboard;
+    if !devices.iter().any(|d| is_pointer(d) && !d.is_virtual()) {
+        return Vec::new();
+    }
+
+    let mut dropped = Vec::new();
+    devices.retain(|d| {
+        let redundant = is_pointer(d) && d.is_virtual();
+        if redundant {
+            log::info!(
+                "[Input] Skipping virtual device {} ({}): a physical pointer is present",
+                d.path(),
+                d.name()
+            );
+            dropped.push(PathBuf::from(d.path()));
+        }
+        !redundant
+    });
+    dropped
+}
+
+/// What the device bookkeeping (unplugging, hot-plug rescans, virtual
+/// pointer skipping) needs to know about an open device, so tests can
+/// drive it with fakes
+#[cfg(target_os = "linux")]
+trait TrackedDevice {
+    fn path(&self) -> &str;
+    fn name(&self) -> &str;
+    fn device_type(&self) -> DeviceType;
+    fn is_virtual(&self) -> bool;
+}
+
+#[cfg(target_os = "linux")]
+impl TrackedDevice for OpenDevice {
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn device_type(&self) -> DeviceType {
+        self.device_type
+    }
+
+    fn is_virtual(&self) -> bool {
+        self.is_virtual
+    }
+}
+
+/// Where rescans find and open devices: /dev/input, or a fake in tests
+#[cfg(target_os = "linux")]
+trait DeviceSource {
+    type Device: TrackedDevice;
+
+    /// Paths of the event nodes currently present
+    fn event_nodes(&self) -> Vec<PathBuf>;
+
+    /// Opens a node, returning it if it's a device we track
+    fn probe(&self, path: &Path) -> std::io::Result<Option<Self::Device>>;
+}
+
+/// The real /dev/input event nodes
+#[cfg(target_os = "linux")]
+struct EvdevSource;
+
+#[cfg(target_os = "linux")]
+impl DeviceSource for EvdevSource {
+    type Device = OpenDevice;
+
+    fn event_nodes(&self) -> Vec<PathBuf> {
+        list_event_nodes()
+    }
+
+    fn probe(&self, path: &Path) -> std::io::Result<Option<OpenDevice>> {
+        probe_device(path)
+    }
+}
+
+/// Drops the devices at the indices in `gone` (ascending), whose reads
+/// showed they were unplugged. Their nodes usually come back on reconnect
+/// (or wake), and the next rescan reopens them. Returns true if any were
+/// dropped.
+#[cfg(target_os = "linux")]
+fn drop_gone_devices<D: TrackedDevice>(
+    devices: &mut Vec<D>,
+    gone: &[usize],
+    ignored: &mut HashSet<PathBuf>,
+) -> bool {
+    for &i in gone.iter().rev() {
+        log::info!(
+            "[Input] Device removed: {}; will reopen it if it reconnects",
+            devices[i].path()
+        );
+        devices.remove(i);
+    }
+    if gone.is_empty() {
+        return false;
+    }
+    // A skipped virtual pointer may be the only one left now
+    ignored.clear();
+    true
+}
+
+/// How often /dev/input is rescanned for hot-plugged devices
+#[cfg(target_os = "linux")]
+const HOTPLUG_RESCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Diffs /dev/input against the open devices: opens nodes that appeared,
+/// drops devices whose node vanished. `ignored` remembers nodes already
+/// probed and rejected so they aren't reopened on every scan.
+/// Returns true if the device list changed.
+#[cfg(target_os = "linux")]
+fn rescan_devices<S: DeviceSource>(
+    source: &S,
+    devices: &mut Vec<S::Device>,
+    ignored: &mut HashSet<PathBuf>,
+) -> bool {
+    let present: HashSet<PathBuf> = source.event_nodes().into_iter().collect();
+    ignored.retain(|path| present.contains(path));
+
+    let before: Vec<String> = devices.iter().map(|d| d.path().to_string()).collect();
+    devices.retain(|d| {
+        let still_there = present.contains(Path::new(d.path()));
+        if !still_there {
+            log::info!("[Input] Device removed: {}", d.path());
+        }
+        still_there
+    });
+    if devices.len() != before.len() {
+        // A skipped virtual pointer may be the only one left now
+        ignored.clear();
+    }
+
+    for path in present {
+        if ignored.contains(&path) || devices.iter().any(|d| Path::new(d.path()) == path) {
+            continue;
+        }
+        match source.probe(&path) {
+            Ok(Some(open_device)) => {
+                log::info!(
+                    "[Input] Device added: {} ({:?}: {})",
+                    open_device.path(),
+                    open_device.device_type(),
+                    open_device.name()
+                );
+                devices.push(open_device);
+            }
+            // Permission errors won't fix themselves either; don't retry
+            Ok(None) | Err(_) => {
+                ignored.insert(path);
+            }
+        }
+    }
+    ignored.extend(drop_redundant_virtual(devices));
+
+    devices.len() != before.len()
+        || devices
+            .iter()
+            .zip(&before)
+            .any(|(d, path)| d.path() != path)
+}
+
+/// Battery percentage of a wireless device, read from the power_supply
+/// entry the kernel attaches to its HID device (a parent of the input node
+/// in sysfs). None if there is none, or anything along the way is missing.
+#[cfg(target_os = "linux")]
+fn read_battery(event_path: &str) -> Option<u8> {
+    let node = Path::new(event_path).file_name()?;
+    let mut dir = fs::canonicalize(Path::new("/sys/class/input").join(node).join("device")).ok()?;
+
+    // input device → HID device → bus device; receivers keep it one level up
+    for _ in 0..3 {
+        if let Ok(supplies) = fs::read_dir(dir.join("power_supply")) {
+            for supply in supplies.flatten() {
+                let capacity = fs::read_to_string(supply.path().join("capacity"));
+                if let Some(percent) = capacity.ok().and_then(|c| c.trim().parse::<u8>().ok()) {
+                    return Some(percent.min(100));
+                }
+            }
+        }
+        dir = dir.parent()?.to_path_buf();
+    }
+    None
+}
+
+/// Emits DeviceInfo for each device that hasn't been described yet or whose
+/// battery level changed since
+#[cfg(target_os = "linux")]
+fn emit_device_info(sink: &dyn EventSink, shared_state: &SharedState, devices: &mut [OpenDevice]) {
+    for device in devices {
+        let battery = read_battery(&device.path);
+        if device.reported_battery == Some(battery) {
+            continue;
+        }
+        device.reported_battery = Some(battery);
+        shared_state.emit(
+            sink,
+            "device-info",
+            OutputEvent::DeviceInfo {
+                name: device.name.clone(),
+                battery,
+            },
+        );
+    }
+}
+
+/// Names of the open devices, for the Ready/DevicesChanged events
+#[cfg(target_os = "linux")]
+fn device_names<'a>(devices: impl IntoIterator<Item = &'a OpenDevice>) -> Vec<String> {
+    devices.into_iter().map(|d| d.name.clone()).collect()
+}
+
+/// Counts (pointing devices, keyboards), including the /dev/input/mice fallback
+#[cfg(target_os = "linux")]
+fn count_devices<'a>(
+    devices: impl IntoIterator<Item = &'a OpenDevice>,
+    has_mice_file: bool,
+) -> (usize, usize) {
+    let mut mice_count = usize::from(has_mice_file);
+    let mut keyboards_count = 0;
+    for device in devices {
+        match device.device_type {
+            // Touchscreens and tablets count as pointing devices too
+            DeviceType::Mouse | DeviceType::AbsPointer => mice_count += 1,
+            DeviceType::Keyboard => keyboards_count += 1,
+        }
+    }
+    (mice_count, keyboards_count)
+}
+
+/// Whether a non-blocking read from the device succeeds. Opening a node
+/// doesn't guarantee we may read it. Any pending events are discarded,
+/// which is fine before Ready since nothing depends on them yet.
+#[cfg(target_os = "linux")]
+fn is_readable(open_device: &mut OpenDevice) -> bool {
+    let error = match open_device.device.fetch_events() {
+        Ok(_) => return true,
+        Err(e) => e,
+    };
+    if classify_read_error(&error) == ReadFailure::NoEvents {
+        return true;
+    }
+    log::warn!(
+        "[Input] {} was opened but can't be read: {}",
+        open_device.path,
+        error
+    );
+    false
+}
+
+#[cfg(target_os = "linux")]
+fn classify_device(device: &Device) -> Option<DeviceType> {
+    let supported_keys = device.supported_keys();
+    let supported_axes = device.supported_relative_axes();
+
+    if let Some(axes) = supported_axes {
+        if axes.contains(RelativeAxisType::REL_X) && axes.contains(RelativeAxisType::REL_Y) {
+            return Some(DeviceType::Mouse);
+        }
+    }
+
+    // Absolute X/Y alone also matches joysticks and touchpads, so require a
+    // direct-input surface (touchscreen, display tablet) or a pen tool
+    if let Some(axes) = device.supported_absolute_axes() {
+        let is_pen = supported_keys
+            .is_some_and(|keys| keys.contains(Key::BTN_TOOL_PEN) || keys.contains(Key::BTN_STYLUS));
+        if axes.contains(AbsoluteAxisType::ABS_X)
+            && axes.contains(AbsoluteAxisType::ABS_Y)
+            && (device.properties().contains(PropType::DIRECT) || is_pen)
+        {
+            return Some(DeviceType::AbsPointer);
+        }
+    }
+
+    if let Some(keys) = supported_keys {
+        if keys.contains(Key::KEY_A) && keys.contains(Key::KEY_S) {
+            return Some(DeviceType::Keyboard);
+        }
+    }
+    None
+}
+
+/// What a failed read from a device means for it
+#[cfg(target_os = "linux")]
+#[derive(Debug, PartialEq)]
+enum ReadFailure {
+    /// Nothing to read right now
+    NoEvents,
+    /// The fd is dead (device unplugged, or a wireless receiver went to
+    /// sleep) and will never deliver again; it must be dropped and reopened
+    Gone,
+    /// Anything else; logged, and the device is kept
+    Other,
+}
+
+#[cfg(target_os = "linux")]
+fn classify_read_error(error: &std::io::Error) -> ReadFailure {
+    match error.raw_os_error() {
+        Some(libc::EAGAIN) => ReadFailure::NoEvents,
+        Some(libc::ENODEV) | Some(libc::EBADF) => ReadFailure::Gone,
+        _ => ReadFailure::Other,
+    }
+}
+
+/// A gap this long in the poll loop (a stalled process, a lid closed just
+/// under the resume threshold) means the kernel buffered motion while we
+/// weren't reading. Replaying it would jerk the cursor, so the first read
+/// from each device afterwards drops it.
+#[cfg(target_os = "linux")]
+const STALE_MOTION_GAP: Duration = Duration::from_millis(500);
+
+/// Whether `event` is pointer motion or scrolling stamped before `cutoff`.
+/// Keys and buttons are never stale: dropping a release would leave them
+/// held.
+#[cfg(target_os = "linux")]
+fn is_stale_motion(event: &InputEvent, cutoff: SystemTime) -> bool {
+    matches!(
+        event.kind(),
+        InputEventKind::RelAxis(_) | InputEventKind::AbsAxis(_)
+    ) && event.timestamp() < cutoff
+}
+
+/// Thins a batch from a device over the event-rate cap down to its key
+/// releases, returning the relative motion it contained
+#[cfg(target_os = "linux")]
+fn drop_flooded_events(events: &mut Vec<InputEvent>) -> (i32, i32) {
+    let (dx, dy) = events
+        .iter()
+        .fold((0, 0), |(dx, dy), event| match event.kind() {
+            InputEventKind::RelAxis(RelativeAxisType::REL_X) => (dx + event.value(), dy),
+            InputEventKind::RelAxis(RelativeAxisType::REL_Y) => (dx, dy + event.value()),
+            _ => (dx, dy),
+        });
+    events.retain(|event| matches!(event.kind(), InputEventKind::Key(_)) && event.value() == 0);
+    (dx, dy)
+}
+
+#[cfg(target_os = "linux")]
+fn process_device_events(
+    open_device: &mut OpenDevice,
+    state: &mut InputState,
+    shared_state: &SharedState,
+    sink: &dyn EventSink,
+    forward: Option<&mut VirtualDevice>,
+) -> bool {
+    // A grabbed keyboard's keys only reach other apps if we pass them on
+    let mut forward = forward.filter(|_| open_device.grabbed);
+
+    let mut events: Vec<_> = match open_device.device.fetch_events() {
+        Ok(events) => events.collect(),
+        Err(e) => match classify_read_error(&e) {
+            ReadFailure::NoEvents => return true,
+            // Unplugged: the caller drops the device
+            ReadFailure::Gone => return false,
+            ReadFailure::Other => {
+                log::warn!("Error reading {}: {}", open_device.path, e);
+                return true;
+            }
+        },
+    };
+
+    // Only the first read after a gap; evdev stamps events with the wall
+    // clock, like the cutoff
+    if let Some(cutoff) = open_device.stale_before.take() {
+        let fetched = events.len();
+        events.retain(|event| !is_stale_motion(event, cutoff));
+        if events.len() < fetched {
+            log::info!(
+                "[Input] Discarded {} stale motion events from {}",
+                fetched - events.len(),
+                open_device.path
+            );
+        }
+    }
+
+    // Flood protection: drop this device's events while it's over the cap,
+    // but still fold its motion into the cursor so tracking stays coherent,
+    // and still apply key releases so nothing stays held.
+    let flooding = open_device.rate_limiter.record(
+        events.len() as u32,
+        state.max_device_events_per_sec,
+        Instant::now(),
+    );
+    if flooding {
+        if open_device.rate_limiter.should_warn() {
+            shared_state.emit_error(
+                sink,
+                format!(
+                    "{} sent more than {} events per second; ignoring it until it calms down",
+                    open_device.path, state.max_device_events_per_sec
+                ),
+            );
+        }
+        if let Some(forward) = forward.take() {
+            let keys: Vec<_> = events
+                .iter()
+                .filter(|event| matches!(event.kind(), InputEventKind::Key(_)))
+                .copied()
+                .collect();
+            forward_keys(forward, &keys);
+        }
+        let fetched = events.len();
+        let (dx, dy) = drop_flooded_events(&mut events);
+        state.apply_cursor_delta(dx, dy);
+        shared_state.count_flood_dropped((fetched - events.len()) as u64);
+    }
+
+    let mut frame = EventFrame {
+        hi_res_remainder: open_device.scroll_remainder,
+        ..EventFrame::default()
+    };
+    let mut pending_keys = Vec::new();
+    for event in events {
+        // A key that fires a shortcut is swallowed from press to release
+        let shortcut_key = match event.kind() {
+            InputEventKind::Key(key) => map_key_code(key),
+            _ => None,
+        };
+        let was_trigger = shortcut_key.is_some_and(|key| state.is_shortcut_trigger_held(key));
+
+        handle_event(
+            event.kind(),
+            event.value(),
+            &mut frame,
+            open_device.abs_mapping.as_mut(),
+            state,
+            shared_state,
+            sink,
+        );
+
+        if let Some(forward) = forward.as_deref_mut() {
+            match event.kind() {
+                InputEventKind::Key(_) => {
+                    let is_trigger =
+                        shortcut_key.is_some_and(|key| state.is_shortcut_trigger_held(key));
+                    if !was_trigger && !is_trigger {
+                        pending_keys.push(event);
+                    }
+                }
+                InputEventKind::Synchronization(Synchronization::SYN_REPORT) => {
+                    forward_keys(forward, &std::mem::take(&mut pending_keys));
+                }
+                _ => {}
+            }
+        }
+    }
+    if let Some(forward) = forward {
+        forward_keys(forward, &pending_keys);
+    }
+    // Normally a no-op: every batch ends with SYN_REPORT
+    flush_frame(
+        &mut frame,
+        open_device.abs_mapping.as_ref(),
+        state,
+        shared_state,
+        sink,
+    );
+    open_device.scroll_remainder = frame.hi_res_remainder;
+
+    true
+}
+
+// =============================================================================
+// Shortcut Consumption (opt-in, `consumeShortcuts`)
+// =============================================================================
+//
+// Normally the focused app also receives a shortcut's keys (Meta+Shift+F
+// types an "F"). With `consumeShortcuts` on, every keyboard is grabbed
+// (EVIOCGRAB) so only we see its events, and all keys are re-injected
+// through a uinput keyboard except a trigger key that fired a shortcut.
+//
+// TRADEOFFS:
+// - Needs write access to /dev/uinput; without it nothing is grabbed
+// - While grabbed, every keystroke passes through this process. If the
+//   input thread stalls, typing stalls with it. If it dies, the kernel
+//   releases the grab when the device fds close (and OpenDevice's Drop
+//   ungrabs explicitly, including when the loop panics).
+// - Other apps see the uinput keyboard instead of the physical one, so
+//   per-device keyboard settings (layouts, LED state) may not apply
+// - Keyboards are only grabbed while no key is held, so a key pressed
+//   before the grab never gets stuck down
+//
+// =============================================================================
+
+/// Name of the uinput keyboard that re-injects grabbed keys
+#[cfg(target_os = "linux")]
+const FORWARD_KEYBOARD_NAME: &str = "AI Character Assistant Keyboard";
+
+/// Creates the uinput keyboard grabbed keys are re-injected through,
+/// able to send every standard key code
+#[cfg(target_os = "linux")]
+fn create_forward_keyboard() -> std::io::Result<VirtualDevice> {
+    let mut keys = AttributeSet::<Key>::new();
+    // Codes below BTN_0 are keyboard keys; buttons would make other apps
+    // treat the device as a mouse or joystick
+    for code in 1..Key::BTN_0.code() {
+        keys.insert(Key::new(code));
+    }
+    VirtualDeviceBuilder::new()?
+        .name(FORWARD_KEYBOARD_NAME)
+        .with_keys(&keys)?
+        .build()
+}
+
+/// Re-injects key events from a grabbed keyboard as one frame
+#[cfg(target_os = "linux")]
+fn forward_keys(forward: &mut VirtualDevice, keys: &[InputEvent]) {
+    if keys.is_empty() {
+        return;
+    }
+    if let Err(e) = forward.emit(keys) {
+        log::warn!("[Input] Could not re-inject keys: {}", e);
+    }
+}
+
+/// Grabs or releases the keyboards to match `enabled`. Called every loop
+/// iteration: keyboards plugged in later (or busy with a held key) are
+/// grabbed once possible. Grabbing never starts unless the re-injection
+/// keyboard exists, so keys can't be swallowed without being passed on.
+#[cfg(target_os = "linux")]
+fn update_grab(devices: &mut [OpenDevice], enabled: bool, forward: &mut Option<VirtualDevice>) {
+    if !enabled {
+        if forward.is_some() {
+            for d in devices.iter_mut().filter(|d| d.grabbed) {
+                let _ = d.device.ungrab();
+                d.grabbed = false;
+            }
+            // Removing the uinput keyboard releases any key it still holds
+            *forward = None;
+            log::info!("[Input] Released keyboard grab");
+        }
+        for d in devices.iter_mut() {
+            d.grab_failed = false;
+        }
+        return;
+    }
+
+    if forward.is_none() {
+        // Don't retry creating it on every iteration after a failure
+        if devices.iter().any(|d| d.grab_failed) {
+            return;
+        }
+        match create_forward_keyboard() {
+            Ok(device) => *forward = Some(device),
+            Err(e) => {
+                log::warn!(
+                    "[Input] consumeShortcuts needs /dev/uinput ({}); shortcuts won't be consumed",
+                    e
+                );
+                for d in devices.iter_mut() {
+                    d.grab_failed = true;
+                }
+                return;
+            }
+        }
+    }
+
+    for d in devices.iter_mut() {
+        if d.device_type != DeviceType::Keyboard || d.grabbed || d.grab_failed {
+            continue;
+        }
+        // Wait until nothing is held, or the release would be re-injected
+        // for a press other apps saw from the physical keyboard
+        if d.device
+            .get_key_state()
+            .map_or(true, |keys| keys.iter().next().is_some())
+        {
+            continue;
+        }
+        match d.device.grab() {
+            Ok(()) => {
+                d.grabbed = true;
+                log::info!("[Input] Grabbed {} to consume shortcuts", d.name);
+            }
+            Err(e) => {
+                d.grab_failed = true;
+                log::warn!("[Input] Could not grab {}: {}", d.name, e);
+            }
+        }
+    }
+}
+
+/// The lock key an LED shows the state of
+#[cfg(target_os = "linux")]
+fn lock_for_led(led: LedType) -> Option<KeyCode> {
+    match led {
+        LedType::LED_CAPSL => Some(KeyCode::CapsLock),
+        LedType::LED_NUML => Some(KeyCode::NumLock),
+        _ => None,
+    }
+}
+
+/// Keys currently down on any keyboard, or None if one couldn't be read
+#[cfg(target_os = "linux")]
+fn pressed_keys(devices: &[OpenDevice]) -> Option<HashSet<KeyCode>> {
+    let mut pressed = HashSet::new();
+    for device in devices {
+        if device.device_type != DeviceType::Keyboard {
+            continue;
+        }
+        let keys = device.device.get_key_state().ok()?;
+        pressed.extend(keys.iter().filter_map(map_key_code));
+    }
+    Some(pressed)
+}
+
+/// Releases held keys whose key-up was lost, e.g. while a keyboard was
+/// being reopened. Leaves everything as is if a keyboard can't be read.
+#[cfg(target_os = "linux")]
+fn reconcile_held_keys(devices: &[OpenDevice], shared_state: &SharedState, sink: &dyn EventSink) {
+    if let Some(pressed) = pressed_keys(devices) {
+        shared_state.reconcile_held_keys(
+            sink,
+            &mut shared_state.input_state.lock().unwrap(),
+            |key| pressed.contains(&key),
+        );
+    }
+}
+
+/// Reads the current Caps/Num Lock state from the keyboards' LEDs. Later
+/// changes arrive as LED events.
+#[cfg(target_os = "linux")]
+fn sync_lock_state(devices: &[OpenDevice], state: &mut InputState) {
+    for device in devices {
+        if device.device_type != DeviceType::Keyboard {
+            continue;
+        }
+        if let Ok(leds) = device.device.get_led_state() {
+            for led in [LedType::LED_CAPSL, LedType::LED_NUML] {
+                if let Some(key) = lock_for_led(led) {
+                    state.set_lock(key, leds.contains(led));
+                }
+            }
+            return;
+        }
+    }
+}
+
+/// Motion accumulated over one evdev frame (the events up to SYN_REPORT),
+/// so a diagonal move is reported as one cursor update rather than one per
+/// axis
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+struct EventFrame {
+    dx: i32,
+    dy: i32,
+    scroll_dx: i32,
+    scroll_dy: i32,
+    /// High-resolution wheel motion in 1/120 notch units. Devices that send
+    /// it also send the coarse axes, so when present it replaces them.
+    hi_res_scroll_dx: Option<i32>,
+    hi_res_scroll_dy: Option<i32>,
+    /// Hi-res units left over from earlier frames; kept across flushes
+    hi_res_remainder: (i32, i32),
+    abs_moved: bool,
+}
+
+/// Hi-res wheel units per notch (the kernel's REL_WHEEL_HI_RES scale)
+#[cfg(target_os = "linux")]
+const HI_RES_UNITS_PER_NOTCH: i32 = 120;
+
+/// Adds hi-res wheel units to the leftover `remainder` and returns the
+/// whole notches, with what's left for the next frame. Smooth-scrolling
+/// wheels split one notch over several frames, so only whole notches
+/// count. Turning the wheel the other way drops the leftover.
+#[cfg(target_os = "linux")]
+fn hi_res_notches(remainder: i32, units: i32) -> (i32, i32) {
+    let total = if remainder.signum() == -units.signum() {
+        units
+    } else {
+        remainder + units
+    };
+    (
+        total / HI_RES_UNITS_PER_NOTCH,
+        total % HI_RES_UNITS_PER_NOTCH,
+    )
+}
+
+/// Applies one evdev event. Motion is collected in `frame` and reported
+/// when the frame ends; keys and buttons take effect immediately.
+/// Doesn't touch the device itself, so the whole pipeline can be driven
+/// from tests.
+#[cfg(target_os = "linux")]
+fn handle_event(
+    kind: InputEventKind,
+    value: i32,
+    frame: &mut EventFrame,
+    abs_mapping: Option<&mut AbsMapping>,
+    state: &mut InputState,
+    shared_state: &SharedState,
+    sink: &dyn EventSink,
+) {
+    match kind {
+        InputEventKind::RelAxis(axis) => match axis {
+            RelativeAxisType::REL_X => frame.dx += value,
+            RelativeAxisType::REL_Y => frame.dy += value,
+            RelativeAxisType::REL_WHEEL => frame.scroll_dy += value,
+            RelativeAxisType::REL_HWHEEL => frame.scroll_dx += value,
+            RelativeAxisType::REL_WHEEL_HI_RES => {
+                *frame.hi_res_scroll_dy.get_or_insert(0) += value;
+            }
+            RelativeAxisType::REL_HWHEEL_HI_RES => {
+                *frame.hi_res_scroll_dx.get_or_insert(0) += value;
+            }
+            _ => {}
+        },
+        InputEventKind::AbsAxis(axis) => {
+            if let Some(mapping) = abs_mapping {
+                match axis {
+                    AbsoluteAxisType::ABS_X => mapping.raw_x = value,
+                    AbsoluteAxisType::ABS_Y => mapping.raw_y = value,
+                    _ => return,
+                }
+                frame.abs_moved = true;
+            }
+        }
+        InputEventKind::Key(key) => {
+            let is_pressed = value == 1;
+            let is_released = value == 0;
+
+            // Modifiers
+            if let Some(shared_key) = map_key_code(key) {
+                if is_pressed {
+                    if state.held_modifiers.insert(shared_key) {
+                        shared_state.emit_key(sink, state, shared_key, true);
+                    }
+                } else if is_released && state.held_modifiers.remove(&shared_key) {
+                    shared_state.emit_key(sink, state, shared_key, false);
+                }
+                // Shortcuts
+                if is_pressed {
+                    if let Some(shortcut) = state.press_shortcut_key(shared_key) {
+                        shared_state.emit_shortcut(sink, state, &shortcut);
+                    }
+                } else if is_released {
+                    state.shortcut_key_released(shared_key);
+                }
+            }
+
+            // Double-tap shortcuts; any other key or button breaks a tap
+            if is_pressed || is_released {
+                let tap_key = map_key_code(key).unwrap_or(KeyCode::Unknown);
+                if let Some(shortcut) =
+                    state.track_modifier_tap(tap_key, is_pressed, Instant::now())
+                {
+                    shared_state.emit_shortcut(sink, state, &shortcut);
+                }
+            }
+
+            // Left button state drives drag and double-click gesture tracking
+            if key == Key::BTN_LEFT {
+                if is_pressed {
+                    state.left_button_down(Instant::now());
+                } else if is_released {
+                    shared_state.release_left_button(sink, state);
+                }
+            }
+            let other_button = match key {
+                Key::BTN_RIGHT => Some(MOUSE_BUTTON_RIGHT),
+                Key::BTN_MIDDLE => Some(MOUSE_BUTTON_MIDDLE),
+                _ => None,
+            };
+            if let Some(button) = other_button.filter(|_| is_pressed || is_released) {
+                state.set_mouse_button(button, is_pressed);
+            }
+
+            if is_pressed {
+                // Clicks
+                if let Some(button) = button_name(key) {
+                    shared_state.emit_click(sink, state, "click", &button);
+                }
+                shared_state.emit(sink, "activity", OutputEvent::Activity);
+            }
+        }
+        // The kernel reports lock state through the keyboard LEDs
+        InputEventKind::Led(led) => {
+            if let Some(key) = lock_for_led(led) {
+                state.set_lock(key, value != 0);
+            }
+        }
+        InputEventKind::Synchronization(Synchronization::SYN_REPORT) => {
+            flush_frame(frame, abs_mapping.as_deref(), state, shared_state, sink);
+        }
+        _ => {}
+    }
+}
+
+/// Click button name for a mouse button key, or None for any other key.
+/// The wheel click is BTN_MIDDLE; the kernel has no separate code for it.
+/// BTN_TASK and the unnamed codes after it, which tilt-click wheels and
+/// extra buttons often use, are named so each one stays distinct.
+#[cfg(target_os = "linux")]
+fn button_name(key: Key) -> Option<String> {
+    let name = match key {
+        Key::BTN_LEFT => "left",
+        Key::BTN_RIGHT => "right",
+        Key::BTN_MIDDLE => "middle",
+        // Thumb buttons; mice differ in which pair they report
+        Key::BTN_SIDE | Key::BTN_BACK => "back",
+        Key::BTN_EXTRA | Key::BTN_FORWARD => "forward",
+        Key::BTN_TASK => "task",
+        // Codes below the joystick range (BTN_TRIGGER) are unnamed mouse
+        // buttons, numbered from BTN_LEFT = 1 like the rest of the range
+        _ if (Key::BTN_TASK.code() + 1..Key::BTN_TRIGGER.code()).contains(&key.code()) => {
+            return Some(format!("button{}", key.code() - Key::BTN_LEFT.code() + 1));
+        }
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+/// Reports the motion collected in `frame` and resets it
+#[cfg(target_os = "linux")]
+fn flush_frame(
+    frame: &mut EventFrame,
+    abs_mapping: Option<&AbsMapping>,
+    state: &mut InputState,
+    shared_state: &SharedState,
+    sink: &dyn EventSink,
+) {
+    let (remainder_x, remainder_y) = frame.hi_res_remainder;
+    let taken = std::mem::take(frame);
+
+    // Prefer the hi-res axes where reported, so smooth-scrolling wheels
+    // aren't counted twice
+    let (scroll_dx, remainder_x) = match taken.hi_res_scroll_dx {
+        Some(units) => hi_res_notches(remainder_x, units),
+        None => (taken.scroll_dx, remainder_x),
+    };
+    let (scroll_dy, remainder_y) = match taken.hi_res_scroll_dy {
+        Some(units) => hi_res_notches(remainder_y, units),
+        None => (taken.scroll_dy, remainder_y),
+    };
+    frame.hi_res_remainder = (remainder_x, remainder_y);
+    let frame = taken;
+    if scroll_dx != 0 || scroll_dy != 0 {
+        shared_state.emit(
+            sink,
+            "scroll",
+            OutputEvent::Scroll {
+                dx: scroll_dx,
+                dy: scroll_dy,
+            },
+        );
+        shared_state.emit(sink, "activity", OutputEvent::Activity);
+    }
+
+    if let (true, Some(mapping)) = (frame.abs_moved, abs_mapping) {
+        let (x, y) = mapping.to_screen(state.screen_width, state.screen_height);
+        if state.set_cursor_absolute(x, y) {
+            shared_state.emit_cursor(sink, state);
+            shared_state.emit_drag_motion(sink, state);
+        }
+    }
+
+    if (frame.dx != 0 || frame.dy != 0) && state.update_cursor(frame.dx, frame.dy) {
+        shared_state.emit_cursor(sink, state);
+        shared_state.emit_drag_motion(sink, state);
+    }
+}
+
+/// Reads the real pointer position from the X server. Accumulated evdev
+/// deltas drift from it over time (acceleration, clamping at screen edges),
+/// so on X11 the loop resyncs to it whenever input goes quiet.
+#[cfg(target_os = "linux")]
+struct X11Pointer {
+    connection: RustConnection,
+    root: xproto::Window,
+}
+
+#[cfg(target_os = "linux")]
+impl X11Pointer {
+    /// Connects to the X server, only in an X11 session: under Wayland the
+    /// XWayland pointer is frozen whenever a native window has focus
+    fn connect() -> Option<Self> {
+        if std::env::var("XDG_SESSION_TYPE").ok()? != "x11" {
+            return None;
+        }
+        match x11rb::connect(None) {
+            Ok((connection, screen)) => {
+                let root = connection.setup().roots.get(screen)?.root;
+                log::info!("[Input] X11 session: resyncing the cursor with XQueryPointer");
+                Some(Self { connection, root })
+            }
+            Err(e) => {
+                log::warn!("[Input] Could not connect to the X server: {}", e);
+                None
+            }
+        }
+    }
+
+    /// The pointer position in root window (global screen) coordinates
+    fn position(&self) -> Result<(i32, i32), String> {
+        let reply = self
+            .connection
+            .query_pointer(self.root)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?;
+        Ok((reply.root_x as i32, reply.root_y as i32))
+    }
+}
+
+/// Moves the tracked cursor to where the X server says the pointer is.
+/// Drops the connection if the server stops answering.
+#[cfg(target_os = "linux")]
+fn resync_cursor(
+    x11_pointer: &mut Option<X11Pointer>,
+    shared_state: &SharedState,
+    sink: &dyn EventSink,
+) {
+    let Some(pointer) = x11_pointer else {
+        return;
+    };
+    let (x, y) = match pointer.position() {
+        Ok(position) => position,
+        Err(e) => {
+            log::warn!("[Input] XQueryPointer failed, no longer resyncing: {}", e);
+            *x11_pointer = None;
+            return;
+        }
+    };
+
+    let mut input_state = shared_state.input_state.lock().unwrap();
+    // A deferred smoothed or throttled report is still catching up
+    if input_state.needs_cursor_tick() {
+        return;
+    }
+    if input_state.set_cursor_absolute(x, y) {
+        shared_state.emit_cursor(sink, &mut input_state);
+        shared_state.emit_drag_motion(sink, &mut input_state);
+    }
+}
+
+// =============================================================================
+// Touchpads (libinput)
+// =============================================================================
+//
+// Touchpads report raw finger positions (ABS_MT_*), which only mean something
+// after libinput's processing: acceleration, tapping, two-finger scrolling and
+// gesture recognition. The evdev reader skips them; instead each touchpad is
+// added to a path-based libinput context whose events are fed into the same
+// pipeline as the evdev devices. Builds without the `touchpad` feature (which
+// needs libinput-dev) simply don't read touchpads.
+//
+// =============================================================================
+
+/// Whether an evdev device is a touchpad: an indirect absolute surface
+/// tracking fingers, and not something the evdev reader already handles
+#[cfg(all(target_os = "linux", feature = "touchpad"))]
+fn is_touchpad(device: &Device) -> bool {
+    let has_position = device.supported_absolute_axes().is_some_and(|axes| {
+        axes.contains(AbsoluteAxisType::ABS_X) && axes.contains(AbsoluteAxisType::ABS_Y)
+    });
+    let tracks_fingers = device
+        .supported_keys()
+        .is_some_and(|keys| keys.contains(Key::BTN_TOOL_FINGER));
+    has_position
+        && tracks_fingers
+        && !device.properties().contains(PropType::DIRECT)
+        && classify_device(device).is_none()
+}
+
+/// Opens device nodes on libinput's behalf
+#[cfg(all(target_os = "linux", feature = "touchpad"))]
+struct LibinputFiles;
+
+#[cfg(all(target_os = "linux", feature = "touchpad"))]
+impl LibinputInterface for LibinputFiles {
+    fn open_restricted(&mut self, path: &Path, flags: i32) -> Result<OwnedFd, i32> {
+        let access = flags & libc::O_ACCMODE;
+        fs::OpenOptions::new()
+            .custom_flags(flags)
+            .read(access == libc::O_RDONLY || access == libc::O_RDWR)
+            .write(access == libc::O_WRONLY || access == libc::O_RDWR)
+            .open(path)
+            .map(OwnedFd::from)
+            .map_err(|e| e.raw_os_error().unwrap_or(libc::EIO))
+    }
+
+    fn close_restricted(&mut self, fd: OwnedFd) {
+        drop(fs::File::from(fd));
+    }
+}
+
+/// Touchpad scroll distance (libinput's pixel-like units) per wheel notch
+#[cfg(all(target_os = "linux", feature = "touchpad"))]
+const TOUCHPAD_SCROLL_UNITS_PER_NOTCH: f64 = 15.0;
+
+/// Adds (dx, dy) / `unit` to `remainder` and takes out the whole units,
+/// so fractional motion adds up instead of being lost
+#[cfg(all(target_os = "linux", feature = "touchpad"))]
+fn take_whole_units(remainder: &mut (f64, f64), dx: f64, dy: f64, unit: f64) -> (i32, i32) {
+    remainder.0 += dx / unit;
+    remainder.1 += dy / unit;
+    let whole = (remainder.0.trunc(), remainder.1.trunc());
+    remainder.0 -= whole.0;
+    remainder.1 -= whole.1;
+    (whole.0 as i32, whole.1 as i32)
+}
+
+/// The touchpads, read through libinput
+#[cfg(all(target_os = "linux", feature = "touchpad"))]
+struct Touchpads {
+    libinput: Libinput,
+    /// libinput's handle for each touchpad node, to remove it when unplugged
+    devices: HashMap<PathBuf, input::Device>,
+    /// Nodes already found not to be touchpads (or not openable)
+    others: HashSet<PathBuf>,
+    /// Pointer motion not yet applied, in pixels
+    motion_remainder: (f64, f64),
+    /// Finger scrolling not yet reported, in notches
+    scroll_remainder: (f64, f64),
+}
+
+#[cfg(all(target_os = "linux", feature = "touchpad"))]
+impl Touchpads {
+    fn new() -> Self {
+        Self {
+            libinput: Libinput::new_from_path(LibinputFiles),
+            devices: HashMap::new(),
+            others: HashSet::new(),
+            motion_remainder: (0.0, 0.0),
+            scroll_remainder: (0.0, 0.0),
+        }
+    }
+
+    /// Adds newly plugged touchpads and forgets unplugged ones.
+    /// Returns true if the set changed.
+    fn rescan(&mut self) -> bool {
+        let present: HashSet<PathBuf> = list_event_nodes().into_iter().collect();
+        self.others.retain(|path| present.contains(path));
+
+        let gone: Vec<PathBuf> = self
+            .devices
+            .keys()
+            .filter(|path| !present.contains(*path))
+            .cloned()
+            .collect();
+        let mut changed = !gone.is_empty();
+        for path in gone {
+            if let Some(device) = self.devices.remove(&path) {
+                log::info!("[Input] Touchpad removed: {:?}", path);
+                self.libinput.path_remove_device(device);
+            }
+        }
+
+        for path in present {
+            if self.devices.contains_key(&path) || self.others.contains(&path) {
+                continue;
+            }
+            if !Device::open(&path).is_ok_and(|device| is_touchpad(&device)) {
+                self.others.insert(path);
+                continue;
+            }
+            match self.libinput.path_add_device(&path.to_string_lossy()) {
+                Some(mut device) => {
+                    // libinput leaves tapping off; most users expect it
+                    let _ = device.config_tap_set_enabled(true);
+                    log::info!("[Input] Touchpad added: {:?} ({})", path, device.name());
+                    self.devices.insert(path, device);
+                    changed = true;
+                }
+                None => {
+                    log::warn!("[Input] libinput could not open touchpad {:?}", path);
+                    self.others.insert(path);
+                }
+            }
+        }
+        changed
+    }
+
+    fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+
+    /// The fd to poll for touchpad events, if any touchpad is open
+    fn raw_fd(&self) -> Option<i32> {
+        (!self.is_empty()).then(|| self.libinput.as_raw_fd())
+    }
+
+    /// Reads everything libinput has queued and applies it
+    fn process(&mut self, shared_state: &SharedState, sink: &dyn EventSink) {
+        if let Err(e) = self.libinput.dispatch() {
+            log::warn!("[Input] libinput dispatch failed: {}", e);
+            return;
+        }
+
+        let mut state = shared_state.input_state.lock().unwrap();
+        for event in &mut self.libinput {
+            let mut frame = EventFrame::default();
+            match event {
+                Event::Pointer(PointerEvent::Motion(motion)) => {
+                    (frame.dx, frame.dy) =
+                        take_whole_units(&mut self.motion_remainder, motion.dx(), motion.dy(), 1.0);
+                }
+                Event::Pointer(PointerEvent::ScrollFinger(scroll)) => {
+                    let value = |axis| {
+                        if scroll.has_axis(axis) {
+                            scroll.scroll_value(axis)
+                        } else {
+                            0.0
                         }
-                        _ => {}
-                    }
-                    let _ = app_handle.emit("activity", OutputEvent::Activity);
+                    };
+                    // libinput scrolls down for positive values; Scroll's
+                    // dy is positive away from the user
+                    (frame.scroll_dx, frame.scroll_dy) = take_whole_units(
+                        &mut self.scroll_remainder,
+                        value(Axis::Horizontal),
+                        -value(Axis::Vertical),
+                        TOUCHPAD_SCROLL_UNITS_PER_NOTCH,
+                    );
+                }
+                // Physical and tapped clicks carry evdev button codes
+                Event::Pointer(PointerEvent::Button(button)) => {
+                    let pressed = button.button_state() == ButtonState::Pressed;
+                    handle_event(
+                        InputEventKind::Key(Key::new(button.button() as u16)),
+                        i32::from(pressed),
+                        &mut frame,
+                        None,
+                        &mut state,
+                        shared_state,
+                        sink,
+                    );
+                }
+                Event::Gesture(GestureEvent::Swipe(GestureSwipeEvent::Update(swipe))) => {
+                    emit_gesture(shared_state, sink, "swipe", 1.0, swipe.dx(), swipe.dy());
                 }
+                Event::Gesture(GestureEvent::Pinch(GesturePinchEvent::Update(pinch))) => {
+                    let scale = pinch.scale();
+                    emit_gesture(shared_state, sink, "pinch", scale, pinch.dx(), pinch.dy());
+                }
+                _ => {}
             }
-            _ => {}
+            flush_frame(&mut frame, None, &mut state, shared_state, sink);
         }
     }
+}
+
+/// Emits one step of a swipe or pinch gesture
+#[cfg(all(target_os = "linux", feature = "touchpad"))]
+fn emit_gesture(
+    shared_state: &SharedState,
+    sink: &dyn EventSink,
+    kind: &str,
+    scale: f64,
+    dx: f64,
+    dy: f64,
+) {
+    shared_state.emit(
+        sink,
+        "gesture",
+        OutputEvent::Gesture {
+            kind: kind.to_string(),
+            scale,
+            dx,
+            dy,
+        },
+    );
+    shared_state.emit(sink, "activity", OutputEvent::Activity);
+}
+
+/// Stand-in when built without libinput: touchpads are not read
+#[cfg(all(target_os = "linux", not(feature = "touchpad")))]
+struct Touchpads;
+
+#[cfg(all(target_os = "linux", not(feature = "touchpad")))]
+impl Touchpads {
+    fn new() -> Self {
+        Self
+    }
 
-    if total_dx != 0 || total_dy != 0 {
-        if state.update_cursor(total_dx, total_dy) {
-            let _ = app_handle.emit(
-                "cursor-pos",
-                OutputEvent::Cursor {
-                    x: state.cursor_x,
-                    y: state.cursor_y,
-                },
-            );
+    fn rescan(&mut self) -> bool {
+        false
+    }
+
+    fn is_empty(&self) -> bool {
+        true
+    }
+
+    fn raw_fd(&self) -> Option<i32> {
+        None
+    }
+
+    fn process(&mut self, _shared_state: &SharedState, _sink: &dyn EventSink) {}
+}
+
+/// If the wall clock advanced this much more than the monotonic clock between
+/// two loop iterations, the machine was almost certainly suspended.
+#[cfg(target_os = "linux")]
+const RESUME_GAP_THRESHOLD: Duration = Duration::from_secs(5);
+
+/// Where relative cursor motion is read from. The kernel mirrors every mouse
+/// into /dev/input/mice, so reading both would apply each movement twice.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MotionSource {
+    /// The per-device evdev nodes
+    Evdev,
+    /// The legacy /dev/input/mice multiplexer (or failing that one mouse's
+    /// /dev/input/mouseN), used only without evdev mice
+    MiceFile,
+}
+
+/// mousedev creates at most this many per-mouse /dev/input/mouseN nodes
+#[cfg(target_os = "linux")]
+const MAX_MOUSE_NODES: usize = 32;
+
+/// The legacy mousedev nodes to fall back on, in order: the multiplexer
+/// of all mice, then each mouse's own node
+#[cfg(target_os = "linux")]
+fn mousedev_paths() -> impl Iterator<Item = PathBuf> {
+    std::iter::once(PathBuf::from("/dev/input/mice"))
+        .chain((0..MAX_MOUSE_NODES).map(|n| PathBuf::from(format!("/dev/input/mouse{}", n))))
+}
+
+#[cfg(target_os = "linux")]
+impl MotionSource {
+    /// Evdev whenever a pointing device could be opened, else the fallback.
+    /// Touchscreens, tablets and libinput touchpads count too: mousedev
+    /// mirrors them as well.
+    fn choose(devices: &[OpenDevice], has_touchpads: bool) -> Self {
+        let has_pointer = has_touchpads
+            || devices
+                .iter()
+                .any(|d| matches!(d.device_type, DeviceType::Mouse | DeviceType::AbsPointer));
+        if has_pointer {
+            MotionSource::Evdev
+        } else {
+            MotionSource::MiceFile
         }
     }
 }
 
+/// Re-picks the motion source for the current device list, opening or
+/// closing /dev/input/mice to match, and logs whenever the choice changes
 #[cfg(target_os = "linux")]
-pub fn run_input_loop(app_handle: AppHandle, shared_state: Arc<SharedState>) {
-    let mut devices = discover_devices();
-    let mut mice_file = std::fs::File::open("/dev/input/mice").ok();
+fn update_motion_source(
+    devices: &[OpenDevice],
+    touchpads: &Touchpads,
+    current: &mut Option<MotionSource>,
+    mice_file: &mut Option<fs::File>,
+) {
+    let source = MotionSource::choose(devices, !touchpads.is_empty());
+    if *current == Some(source) {
+        return;
+    }
+    *current = Some(source);
 
-    let mice_count = devices
-        .iter()
-        .filter(|d| d.device_type == DeviceType::Mouse)
-        .count();
-    let keyboards_count = devices
+    match source {
+        MotionSource::Evdev => {
+            *mice_file = None;
+            log::info!("[Input] Motion source: evdev devices");
+        }
+        MotionSource::MiceFile => {
+            let opened = mousedev_paths()
+                .find_map(|path| fs::File::open(&path).ok().map(|file| (path, file)));
+            match opened {
+                Some((path, file)) => {
+                    log::info!(
+                        "[Input] Motion source: {} (no evdev mouse found)",
+                        path.display()
+                    );
+                    *mice_file = Some(file);
+                }
+                None => {
+                    log::info!(
+                        "[Input] Motion source: none (no evdev mouse, no readable mousedev node)"
+                    );
+                    *mice_file = None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn emit_ready(
+    sink: &dyn EventSink,
+    shared_state: &SharedState,
+    devices: &mut [OpenDevice],
+    has_mice_file: bool,
+    has_touchpads: bool,
+) {
+    // Only report devices we can actually read; if there are none, Ready
+    // would claim a working setup where nothing moves
+    let readable: Vec<bool> = devices.iter_mut().map(is_readable).collect();
+    let readable_devices: Vec<&OpenDevice> = devices
         .iter()
-        .filter(|d| d.device_type == DeviceType::Keyboard)
-        .count();
+        .zip(readable)
+        .filter_map(|(device, readable)| readable.then_some(device))
+        .collect();
+    if readable_devices.is_empty() && !has_mice_file && !has_touchpads {
+        report_unreadable_devices(sink, shared_state, devices.len());
+        return;
+    }
+
+    let (mice_count, keyboards_count) =
+        count_devices(readable_devices.iter().copied(), has_mice_file);
+
+    // Keyboards alone still run shortcuts, but say why the character
+    // isn't following the cursor
+    if mice_count == 0 && !has_touchpads {
+        report_missing_pointer(sink, shared_state);
+    }
 
-    let screen_width = shared_state.input_state.lock().unwrap().screen_width;
-    let screen_height = shared_state.input_state.lock().unwrap().screen_height;
+    let (screen_width, screen_height, monitors) = {
+        let input_state = shared_state.input_state.lock().unwrap();
+        (
+            input_state.screen_width,
+            input_state.screen_height,
+            input_state.monitors.rects.clone(),
+        )
+    };
 
-    let _ = app_handle.emit(
+    shared_state.emit(
+        sink,
         "ready",
         OutputEvent::Ready {
-            mice_count: mice_count + if mice_file.is_some() { 1 } else { 0 },
+            mice_count,
             keyboards_count,
+            device_names: device_names(readable_devices.iter().copied()),
             screen_width,
             screen_height,
+            monitors,
+            scale_factor: detect_scale_factor(),
         },
     );
+}
+
+/// Tells the frontend when no evdev device could be opened or read, which
+/// usually means the user isn't in the `input` group
+#[cfg(target_os = "linux")]
+fn report_unreadable_devices(sink: &dyn EventSink, shared_state: &SharedState, opened: usize) {
+    let problem = match fs::read_dir("/dev/input") {
+        Err(e) => format!("Cannot read /dev/input ({})", e),
+        Ok(_) if opened > 0 => format!(
+            "{} input devices were opened but none of them can be read",
+            opened
+        ),
+        Ok(_) => "No mouse, touchpad or keyboard could be opened in /dev/input, \
+                  and /dev/input/mice is unavailable"
+            .to_string(),
+    };
+    let mut message = format!(
+        "{}. Run: sudo usermod -a -G input $USER, then log out and back in",
+        problem
+    );
+    if let Some(protocols) = wayland_pointer_protocols() {
+        log::info!("[Input] Wayland pointer protocols: {:?}", protocols);
+        message.push_str(
+            ". Wayland doesn't let apps see the cursor outside their own windows, \
+             so there is no fallback",
+        );
+    }
+    shared_state.emit_error(sink, message);
+}
+
+/// Tells the frontend when keyboards can be read but nothing reports
+/// pointer motion: no evdev mouse, touchscreen or touchpad, and no
+/// readable mousedev node
+#[cfg(target_os = "linux")]
+fn report_missing_pointer(sink: &dyn EventSink, shared_state: &SharedState) {
+    let mut message = "Keyboard shortcuts work, but no mouse or touchpad could be read \
+                       and /dev/input/mice is unavailable, so the cursor isn't tracked"
+        .to_string();
+    if !cfg!(feature = "touchpad") {
+        message.push_str(". Touchpads are only read in builds with the touchpad feature");
+    }
+    message.push_str(
+        ". If a mouse is connected, run: sudo usermod -a -G input $USER, then log out and back in",
+    );
+    shared_state.emit_error(sink, message);
+}
+
+/// Self-pipe the input loop polls alongside the devices, so it can be
+/// woken from another thread instead of sleeping out its idle timeout
+/// (up to `maxPollTimeoutMs`). Read end first.
+#[cfg(target_os = "linux")]
+static WAKE_PIPE: OnceLock<Option<(UnixStream, UnixStream)>> = OnceLock::new();
 
-    println!(
-        "[Tauri Input] Thread started. Monitor: {}x{}",
-        screen_width, screen_height
+#[cfg(target_os = "linux")]
+fn wake_pipe() -> Option<&'static (UnixStream, UnixStream)> {
+    WAKE_PIPE
+        .get_or_init(|| {
+            let pipe = UnixStream::pair().and_then(|(read, write)| {
+                read.set_nonblocking(true)?;
+                write.set_nonblocking(true)?;
+                Ok((read, write))
+            });
+            match pipe {
+                Ok(pipe) => Some(pipe),
+                Err(e) => {
+                    log::warn!("[Input] Could not create the wake-up pipe: {}", e);
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+/// Empties the wake-up pipe after the loop woke, so it can block again
+#[cfg(target_os = "linux")]
+fn drain_wake_pipe() {
+    if let Some((read, _)) = wake_pipe() {
+        let mut buf = [0u8; 64];
+        while matches!((&*read).read(&mut buf), Ok(n) if n > 0) {}
+    }
+}
+
+/// Wakes the poll loop at once, so it sees a shutdown or changed settings
+/// (e.g. `consumeShortcuts`) without waiting for input or its timeout.
+/// A full pipe already has a wake-up pending, so write errors are ignored.
+#[cfg(target_os = "linux")]
+pub fn wake_input_loop() {
+    if let Some((_, write)) = wake_pipe() {
+        let _ = (&*write).write(&[1]);
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn run_input_loop(sink: Arc<dyn EventSink>, shared_state: Arc<SharedState>) {
+    let mut devices = discover_devices();
+    publish_devices(&devices);
+    let mut touchpads = Touchpads::new();
+    touchpads.rescan();
+    let mut motion_source = None;
+    let mut mice_file = None;
+    update_motion_source(&devices, &touchpads, &mut motion_source, &mut mice_file);
+
+    emit_ready(
+        &sink,
+        &shared_state,
+        &mut devices,
+        mice_file.is_some(),
+        !touchpads.is_empty(),
     );
+    emit_device_info(&sink, &shared_state, &mut devices);
+    sync_lock_state(&devices, &mut shared_state.input_state.lock().unwrap());
+
+    {
+        let input_state = shared_state.input_state.lock().unwrap();
+        log::info!(
+            "[Tauri Input] Thread started. Monitor: {}x{}",
+            input_state.screen_width,
+            input_state.screen_height
+        );
+    }
+
+    // Instant is CLOCK_MONOTONIC, which stops while suspended; SystemTime
+    // keeps counting. A large divergence between them means we just resumed.
+    let mut last_tick = Instant::now();
+    let mut last_wall = SystemTime::now();
+    let mut last_poll_return = Instant::now();
+
+    let mut last_rescan = Instant::now();
+    let mut ignored_nodes = HashSet::new();
+
+    let mut last_heartbeat = Instant::now();
+    let mut last_cursor_tick = Instant::now();
+
+    // Re-injection keyboard while shortcut consumption is active
+    let mut forward = None;
+
+    // Source of the real pointer position, on X11 only
+    let mut x11_pointer = X11Pointer::connect();
 
     loop {
+        if shared_state.is_shutting_down() {
+            log::info!("[Input] Shutdown requested. Stopping input loop.");
+            *LOOP_DEVICES.lock().unwrap_or_else(|e| e.into_inner()) = None;
+            return;
+        }
+
+        let consume_shortcuts = shared_state.input_state.lock().unwrap().consume_shortcuts;
+        update_grab(&mut devices, consume_shortcuts, &mut forward);
+
+        let mono_elapsed = last_tick.elapsed();
+        let wall_elapsed = last_wall.elapsed().unwrap_or(mono_elapsed);
+        last_tick = Instant::now();
+        last_wall = SystemTime::now();
+
+        // Time spent asleep or away from poll() since it last returned
+        let away = wall_elapsed
+            .saturating_sub(mono_elapsed)
+            .max(last_poll_return.elapsed());
+        if away > STALE_MOTION_GAP {
+            for d in devices.iter_mut() {
+                d.stale_before = Some(last_wall);
+            }
+        }
+
+        if wall_elapsed.saturating_sub(mono_elapsed) > RESUME_GAP_THRESHOLD {
+            log::info!(
+                "[Input] Resume from suspend detected ({}s asleep). Re-initializing input...",
+                (wall_elapsed - mono_elapsed).as_secs()
+            );
+
+            // Event numbers may have changed across sleep, so start from scratch
+            devices = discover_devices();
+            publish_devices(&devices);
+            touchpads = Touchpads::new();
+            touchpads.rescan();
+            motion_source = None;
+            update_motion_source(&devices, &touchpads, &mut motion_source, &mut mice_file);
+            ignored_nodes.clear();
+
+            invalidate_screen_cache();
+            shared_state
+                .input_state
+                .lock()
+                .unwrap()
+                .set_monitors(detect_monitors());
+
+            emit_ready(
+                &sink,
+                &shared_state,
+                &mut devices,
+                mice_file.is_some(),
+                !touchpads.is_empty(),
+            );
+            emit_device_info(&sink, &shared_state, &mut devices);
+            sync_lock_state(&devices, &mut shared_state.input_state.lock().unwrap());
+            reconcile_held_keys(&devices, &shared_state, sink.as_ref());
+        }
+
         let mut poll_fds = Vec::new();
 
         for d in &devices {
@@ -330,25 +2634,74 @@ pub fn run_input_loop(app_handle: AppHandle, shared_state: Arc<SharedState>) {
             poll_fds.push(PollFd::new(borrowed, PollFlags::POLLIN));
         }
 
+        // libinput's fd goes right after the evdev devices
+        let touchpad_fd = touchpads.raw_fd();
+        if let Some(fd) = touchpad_fd {
+            let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+            poll_fds.push(PollFd::new(borrowed, PollFlags::POLLIN));
+        }
+
+        let mice_index = poll_fds.len();
         if let Some(ref f) = mice_file {
             let borrowed = unsafe { BorrowedFd::borrow_raw(f.as_raw_fd()) };
             poll_fds.push(PollFd::new(borrowed, PollFlags::POLLIN));
         }
 
-        if let Ok(n) = poll(&mut poll_fds, nix::poll::PollTimeout::from(1000u16)) {
+        // The wake-up pipe always goes last
+        if let Some((read, _)) = wake_pipe() {
+            let borrowed = unsafe { BorrowedFd::borrow_raw(read.as_raw_fd()) };
+            poll_fds.push(PollFd::new(borrowed, PollFlags::POLLIN));
+        }
+
+        // Indices of devices that were unplugged during this iteration
+        let mut gone = Vec::new();
+
+        // Wake up at the tick rate while a smoothed or throttled cursor
+        // move is still waiting to be reported; otherwise back off the
+        // longer the user has been away
+        let idle_poll_timeout = shared_state.poll_timeout();
+        let timeout = if shared_state.input_state.lock().unwrap().needs_cursor_tick() {
+            CURSOR_TICK
+        } else {
+            idle_poll_timeout
+        };
+        let timeout_ms = timeout.as_millis().min(u16::MAX as u128) as u16;
+
+        let polled = poll(&mut poll_fds, nix::poll::PollTimeout::from(timeout_ms));
+        last_poll_return = Instant::now();
+        if let Ok(n) = polled {
             if n > 0 {
+                drain_wake_pipe();
                 for (i, d) in devices.iter_mut().enumerate() {
                     if let Some(revents) = poll_fds[i].revents() {
                         if revents.contains(PollFlags::POLLIN) {
                             let mut input_state = shared_state.input_state.lock().unwrap();
-                            process_device_events(d, &mut input_state, &app_handle);
+                            if !process_device_events(
+                                d,
+                                &mut input_state,
+                                &shared_state,
+                                &sink,
+                                forward.as_mut(),
+                            ) {
+                                gone.push(i);
+                            }
+                        } else if revents.intersects(PollFlags::POLLHUP | PollFlags::POLLERR) {
+                            gone.push(i);
                         }
                     }
                 }
 
+                if touchpad_fd.is_some() {
+                    let readable = poll_fds[devices.len()]
+                        .revents()
+                        .is_some_and(|revents| revents.contains(PollFlags::POLLIN));
+                    if readable {
+                        touchpads.process(&shared_state, sink.as_ref());
+                    }
+                }
+
                 if mice_file.is_some() {
-                    let idx = poll_fds.len() - 1;
-                    if let Some(revents) = poll_fds[idx].revents() {
+                    if let Some(revents) = poll_fds[mice_index].revents() {
                         if revents.contains(PollFlags::POLLIN) {
                             let mut buf = [0u8; 3];
                             if let Some(ref mut f) = mice_file {
@@ -357,34 +2710,40 @@ pub fn run_input_loop(app_handle: AppHandle, shared_state: Arc<SharedState>) {
                                     let rel_y = -(buf[2] as i8 as i32);
 
                                     let mut input_state = shared_state.input_state.lock().unwrap();
+
+                                    // The PS/2 packet carries held-button state, so
+                                    // track the left button by its transitions
+                                    let left_down = (buf[0] & 1) != 0;
+                                    if left_down && !input_state.left_button_held {
+                                        input_state.left_button_down(Instant::now());
+                                    } else if !left_down && input_state.left_button_held {
+                                        shared_state.release_left_button(&sink, &mut input_state);
+                                    }
+                                    // Its other bits match MOUSE_BUTTON_RIGHT/MIDDLE
+                                    input_state
+                                        .set_mouse_button(MOUSE_BUTTON_RIGHT, (buf[0] & 2) != 0);
+                                    input_state
+                                        .set_mouse_button(MOUSE_BUTTON_MIDDLE, (buf[0] & 4) != 0);
+
                                     if input_state.update_cursor(rel_x, rel_y) {
-                                        let _ = app_handle.emit(
-                                            "cursor-pos",
-                                            OutputEvent::Cursor {
-                                                x: input_state.cursor_x,
-                                                y: input_state.cursor_y,
-                                            },
-                                        );
+                                        shared_state.emit_cursor(&sink, &mut input_state);
+                                        shared_state.emit_drag_motion(&sink, &mut input_state);
                                     }
                                     if (buf[0] & 1) != 0 {
-                                        let _ = app_handle.emit(
+                                        shared_state.emit_click(
+                                            &sink,
+                                            &mut input_state,
                                             "click",
-                                            OutputEvent::Click {
-                                                button: "left".into(),
-                                                x: input_state.cursor_x,
-                                                y: input_state.cursor_y,
-                                            },
+                                            "left",
                                         );
                                     }
 
                                     if (buf[0] & 2) != 0 {
-                                        let _ = app_handle.emit(
+                                        shared_state.emit_click(
+                                            &sink,
+                                            &mut input_state,
                                             "click",
-                                            OutputEvent::Click {
-                                                button: "right".into(),
-                                                x: input_state.cursor_x,
-                                                y: input_state.cursor_y,
-                                            },
+                                            "right",
                                         );
                                     }
                                 }
@@ -393,8 +2752,501 @@ pub fn run_input_loop(app_handle: AppHandle, shared_state: Arc<SharedState>) {
                     }
                 }
             } else {
-                let _ = app_handle.emit("heartbeat", OutputEvent::Heartbeat);
+                resync_cursor(&mut x11_pointer, &shared_state, sink.as_ref());
+
+                // Deferred cursor reports shorten the poll timeout, so pace
+                // heartbeats by the idle timeout rather than emitting one
+                // per wake-up. They slow down with it while the user is away.
+                if last_heartbeat.elapsed() >= idle_poll_timeout {
+                    last_heartbeat = Instant::now();
+                    shared_state.emit(&sink, "heartbeat", OutputEvent::Heartbeat);
+                    shared_state.check_idle(&sink);
+                }
+            }
+        }
+
+        // Checked on every iteration, since continuous movement means the
+        // poll rarely times out
+        if last_cursor_tick.elapsed() >= CURSOR_TICK {
+            last_cursor_tick = Instant::now();
+            let mut input_state = shared_state.input_state.lock().unwrap();
+            if input_state.tick_smoothing().is_some() || input_state.take_throttled_cursor() {
+                shared_state.emit_cursor(&sink, &mut input_state);
+                shared_state.emit_drag_motion(&sink, &mut input_state);
+            }
+        }
+
+        // poll_fds indexes into `devices`, so it must not outlive any
+        // change to the list below; it's rebuilt at the top of the loop
+        drop(poll_fds);
+
+        let mut devices_changed = drop_gone_devices(&mut devices, &gone, &mut ignored_nodes);
+
+        // Checked on every iteration rather than only on poll timeout, so a
+        // constantly moving mouse can't starve the rescan
+        if last_rescan.elapsed() >= HOTPLUG_RESCAN_INTERVAL {
+            last_rescan = Instant::now();
+            devices_changed |= rescan_devices(&EvdevSource, &mut devices, &mut ignored_nodes);
+            devices_changed |= touchpads.rescan();
+            emit_device_info(&sink, &shared_state, &mut devices);
+            reconcile_held_keys(&devices, &shared_state, sink.as_ref());
+        }
+
+        if devices_changed {
+            publish_devices(&devices);
+            update_motion_source(&devices, &touchpads, &mut motion_source, &mut mice_file);
+            let (mice_count, keyboards_count) = count_devices(&devices, mice_file.is_some());
+            shared_state.emit(
+                &sink,
+                "devices-changed",
+                OutputEvent::DevicesChanged {
+                    mice_count,
+                    keyboards_count,
+                    device_names: device_names(&devices),
+                },
+            );
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    /// Collects emitted events instead of sending them anywhere
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<OutputEvent>>,
+    }
+
+    impl EventSink for RecordingSink {
+        fn send(&self, _channel: &str, event: &OutputEvent) {
+            self.events.lock().unwrap().push(event.clone());
+        }
+    }
+
+    /// Feeds `events` through handle_event as one frame, with the tracked
+    /// cursor already synced, and returns everything that was emitted
+    fn run_frame(state: &mut InputState, events: &[(InputEventKind, i32)]) -> Vec<OutputEvent> {
+        run_frame_with(state, &mut EventFrame::default(), events)
+    }
+
+    /// run_frame, continuing from `frame` as the next frame of one device
+    fn run_frame_with(
+        state: &mut InputState,
+        frame: &mut EventFrame,
+        events: &[(InputEventKind, i32)],
+    ) -> Vec<OutputEvent> {
+        let shared_state = SharedState::new(InputState::new(1920, 1080));
+        let sink = RecordingSink::default();
+        let syn = (
+            InputEventKind::Synchronization(Synchronization::SYN_REPORT),
+            0,
+        );
+        for &(kind, value) in events.iter().chain([&syn]) {
+            handle_event(kind, value, frame, None, state, &shared_state, &sink);
+        }
+        sink.events.into_inner().unwrap()
+    }
+
+    fn synced_state() -> InputState {
+        let mut state = InputState::new(1920, 1080);
+        state.cursor_synced = true;
+        state
+    }
+
+    #[test]
+    fn relative_motion_frame_emits_one_cursor_event() {
+        let mut state = synced_state();
+        let events = run_frame(
+            &mut state,
+            &[
+                (InputEventKind::RelAxis(RelativeAxisType::REL_X), 5),
+                (InputEventKind::RelAxis(RelativeAxisType::REL_Y), -3),
+            ],
+        );
+
+        let cursors: Vec<_> = events
+            .iter()
+            .filter(|e| matches!(e, OutputEvent::Cursor { .. }))
+            .collect();
+        assert_eq!(cursors.len(), 1);
+        assert!(matches!(
+            cursors[0],
+            OutputEvent::Cursor {
+                x: 965,
+                y: 537,
+                buttons: 0,
+                at_edge: false
+            }
+        ));
+    }
+
+    #[test]
+    fn hi_res_wheel_replaces_the_coarse_axis() {
+        let mut state = synced_state();
+        let events = run_frame(
+            &mut state,
+            &[
+                (
+                    InputEventKind::RelAxis(RelativeAxisType::REL_WHEEL_HI_RES),
+                    120,
+                ),
+                (InputEventKind::RelAxis(RelativeAxisType::REL_WHEEL), 1),
+            ],
+        );
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, OutputEvent::Scroll { dx: 0, dy: 1 })));
+
+        // A smooth-scrolling wheel spreads a notch over several frames, with
+        // no coarse event at all; only the whole notch scrolls
+        let mut frame = EventFrame::default();
+        let quarter_notch = [(
+            InputEventKind::RelAxis(RelativeAxisType::REL_HWHEEL_HI_RES),
+            -30,
+        )];
+        let events = run_frame_with(&mut state, &mut frame, &quarter_notch);
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, OutputEvent::Scroll { .. })));
+
+        let mut scrolls = Vec::new();
+        for _ in 1..4 {
+            let events = run_frame_with(&mut state, &mut frame, &quarter_notch);
+            scrolls.extend(
+                events
+                    .into_iter()
+                    .filter(|e| matches!(e, OutputEvent::Scroll { .. })),
+            );
+        }
+        assert_eq!(scrolls.len(), 1);
+        assert!(matches!(scrolls[0], OutputEvent::Scroll { dx: -1, dy: 0 }));
+    }
+
+    #[test]
+    fn left_button_press_emits_a_click() {
+        let mut state = synced_state();
+        let events = run_frame(&mut state, &[(InputEventKind::Key(Key::BTN_LEFT), 1)]);
+
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, OutputEvent::Click { button, .. } if button == "left")));
+    }
+
+    #[test]
+    fn wake_input_loop_interrupts_poll_until_drained() {
+        let (read, _) = wake_pipe().unwrap();
+        let readable = || {
+            let borrowed = unsafe { BorrowedFd::borrow_raw(read.as_raw_fd()) };
+            let mut fds = [PollFd::new(borrowed, PollFlags::POLLIN)];
+            poll(&mut fds, nix::poll::PollTimeout::from(0u16)).unwrap() > 0
+        };
+
+        wake_input_loop();
+        wake_input_loop();
+        assert!(readable());
+        drain_wake_pipe();
+        assert!(!readable());
+    }
+
+    #[test]
+    fn mice_multiplexer_is_tried_before_single_mice() {
+        let paths: Vec<PathBuf> = mousedev_paths().collect();
+        assert_eq!(paths[0], Path::new("/dev/input/mice"));
+        assert_eq!(paths[1], Path::new("/dev/input/mouse0"));
+        assert_eq!(paths.len(), MAX_MOUSE_NODES + 1);
+    }
+
+    #[test]
+    fn extra_mouse_buttons_keep_distinct_names() {
+        assert_eq!(button_name(Key::BTN_MIDDLE).as_deref(), Some("middle"));
+        assert_eq!(button_name(Key::BTN_TASK).as_deref(), Some("task"));
+        assert_eq!(button_name(Key::new(0x118)).as_deref(), Some("button9"));
+        assert_eq!(button_name(Key::BTN_TRIGGER), None);
+        assert_eq!(button_name(Key::KEY_A), None);
+    }
+
+    #[test]
+    fn meta_shift_f_emits_its_shortcut() {
+        let mut state = synced_state();
+        let events = run_frame(
+            &mut state,
+            &[
+                (InputEventKind::Key(Key::KEY_LEFTMETA), 1),
+                (InputEventKind::Key(Key::KEY_LEFTSHIFT), 1),
+                (InputEventKind::Key(Key::KEY_F), 1),
+            ],
+        );
+
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, OutputEvent::Shortcut { name, .. } if name == "toggle_chat")));
+    }
+
+    #[test]
+    fn only_old_motion_is_stale() {
+        use evdev::EventType;
+        let cutoff = SystemTime::now() - STALE_MOTION_GAP;
+        let rel_x = RelativeAxisType::REL_X.0;
+
+        // InputEvent::new stamps the Unix epoch
+        assert!(is_stale_motion(
+            &InputEvent::new(EventType::RELATIVE, rel_x, 5),
+            cutoff
+        ));
+        assert!(!is_stale_motion(
+            &InputEvent::new_now(EventType::RELATIVE, rel_x, 5),
+            cutoff
+        ));
+        assert!(!is_stale_motion(
+            &InputEvent::new(EventType::KEY, Key::KEY_A.code(), 0),
+            cutoff
+        ));
+    }
+
+    #[test]
+    fn a_flooding_device_keeps_only_its_key_releases() {
+        use evdev::EventType;
+        let rel =
+            |axis: RelativeAxisType, value| InputEvent::new(EventType::RELATIVE, axis.0, value);
+        let key = |value| InputEvent::new(EventType::KEY, Key::KEY_LEFTSHIFT.code(), value);
+        let mut events = vec![
+            rel(RelativeAxisType::REL_X, 4),
+            key(1),
+            rel(RelativeAxisType::REL_Y, -2),
+            key(2),
+            rel(RelativeAxisType::REL_X, 1),
+            key(0),
+            InputEvent::new(EventType::SYNCHRONIZATION, 0, 0),
+        ];
+
+        assert_eq!(drop_flooded_events(&mut events), (5, -2));
+        let kept: Vec<_> = events.iter().map(|e| (e.kind(), e.value())).collect();
+        assert_eq!(kept, [(InputEventKind::Key(Key::KEY_LEFTSHIFT), 0)]);
+    }
+
+    #[test]
+    fn keysyms_yield_the_typed_letter_or_digit() {
+        // q/Q on a QWERTY key, a/A where AZERTY moves it
+        assert_eq!(keysym_key(&[0x71, 0x51]), Some(KeyCode::Q));
+        assert_eq!(keysym_key(&[0x61, 0x41]), Some(KeyCode::A));
+        // AZERTY's top row: "&" unshifted, "1" shifted
+        assert_eq!(keysym_key(&[0x26, 0x31]), Some(KeyCode::Digit1));
+        // eacute, 2
+        assert_eq!(keysym_key(&[0xe9, 0x32]), Some(KeyCode::Digit2));
+        assert_eq!(keysym_key(&[0xff0d]), None);
+    }
+
+    #[test]
+    fn wm_class_yields_the_class_name() {
+        assert_eq!(
+            parse_wm_class(b"navigator\0firefox\0").as_deref(),
+            Some("firefox")
+        );
+        assert_eq!(parse_wm_class(b"steam_app_1245620\0"), None);
+        assert_eq!(parse_wm_class(b""), None);
+    }
+
+    /// A device that reads fine until it's unplugged
+    struct FakeDevice {
+        path: String,
+        device_type: DeviceType,
+        unplugged: bool,
+    }
+
+    impl FakeDevice {
+        fn fetch_events(&self) -> std::io::Result<()> {
+            if self.unplugged {
+                Err(std::io::Error::from_raw_os_error(libc::ENODEV))
+            } else {
+                Ok(())
             }
         }
     }
+
+    impl TrackedDevice for FakeDevice {
+        fn path(&self) -> &str {
+            &self.path
+        }
+
+        fn name(&self) -> &str {
+            "Fake device"
+        }
+
+        fn device_type(&self) -> DeviceType {
+            self.device_type
+        }
+
+        fn is_virtual(&self) -> bool {
+            false
+        }
+    }
+
+    /// Event nodes that come and go as the test says
+    #[derive(Default)]
+    struct FakeSource {
+        nodes: HashMap<PathBuf, DeviceType>,
+    }
+
+    impl DeviceSource for FakeSource {
+        type Device = FakeDevice;
+
+        fn event_nodes(&self) -> Vec<PathBuf> {
+            self.nodes.keys().cloned().collect()
+        }
+
+        fn probe(&self, path: &Path) -> std::io::Result<Option<FakeDevice>> {
+            Ok(self.nodes.get(path).map(|&device_type| FakeDevice {
+                path: path.to_string_lossy().to_string(),
+                device_type,
+                unplugged: false,
+            }))
+        }
+    }
+
+    #[test]
+    fn unplugged_device_is_dropped_and_reopened_when_it_returns() {
+        let mouse = PathBuf::from("/dev/input/event3");
+        let keyboard = PathBuf::from("/dev/input/event4");
+        let mut source = FakeSource::default();
+        source.nodes.insert(mouse.clone(), DeviceType::Mouse);
+        source.nodes.insert(keyboard.clone(), DeviceType::Keyboard);
+        let mut devices = Vec::new();
+        let mut ignored = HashSet::new();
+        assert!(rescan_devices(&source, &mut devices, &mut ignored));
+        assert_eq!(devices.len(), 2);
+
+        // The mouse is unplugged: its node goes and its next read fails
+        source.nodes.remove(&mouse);
+        for device in devices.iter_mut() {
+            device.unplugged = Path::new(&device.path) == mouse;
+        }
+        ignored.insert(PathBuf::from("/dev/input/event9"));
+        let gone: Vec<usize> = devices
+            .iter()
+            .enumerate()
+            .filter(|(_, device)| {
+                device
+                    .fetch_events()
+                    .is_err_and(|e| classify_read_error(&e) == ReadFailure::Gone)
+            })
+            .map(|(i, _)| i)
+            .collect();
+        assert!(drop_gone_devices(&mut devices, &gone, &mut ignored));
+        assert_eq!(devices.len(), 1);
+        assert_eq!(Path::new(&devices[0].path), keyboard);
+        assert!(ignored.is_empty());
+
+        // Nothing changes until it's back
+        assert!(!drop_gone_devices(&mut devices, &[], &mut ignored));
+        assert!(!rescan_devices(&source, &mut devices, &mut ignored));
+
+        source.nodes.insert(mouse.clone(), DeviceType::Mouse);
+        assert!(rescan_devices(&source, &mut devices, &mut ignored));
+        assert!(devices
+            .iter()
+            .any(|device| Path::new(&device.path) == mouse && device.fetch_events().is_ok()));
+    }
+
+    #[test]
+    fn unplugged_device_reads_are_fatal() {
+        // What fetch_events returns once the device behind the fd is gone
+        for errno in [libc::ENODEV, libc::EBADF] {
+            let error = std::io::Error::from_raw_os_error(errno);
+            assert_eq!(classify_read_error(&error), ReadFailure::Gone);
+        }
+    }
+
+    #[test]
+    fn empty_and_transient_reads_keep_the_device() {
+        let would_block = std::io::Error::from_raw_os_error(libc::EAGAIN);
+        assert_eq!(classify_read_error(&would_block), ReadFailure::NoEvents);
+
+        let interrupted = std::io::Error::from_raw_os_error(libc::EINTR);
+        assert_eq!(classify_read_error(&interrupted), ReadFailure::Other);
+    }
+
+    #[test]
+    fn screen_backend_names_are_parsed() {
+        assert_eq!(
+            ScreenBackend::from_name("wayland"),
+            Some(ScreenBackend::Wayland)
+        );
+        assert_eq!(
+            ScreenBackend::from_name("xrandr"),
+            Some(ScreenBackend::Randr)
+        );
+        assert_eq!(
+            ScreenBackend::from_name(" X11Query "),
+            Some(ScreenBackend::X11)
+        );
+        assert_eq!(ScreenBackend::from_name("env"), Some(ScreenBackend::Env));
+        assert_eq!(ScreenBackend::from_name("gdi"), None);
+    }
+
+    #[test]
+    fn xrandr_primary_output_is_picked() {
+        let output = "\
+Screen 0: minimum 320 x 200, current 4480 x 1440, maximum 16384 x 16384
+DP-2 connected 1920x1080+2560+0 (normal left inverted right) 527mm x 296mm
+   1920x1080     60.00*+
+HDMI-1 connected primary 2560x1440+0+0 (normal left inverted right) 597mm x 336mm
+   2560x1440     59.95*+
+   1920x1080i    60.00    50.00
+HDMI-2 disconnected (normal left inverted right x axis y axis)
+";
+        let monitors = parse_randr_output(output);
+        assert_eq!(monitors.len(), 2);
+        let primary = primary_monitor(&monitors).unwrap();
+        assert_eq!((primary.width, primary.height), (2560, 1440));
+        assert_eq!((primary.x, primary.y), (0, 0));
+    }
+
+    #[test]
+    fn wlr_randr_current_mode_is_used() {
+        let output = "\
+eDP-1 \"Sharp Corporation 0x1515 (eDP-1)\"
+  Enabled: yes
+  Modes:
+    3840x2160 px, 59.997002 Hz (preferred)
+    1920x1080 px, 60.000000 Hz (current)
+  Position: 0,0
+  Transform: normal
+  Scale: 1.000000
+";
+        let monitors = parse_randr_output(output);
+        let primary = primary_monitor(&monitors).unwrap();
+        assert_eq!((primary.width, primary.height), (1920, 1080));
+        assert_eq!(primary.scale, 1.0);
+    }
+
+    #[test]
+    fn wlr_randr_fractional_scale_is_read() {
+        let output = "\
+eDP-1 \"BOE 0x0BCA (eDP-1)\"
+  Enabled: yes
+  Modes:
+    2880x1800 px, 90.000999 Hz (preferred, current)
+  Position: 0,0
+  Scale: 1.750000
+";
+        let monitors = parse_randr_output(output);
+        assert_eq!(monitors[0].scale, 1.75);
+    }
+
+    #[test]
+    fn garbage_and_interlaced_lines_do_not_break_parsing() {
+        let output = "\
+not randr output at all
++-+-x connected primary x+
+HDMI-1 connected 1920x1080i+0+0
+   1920x1080i    60.00*
+   ???
+";
+        let monitors = parse_randr_output(output);
+        let primary = primary_monitor(&monitors).unwrap();
+        assert_eq!((primary.width, primary.height), (1920, 1080));
+        assert!(parse_randr_output("").is_empty());
+    }
 }
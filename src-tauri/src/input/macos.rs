@@ -1,19 +1,52 @@
 
 #[cfg(target_os = "macos")]
-use crate::shared::{KeyCode, OutputEvent, SharedState};
+use crate::shared::{
+    DeviceInfo, EventSink, KeyCode, Monitors, OutputEvent, Rect, SharedState, MOUSE_BUTTON_MIDDLE,
+    MOUSE_BUTTON_RIGHT,
+};
+#[cfg(target_os = "macos")]
+use core_foundation::base::{CFType, TCFType};
+#[cfg(target_os = "macos")]
+use core_foundation::boolean::CFBoolean;
+#[cfg(target_os = "macos")]
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
 #[cfg(target_os = "macos")]
-use core_foundation::runloop::{kCFRunLoopCommonModes, CFRunLoop};
+use core_foundation::number::CFNumber;
+#[cfg(target_os = "macos")]
+use core_foundation::runloop::{kCFRunLoopCommonModes, kCFRunLoopDefaultMode, CFRunLoop};
+#[cfg(target_os = "macos")]
+use core_foundation::string::{CFString, CFStringRef};
 #[cfg(target_os = "macos")]
 use core_graphics::display::CGDisplay;
 #[cfg(target_os = "macos")]
 use core_graphics::event::{
-    CGEventFlags, CGEventTap, CGEventTapLocation, CGEventTapOptions, CGEventTapPlacement,
-    CGEventType, CGKeyCode, EventField,
+    CGEvent, CGEventFlags, CGEventTap, CGEventTapLocation, CGEventTapOptions,
+    CGEventTapPlacement, CGEventType, CGKeyCode, EventField,
+};
+#[cfg(target_os = "macos")]
+use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+#[cfg(target_os = "macos")]
+use core_graphics::geometry::CGRect;
+#[cfg(target_os = "macos")]
+use core_graphics::window::{
+    copy_window_info, kCGNullWindowID, kCGWindowBounds, kCGWindowLayer,
+    kCGWindowListExcludeDesktopElements, kCGWindowListOptionOnScreenOnly, kCGWindowOwnerName,
+    kCGWindowOwnerPID,
 };
 #[cfg(target_os = "macos")]
-use std::sync::Arc;
+use std::collections::HashMap;
+#[cfg(target_os = "macos")]
+use std::ffi::c_void;
+#[cfg(target_os = "macos")]
+use std::ptr;
 #[cfg(target_os = "macos")]
-use tauri::{AppHandle, Emitter};
+use std::sync::atomic::{AtomicPtr, Ordering};
+#[cfg(target_os = "macos")]
+use std::sync::{Arc, Once};
+#[cfg(target_os = "macos")]
+use std::thread;
+#[cfg(target_os = "macos")]
+use std::time::{Duration, Instant};
 
 #[cfg(target_os = "macos")]
 pub fn detect_screen_size() -> (i32, i32) {
@@ -21,16 +54,153 @@ pub fn detect_screen_size() -> (i32, i32) {
     (display.pixels_wide() as i32, display.pixels_high() as i32)
 }
 
+#[cfg(target_os = "macos")]
+pub fn detect_monitors() -> Monitors {
+    // Display bounds are in the same global, top-left-origin space as
+    // event locations
+    let rects: Vec<Rect> = CGDisplay::active_displays()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|id| {
+            let bounds = CGDisplay::new(id).bounds();
+            Rect {
+                x: bounds.origin.x as i32,
+                y: bounds.origin.y as i32,
+                width: bounds.size.width as i32,
+                height: bounds.size.height as i32,
+            }
+        })
+        .collect();
+
+    if rects.is_empty() {
+        let (width, height) = detect_screen_size();
+        return Monitors::single(width, height);
+    }
+    Monitors { rects }
+}
+
 #[cfg(target_os = "macos")]
 pub fn check_fullscreen() -> bool {
-    // Basic heuristic: Check if the frontmost app has a window covering the screen
-    // For MVP, we'll default to false.
+    // Window info comes ordered front to back. The first regular (layer 0)
+    // window that isn't ours belongs to the frontmost app; the menu bar, Dock
+    // and overlays all sit on higher layers.
+    let options = kCGWindowListOptionOnScreenOnly | kCGWindowListExcludeDesktopElements;
+    let Some(windows) = copy_window_info(options, kCGNullWindowID) else {
+        return false;
+    };
+    let own_pid = std::process::id() as i64;
+
+    for item in windows.iter() {
+        let info: CFDictionary<CFString, CFType> =
+            unsafe { CFDictionary::wrap_under_get_rule(*item as CFDictionaryRef) };
+        let number = |key: CFStringRef| {
+            info.find(key)
+                .and_then(|value| value.downcast::<CFNumber>())
+                .and_then(|number| number.to_i64())
+        };
+        let (layer, owner) = unsafe { (number(kCGWindowLayer), number(kCGWindowOwnerPID)) };
+        if layer != Some(0) || owner == Some(own_pid) {
+            continue;
+        }
+
+        let bounds = unsafe { info.find(kCGWindowBounds) }
+            .and_then(|value| value.downcast::<CFDictionary>())
+            .and_then(|dict| CGRect::from_dict_representation(&dict));
+        let Some(window) = bounds else {
+            return false;
+        };
+
+        // Fullscreen if it covers an entire display (bounds are in points,
+        // the same global space as the display bounds)
+        let displays = CGDisplay::active_displays().unwrap_or_default();
+        return displays.into_iter().any(|id| {
+            let display = CGDisplay::new(id).bounds();
+            window.origin.x <= display.origin.x
+                && window.origin.y <= display.origin.y
+                && window.origin.x + window.size.width >= display.origin.x + display.size.width
+                && window.origin.y + window.size.height >= display.origin.y + display.size.height
+        });
+    }
     false
 }
 
+/// Name of the app owning the frontmost regular window, found the same way
+/// as in `check_fullscreen`
+#[cfg(target_os = "macos")]
+pub fn foreground_app() -> Option<String> {
+    let options = kCGWindowListOptionOnScreenOnly | kCGWindowListExcludeDesktopElements;
+    let windows = copy_window_info(options, kCGNullWindowID)?;
+    let own_pid = std::process::id() as i64;
+
+    for item in windows.iter() {
+        let info: CFDictionary<CFString, CFType> =
+            unsafe { CFDictionary::wrap_under_get_rule(*item as CFDictionaryRef) };
+        let number = |key: CFStringRef| {
+            info.find(key)
+                .and_then(|value| value.downcast::<CFNumber>())
+                .and_then(|number| number.to_i64())
+        };
+        let (layer, owner) = unsafe { (number(kCGWindowLayer), number(kCGWindowOwnerPID)) };
+        if layer != Some(0) || owner == Some(own_pid) {
+            continue;
+        }
+        return unsafe { info.find(kCGWindowOwnerName) }
+            .and_then(|value| value.downcast::<CFString>())
+            .map(|name| name.to_string());
+    }
+    None
+}
+
+/// Layout-aware shortcut keys aren't supported yet: the tap reports
+/// physical key codes and shortcuts always match those
+#[cfg(target_os = "macos")]
+pub fn keyboard_layout() -> HashMap<KeyCode, KeyCode> {
+    HashMap::new()
+}
+
 #[link(name = "CoreFoundation", kind = "framework")]
 extern "C" {
-    fn CFRunLoopRun();
+    fn CFRunLoopStop(run_loop: *mut c_void);
+    fn CFMachPortInvalidate(port: *mut c_void);
+}
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXIsProcessTrusted() -> bool;
+    fn AXIsProcessTrustedWithOptions(options: CFDictionaryRef) -> bool;
+    fn CGEventTapEnable(tap: *mut c_void, enable: bool);
+    static kAXTrustedCheckOptionPrompt: CFStringRef;
+}
+
+#[cfg(target_os = "macos")]
+pub fn has_input_permission() -> bool {
+    // The event tap only receives events once Accessibility access is granted
+    unsafe { AXIsProcessTrusted() }
+}
+
+/// The event tap sees every mouse and keyboard at once, so there is one
+/// entry for each
+#[cfg(target_os = "macos")]
+pub fn list_devices() -> Vec<DeviceInfo> {
+    let readable = has_input_permission();
+    ["mouse", "keyboard"]
+        .into_iter()
+        .map(|kind| DeviceInfo {
+            path: "CGEventTap".to_string(),
+            name: format!("All {}s", kind),
+            kind: kind.to_string(),
+            readable,
+        })
+        .collect()
+}
+
+/// Like `has_input_permission`, but if access is missing also asks macOS to
+/// show its prompt pointing the user at the Accessibility settings
+#[cfg(target_os = "macos")]
+fn request_accessibility() -> bool {
+    let key = unsafe { CFString::wrap_under_get_rule(kAXTrustedCheckOptionPrompt) };
+    let options = CFDictionary::from_CFType_pairs(&[(key, CFBoolean::true_value())]);
+    unsafe { AXIsProcessTrustedWithOptions(options.as_concrete_TypeRef()) }
 }
 
 #[cfg(target_os = "macos")]
@@ -38,11 +208,54 @@ fn map_keycode(code: CGKeyCode) -> Option<KeyCode> {
     // Mapping specific macOS keycodes to our shared KeyCode enum
     // Reference: https://github.com/phracker/MacOSX-SDKs/blob/master/MacOSX10.6.sdk/System/Library/Frameworks/Carbon.framework/Versions/A/Frameworks/HIToolbox.framework/Versions/A/Headers/Events.h
     match code {
-        0x00 => Some(KeyCode::A),
-        0x01 => Some(KeyCode::S),
-        0x02 => Some(KeyCode::D),
-        0x03 => Some(KeyCode::F),
-        0x08 => Some(KeyCode::C),
+        0x00 => Some(KeyCode::A),       // kVK_ANSI_A
+        0x01 => Some(KeyCode::S),       // kVK_ANSI_S
+        0x02 => Some(KeyCode::D),       // kVK_ANSI_D
+        0x03 => Some(KeyCode::F),       // kVK_ANSI_F
+        0x04 => Some(KeyCode::H),       // kVK_ANSI_H
+        0x05 => Some(KeyCode::G),       // kVK_ANSI_G
+        0x06 => Some(KeyCode::Z),       // kVK_ANSI_Z
+        0x07 => Some(KeyCode::X),       // kVK_ANSI_X
+        0x08 => Some(KeyCode::C),       // kVK_ANSI_C
+        0x09 => Some(KeyCode::V),       // kVK_ANSI_V
+        0x0B => Some(KeyCode::B),       // kVK_ANSI_B
+        0x0C => Some(KeyCode::Q),       // kVK_ANSI_Q
+        0x0D => Some(KeyCode::W),       // kVK_ANSI_W
+        0x0E => Some(KeyCode::E),       // kVK_ANSI_E
+        0x0F => Some(KeyCode::R),       // kVK_ANSI_R
+        0x10 => Some(KeyCode::Y),       // kVK_ANSI_Y
+        0x11 => Some(KeyCode::T),       // kVK_ANSI_T
+        0x12 => Some(KeyCode::Digit1),  // kVK_ANSI_1
+        0x13 => Some(KeyCode::Digit2),  // kVK_ANSI_2
+        0x14 => Some(KeyCode::Digit3),  // kVK_ANSI_3
+        0x15 => Some(KeyCode::Digit4),  // kVK_ANSI_4
+        0x16 => Some(KeyCode::Digit6),  // kVK_ANSI_6
+        0x17 => Some(KeyCode::Digit5),  // kVK_ANSI_5
+        0x19 => Some(KeyCode::Digit9),  // kVK_ANSI_9
+        0x1A => Some(KeyCode::Digit7),  // kVK_ANSI_7
+        0x1C => Some(KeyCode::Digit8),  // kVK_ANSI_8
+        0x1D => Some(KeyCode::Digit0),  // kVK_ANSI_0
+        0x1F => Some(KeyCode::O),       // kVK_ANSI_O
+        0x20 => Some(KeyCode::U),       // kVK_ANSI_U
+        0x22 => Some(KeyCode::I),       // kVK_ANSI_I
+        0x23 => Some(KeyCode::P),       // kVK_ANSI_P
+        0x25 => Some(KeyCode::L),       // kVK_ANSI_L
+        0x26 => Some(KeyCode::J),       // kVK_ANSI_J
+        0x28 => Some(KeyCode::K),       // kVK_ANSI_K
+        0x2D => Some(KeyCode::N),       // kVK_ANSI_N
+        0x2E => Some(KeyCode::M),       // kVK_ANSI_M
+        0x60 => Some(KeyCode::F5),      // kVK_F5
+        0x61 => Some(KeyCode::F6),      // kVK_F6
+        0x62 => Some(KeyCode::F7),      // kVK_F7
+        0x63 => Some(KeyCode::F3),      // kVK_F3
+        0x64 => Some(KeyCode::F8),      // kVK_F8
+        0x65 => Some(KeyCode::F9),      // kVK_F9
+        0x67 => Some(KeyCode::F11),     // kVK_F11
+        0x6D => Some(KeyCode::F10),     // kVK_F10
+        0x6F => Some(KeyCode::F12),     // kVK_F12
+        0x76 => Some(KeyCode::F4),      // kVK_F4
+        0x78 => Some(KeyCode::F2),      // kVK_F2
+        0x7A => Some(KeyCode::F1),      // kVK_F1
         
         0x38 => Some(KeyCode::LeftShift),  // kVK_Shift
         0x3C => Some(KeyCode::RightShift), // kVK_RightShift
@@ -57,26 +270,162 @@ fn map_keycode(code: CGKeyCode) -> Option<KeyCode> {
     }
 }
 
+/// Run loop of the input thread while the event tap is installed, so
+/// wake_input_loop can stop it from another thread
 #[cfg(target_os = "macos")]
-pub fn run_input_loop(app_handle: AppHandle, shared_state: Arc<SharedState>) {
-    let (width, height) = detect_screen_size();
+static INPUT_RUN_LOOP: AtomicPtr<c_void> = AtomicPtr::new(ptr::null_mut());
+
+/// How long the run loop runs before checking for a shutdown, in case the
+/// stop request arrives before the loop is running
+#[cfg(target_os = "macos")]
+const SHUTDOWN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Stops the input thread's run loop so it can remove the event tap.
+/// CFRunLoopStop is safe to call from any thread.
+#[cfg(target_os = "macos")]
+pub fn wake_input_loop() {
+    let run_loop = INPUT_RUN_LOOP.load(Ordering::Acquire);
+    if !run_loop.is_null() {
+        unsafe { CFRunLoopStop(run_loop); }
+    }
+}
+
+/// Current pointer position in global points, read from a null CGEvent.
+/// None if no event source is available (e.g. in a locked session).
+#[cfg(target_os = "macos")]
+fn cursor_location() -> Option<(i32, i32)> {
+    let source = CGEventSource::new(CGEventSourceStateID::CombinedSessionState).ok()?;
+    let point = CGEvent::new(source).ok()?.location();
+    Some((point.x as i32, point.y as i32))
+}
+
+/// Spawns the idle watcher once per process
+#[cfg(target_os = "macos")]
+static IDLE_WATCHER: Once = Once::new();
+
+#[cfg(target_os = "macos")]
+pub fn run_input_loop(sink: Arc<dyn EventSink>, shared_state: Arc<SharedState>) {
+    let (width, height, monitors) = {
+        let input_state = shared_state.input_state.lock().unwrap();
+        (input_state.screen_width, input_state.screen_height, input_state.monitors.rects.clone())
+    };
+
+    // Start at the real pointer rather than the screen center, so the
+    // character is aligned before the first move arrives
+    let synced = match cursor_location() {
+        Some((x, y)) => {
+            shared_state.input_state.lock().unwrap().set_cursor_absolute(x, y);
+            true
+        }
+        None => {
+            log::warn!("[macOS Input] Could not read the cursor position, starting at screen center");
+            false
+        }
+    };
     
-    let _ = app_handle.emit(
+    shared_state.emit(
+        &sink,
         "ready",
         OutputEvent::Ready {
             mice_count: 1, // macOS abstracts this
             keyboards_count: 1,
+            device_names: Vec::new(),
             screen_width: width,
             screen_height: height,
+            monitors,
+            scale_factor: 1.0, // Display bounds and the cursor are both in points
         },
     );
 
-    println!("[macOS Input] Starting Input Tap...");
-    println!("[macOS Input] NOTE: Accessibility Permissions are REQUIRED for this to work.");
+    // The run loop below only wakes for input, so watch for idleness from
+    // a separate thread. It runs until shutdown, so a restart after a panic
+    // doesn't start another one.
+    IDLE_WATCHER.call_once(|| {
+        let sink = sink.clone();
+        let shared_state = shared_state.clone();
+        thread::spawn(move || {
+            while !shared_state.is_shutting_down() {
+                thread::sleep(Duration::from_secs(1));
+                shared_state.check_idle(&sink);
+            }
+        });
+    });
+
+    if synced {
+        let mut input_state = shared_state.input_state.lock().unwrap();
+        shared_state.emit_cursor(&sink, &mut input_state);
+    }
+
+    log::info!("[macOS Input] Starting Input Tap...");
+    log::info!("[macOS Input] NOTE: Accessibility Permissions are REQUIRED for this to work.");
+
+    // Without Accessibility access the tap can't be created (or receives
+    // nothing). Tell the user how to fix it and keep retrying, so granting
+    // access while the app runs starts tracking without a restart.
+    let mut reported = false;
+    if !request_accessibility() {
+        shared_state.emit_error(&sink, ACCESSIBILITY_ERROR);
+        reported = true;
+    }
+    let tap = loop {
+        if shared_state.is_shutting_down() {
+            return;
+        }
+        if has_input_permission() {
+            match create_event_tap(sink.clone(), shared_state.clone()) {
+                Ok(tap) => break tap,
+                Err(()) => log::warn!("[macOS Input] Could not create the event tap, retrying"),
+            }
+        }
+        if !reported {
+            shared_state.emit_error(&sink, ACCESSIBILITY_ERROR);
+            reported = true;
+        }
+        thread::sleep(TAP_RETRY_INTERVAL);
+    };
+
+    let source = tap.mach_port.clone();
+    let loop_source = source.create_runloop_source(0).expect("Failed to create runloop source");
+    
+    let current_loop = CFRunLoop::get_current();
+    unsafe {
+        current_loop.add_source(&loop_source, kCFRunLoopCommonModes);
+    }
+    
+    tap.enable();
+    
+    // Run until stop_input_loop; run_in_mode also returns every
+    // SHUTDOWN_CHECK_INTERVAL so a missed stop only delays the exit
+    INPUT_RUN_LOOP.store(current_loop.as_concrete_TypeRef() as *mut c_void, Ordering::Release);
+    while !shared_state.is_shutting_down() {
+        CFRunLoop::run_in_mode(unsafe { kCFRunLoopDefaultMode }, SHUTDOWN_CHECK_INTERVAL, false);
+    }
+    INPUT_RUN_LOOP.store(ptr::null_mut(), Ordering::Release);
+    
+    // Tear the tap down so no system-wide tap outlives the app
+    let port = tap.mach_port.as_concrete_TypeRef() as *mut c_void;
+    unsafe {
+        CGEventTapEnable(port, false);
+        current_loop.remove_source(&loop_source, kCFRunLoopCommonModes);
+        CFMachPortInvalidate(port);
+    }
+    log::info!("[macOS Input] Event tap removed");
+}
 
-    // Create an event tap to monitor global input
+/// Shown to the user while Accessibility access is missing
+#[cfg(target_os = "macos")]
+const ACCESSIBILITY_ERROR: &str = "Accessibility access is required to follow the mouse. \
+Open System Settings > Privacy & Security > Accessibility and enable AI Character Assistant.";
 
-    let tap = match CGEventTap::new(
+/// How often tap creation is retried while Accessibility access is missing
+#[cfg(target_os = "macos")]
+const TAP_RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Creates an event tap monitoring global input. Fails without
+/// Accessibility access.
+#[cfg(target_os = "macos")]
+fn create_event_tap(sink: Arc<dyn EventSink>, shared_state: Arc<SharedState>) -> Result<CGEventTap<'static>, ()> {
+    CGEventTap::new(
         CGEventTapLocation::HID,
         CGEventTapPlacement::HeadInsertEventTap,
         CGEventTapOptions::ListenOnly,
@@ -85,55 +434,87 @@ pub fn run_input_loop(app_handle: AppHandle, shared_state: Arc<SharedState>) {
             CGEventType::KeyUp,
             CGEventType::FlagsChanged,
             CGEventType::MouseMoved,
+            CGEventType::LeftMouseDragged,
+            CGEventType::RightMouseDragged,
+            CGEventType::OtherMouseDragged,
             CGEventType::LeftMouseDown,
             CGEventType::LeftMouseUp,
             CGEventType::RightMouseDown,
             CGEventType::RightMouseUp,
             CGEventType::OtherMouseDown,
             CGEventType::OtherMouseUp,
+            CGEventType::ScrollWheel,
         ],
         move |_proxy, type_, event| {
             // Callback context
             let mut input_state = shared_state.input_state.lock().unwrap();
             
             match type_ {
-                CGEventType::MouseMoved => {
+                // While a button is held macOS sends *MouseDragged instead of MouseMoved
+                CGEventType::MouseMoved
+                | CGEventType::LeftMouseDragged
+                | CGEventType::RightMouseDragged
+                | CGEventType::OtherMouseDragged => {
                     let point = event.location();
                     let x = point.x as i32;
                     let y = point.y as i32;
                     
-                    if input_state.update_cursor(0, 0) || x != input_state.cursor_x || y != input_state.cursor_y {
-                        input_state.cursor_x = x;
-                        input_state.cursor_y = y;
-                        input_state.last_reported_x = x;
-                        input_state.last_reported_y = y;
-                        
-                        let _ = app_handle.emit(
-                            "cursor-pos",
-                            OutputEvent::Cursor { x, y },
-                        );
+                    if input_state.set_cursor_absolute(x, y) {
+                        shared_state.emit_cursor(&sink, &mut input_state);
+                        shared_state.emit_drag_motion(&sink, &mut input_state);
                     }
                 }
                 CGEventType::LeftMouseDown | CGEventType::RightMouseDown | CGEventType::OtherMouseDown => {
                      let button = match type_ {
-                         CGEventType::LeftMouseDown => "left",
-                         CGEventType::RightMouseDown => "right",
-                         _ => "middle",
+                         CGEventType::LeftMouseDown => "left".to_string(),
+                         CGEventType::RightMouseDown => "right".to_string(),
+                         // Other buttons are numbered: 2 = middle, 3 = back, 4 = forward,
+                         // and any beyond (e.g. tilt-click) keep their own number
+                         _ => match event.get_integer_value_field(EventField::MOUSE_EVENT_BUTTON_NUMBER) {
+                             2 => "middle".to_string(),
+                             3 => "back".to_string(),
+                             4 => "forward".to_string(),
+                             number => format!("button{}", number + 1),
+                         },
                      };
+                     match button.as_str() {
+                         "left" => input_state.left_button_down(Instant::now()),
+                         "right" => input_state.set_mouse_button(MOUSE_BUTTON_RIGHT, true),
+                         "middle" => input_state.set_mouse_button(MOUSE_BUTTON_MIDDLE, true),
+                         _ => {}
+                     }
                      
-                     let _ = app_handle.emit(
-                         "click",
-                         OutputEvent::Click {
-                             button: button.into(),
-                             x: input_state.cursor_x,
-                             y: input_state.cursor_y,
-                         },
-                     );
-                     let _ = app_handle.emit("activity", OutputEvent::Activity);
+                     shared_state.emit_click(&sink, &mut input_state, "click", &button);
+                     shared_state.emit(&sink, "activity", OutputEvent::Activity);
+                }
+                CGEventType::LeftMouseUp => {
+                     shared_state.release_left_button(&sink, &mut input_state);
+                }
+                CGEventType::RightMouseUp => {
+                     input_state.set_mouse_button(MOUSE_BUTTON_RIGHT, false);
+                }
+                CGEventType::OtherMouseUp => {
+                     // Same numbering as OtherMouseDown
+                     let number = event.get_integer_value_field(EventField::MOUSE_EVENT_BUTTON_NUMBER);
+                     if number == 2 {
+                         input_state.set_mouse_button(MOUSE_BUTTON_MIDDLE, false);
+                     }
+                }
+                CGEventType::ScrollWheel => {
+                     // Axis 1 is vertical (positive = up), axis 2 horizontal (positive = left)
+                     let dy = event.get_integer_value_field(EventField::SCROLL_WHEEL_EVENT_DELTA_AXIS_1) as i32;
+                     let dx = -(event.get_integer_value_field(EventField::SCROLL_WHEEL_EVENT_DELTA_AXIS_2) as i32);
+                     if dx != 0 || dy != 0 {
+                         shared_state.emit(&sink, "scroll", OutputEvent::Scroll { dx, dy });
+                         shared_state.emit(&sink, "activity", OutputEvent::Activity);
+                     }
                 }
 
                 CGEventType::KeyDown | CGEventType::FlagsChanged => {
                     let code = event.get_integer_value_field(EventField::KEYBOARD_EVENT_KEYCODE) as u16;
+
+                    // Caps Lock toggles, so record whether it's on rather than tracking its key
+                    input_state.set_lock(KeyCode::CapsLock, event.get_flags().contains(CGEventFlags::CGEventFlagAlphaShift));
                     
                     if let Some(key) = map_keycode(code) {
                          // Use match instead of == for CGEventType check to avoid PartialEq issues
@@ -152,48 +533,41 @@ pub fn run_input_loop(app_handle: AppHandle, shared_state: Arc<SharedState>) {
                         };
                         
                         if is_down {
-                            input_state.held_modifiers.insert(key);
-                            if let Some(shortcut) = input_state.check_shortcut(key) {
-                                let _ = app_handle.emit(
-                                    "shortcut",
-                                    OutputEvent::Shortcut { name: shortcut.to_string() },
-                                );
+                            // Held keys auto-repeat; only report the initial press
+                            if input_state.held_modifiers.insert(key) {
+                                shared_state.emit_key(&sink, &input_state, key, true);
                             }
-                             let _ = app_handle.emit("activity", OutputEvent::Activity);
+                            if let Some(shortcut) = input_state.press_shortcut_key(key) {
+                                shared_state.emit_shortcut(&sink, &input_state, &shortcut);
+                            }
+                             shared_state.emit(&sink, "activity", OutputEvent::Activity);
                         } else {
-                             input_state.held_modifiers.remove(&key);
+                             if input_state.held_modifiers.remove(&key) {
+                                 shared_state.emit_key(&sink, &input_state, key, false);
+                             }
+                             input_state.shortcut_key_released(key);
+                        }
+                        if let Some(shortcut) = input_state.track_modifier_tap(key, is_down, Instant::now()) {
+                            shared_state.emit_shortcut(&sink, &input_state, &shortcut);
                         }
+                    } else if matches!(type_, CGEventType::KeyDown) {
+                        // Any other key breaks a modifier double-tap
+                        input_state.track_modifier_tap(KeyCode::Unknown, true, Instant::now());
                     }
                 }
                 CGEventType::KeyUp => {
                      let code = event.get_integer_value_field(EventField::KEYBOARD_EVENT_KEYCODE) as u16;
                      if let Some(key) = map_keycode(code) {
-                         input_state.held_modifiers.remove(&key);
+                         if input_state.held_modifiers.remove(&key) {
+                             shared_state.emit_key(&sink, &input_state, key, false);
+                         }
+                         input_state.shortcut_key_released(key);
+                         input_state.track_modifier_tap(key, false, Instant::now());
                      }
                 }
                 _ => {}
             }
             Some(event.to_owned())
         },
-    ) {
-        Ok(tap) => tap,
-        Err(e) => {
-            eprintln!("Error creating Event Tap: {:?}", e);
-            eprintln!("Make sure Accessibility Permissions are granted!");
-            return;
-        }
-    };
-
-    let source = tap.mach_port.clone();
-    let loop_source = source.create_runloop_source(0).expect("Failed to create runloop source");
-    
-    let current_loop = CFRunLoop::get_current();
-    unsafe {
-        current_loop.add_source(&loop_source, kCFRunLoopCommonModes);
-    }
-    
-    tap.enable();
-    
-    unsafe { CFRunLoopRun(); }
+    )
 }
-
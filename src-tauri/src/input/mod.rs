@@ -21,8 +21,16 @@
 // REQUIRED INTERFACE:
 // Each OS module (linux.rs, windows.rs) must implement:
 // - detect_screen_size() -> (i32, i32)
+// - detect_monitors() -> Monitors
 // - check_fullscreen() -> bool
-// - run_input_loop(app_handle: AppHandle, shared_state: Arc<SharedState>)
+// - foreground_app() -> Option<String>
+// - keyboard_layout() -> HashMap<KeyCode, KeyCode>
+// - has_input_permission() -> bool
+// - list_devices() -> Vec<DeviceInfo>
+// - run_input_loop(sink: Arc<dyn EventSink>, shared_state: Arc<SharedState>)
+// - wake_input_loop() (unblocks run_input_loop so it can see a shutdown;
+//   on Linux also used to apply changed settings)
+// - enable_dpi_awareness() (Windows only)
 //
 // =============================================================================
 
@@ -58,9 +66,12 @@ use windows as backend;
 use macos as backend;
 
 // Re-export types needed by main.rs
-use crate::shared::SharedState;
+use crate::shared::{DeviceInfo, EventSink, Monitors, SharedState};
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::Arc;
-use tauri::AppHandle;
+use std::thread;
+use std::time::Duration;
 
 // =============================================================================
 // Unified Public API
@@ -70,7 +81,9 @@ use tauri::AppHandle;
 /// Detects the primary screen resolution.
 ///
 /// # Platform Specific Behavior
-/// - **Linux**: Queries X11 using `xrandr` command
+/// - **Linux**: `SCREEN_WIDTH`/`SCREEN_HEIGHT` if set, else the Wayland
+///   outputs, `wlr-randr`/`xrandr`, or the X11 root window, in that order.
+///   `FOXY_SCREEN_BACKEND` (`env`, `wayland`, `xrandr`, `x11query`) forces one.
 /// - **Windows**: Uses `GetSystemMetrics` Win32 API
 ///
 /// # Returns
@@ -79,11 +92,30 @@ pub fn detect_screen_size() -> (i32, i32) {
     backend::detect_screen_size()
 }
 
+/// Lists every connected monitor in global screen coordinates.
+///
+/// # Platform Specific Behavior
+/// - **Linux**: Same sources as `detect_screen_size()`, falling back to a
+///   single 1920x1080 monitor
+/// - **Windows**: Enumerates monitors with `EnumDisplayMonitors`
+/// - **macOS**: Reads the bounds of every active `CGDisplay`
+pub fn detect_monitors() -> Monitors {
+    backend::detect_monitors()
+}
+
+/// Makes the process per-monitor DPI aware, so Win32 reports real pixel
+/// positions on scaled displays. Must run before any window is created.
+#[cfg(target_os = "windows")]
+pub fn enable_dpi_awareness() {
+    backend::enable_dpi_awareness()
+}
+
 /// Checks if any application is currently running in full-screen mode.
 ///
 /// # Platform Specific Behavior
 /// - **Linux**: Checks X11 window properties for `_NET_WM_STATE_FULLSCREEN`
 /// - **Windows**: Queries foreground window and compares size to screen size
+/// - **macOS**: Checks whether the frontmost app's window covers a display
 ///
 /// # Returns
 /// `true` if a fullscreen app is detected, `false` otherwise
@@ -91,6 +123,72 @@ pub fn check_fullscreen() -> bool {
     backend::check_fullscreen()
 }
 
+/// How often the foreground app is re-queried
+const FOREGROUND_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Names the app in the foreground, for per-app shortcut profiles.
+///
+/// # Platform Specific Behavior
+/// - **Linux**: The X11 `WM_CLASS` class of `_NET_ACTIVE_WINDOW` (XWayland
+///   windows only under Wayland)
+/// - **Windows**: The foreground window's process name, without `.exe`
+/// - **macOS**: The owner of the frontmost regular window
+pub fn foreground_app() -> Option<String> {
+    backend::foreground_app()
+}
+
+/// Re-queries the foreground app every FOREGROUND_POLL_INTERVAL and
+/// records it, emitting ForegroundApp on changes. The keyboard layout is
+/// re-read on the same schedule, since switching layouts usually goes
+/// with switching apps. Blocks until shutdown, so run it on its own thread.
+pub fn watch_foreground_app(sink: &dyn EventSink, shared_state: &SharedState) {
+    while !shared_state.is_shutting_down() {
+        shared_state.set_foreground_app(sink, foreground_app());
+        refresh_keyboard_layout(shared_state);
+        thread::sleep(FOREGROUND_POLL_INTERVAL);
+    }
+}
+
+/// Re-reads the keyboard layout for ShortcutKeyMode::Logical
+fn refresh_keyboard_layout(shared_state: &SharedState) {
+    let layout = backend::keyboard_layout();
+    let mut input_state = shared_state.input_state.lock().unwrap();
+    if input_state.keyboard_layout != layout {
+        log::debug!(
+            "[Input] Keyboard layout moves {} keys",
+            layout
+                .iter()
+                .filter(|(physical, logical)| physical != logical)
+                .count()
+        );
+        input_state.keyboard_layout = layout;
+    }
+}
+
+/// Checks whether the process is allowed to read global input at all.
+///
+/// # Platform Specific Behavior
+/// - **Linux**: At least one `/dev/input/event*` node can be opened for reading
+///   (usually requires membership in the `input` group)
+/// - **Windows**: Always `true` (Raw Input needs no special permission)
+/// - **macOS**: The app has been granted Accessibility permission
+pub fn has_input_permission() -> bool {
+    backend::has_input_permission()
+}
+
+/// Lists the input devices the backend reads, and whether each can be read.
+///
+/// # Platform Specific Behavior
+/// - **Linux**: The `/dev/input/event*` mice, keyboards, touchscreens and
+///   tablets the running input loop has open. Before it starts, the ones it
+///   would use, each opened and probed with a read
+/// - **Windows**: One entry each for mice and keyboards (Raw Input)
+/// - **macOS**: One entry each for mice and keyboards, readable once
+///   Accessibility permission is granted
+pub fn list_devices() -> Vec<DeviceInfo> {
+    backend::list_devices()
+}
+
 /// Starts the main input event loop on a background thread.
 /// This loop continuously monitors mouse and keyboard input and emits
 /// events to the frontend via Tauri's event system.
@@ -100,11 +198,104 @@ pub fn check_fullscreen() -> bool {
 /// - **Windows**: Creates a hidden message window and registers for Raw Input
 ///
 /// # Arguments
-/// * `app_handle` - Tauri application handle for emitting events
+/// * `sink` - Where events go: the Tauri app handle, or StdoutSink when
+///   running headless with `--json-stdout`
 /// * `shared_state` - Thread-safe shared state for cursor tracking
 ///
+/// If the backend panics (e.g. on a malformed event), the error is reported
+/// on the "error" channel and the loop restarts after INPUT_RESTART_DELAY,
+/// up to MAX_INPUT_RESTARTS times.
+///
 /// # Note
 /// This function blocks indefinitely and should be called from a spawned thread.
-pub fn run_input_loop(app_handle: AppHandle, shared_state: Arc<SharedState>) {
-    backend::run_input_loop(app_handle, shared_state)
+pub fn run_input_loop(sink: Arc<dyn EventSink>, shared_state: Arc<SharedState>) {
+    let mut restarts = 0;
+    loop {
+        refresh_keyboard_layout(&shared_state);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            backend::run_input_loop(sink.clone(), shared_state.clone())
+        }));
+        let Err(payload) = result else {
+            return;
+        };
+
+        let message = panic_message(payload.as_ref());
+        shared_state.recover_from_panic();
+        if shared_state.is_shutting_down() {
+            return;
+        }
+        if restarts == MAX_INPUT_RESTARTS {
+            shared_state.emit_error(
+                &sink,
+                format!("Input tracking stopped after repeated crashes: {}", message),
+            );
+            return;
+        }
+        restarts += 1;
+        shared_state.emit_error(
+            &sink,
+            format!(
+                "Input tracking crashed ({}); restarting ({}/{})",
+                message, restarts, MAX_INPUT_RESTARTS
+            ),
+        );
+        thread::sleep(INPUT_RESTART_DELAY);
+    }
+}
+
+/// How many times a panicking input loop is restarted before giving up
+const MAX_INPUT_RESTARTS: u32 = 3;
+
+/// Pause before restarting a panicked input loop, so a device that keeps
+/// sending the same bad event doesn't spin
+const INPUT_RESTART_DELAY: Duration = Duration::from_secs(2);
+
+/// The message a panic was raised with
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+/// Stops the input loop started by `run_input_loop`, e.g. when the app is
+/// closing, so the input thread doesn't outlive the window.
+///
+/// # Platform Specific Behavior
+/// - **Linux**: Wakes the poll loop through its wake-up pipe; it sees the
+///   flag and returns, closing all devices
+/// - **Windows**: Closes the hidden input window, which ends the message loop
+/// - **macOS**: Stops the input thread's run loop, which then disables and
+///   removes the event tap
+pub fn stop_input_loop(shared_state: &SharedState) {
+    shared_state.request_shutdown();
+    backend::wake_input_loop();
+}
+
+/// Makes the input loop pick up changed settings (e.g. `consumeShortcuts`)
+/// right away. Only the Linux loop sleeps between events, for up to
+/// `maxPollTimeoutMs` while idle; the other backends react to the next
+/// input anyway, and on them waking means stopping.
+pub fn refresh_input_loop() {
+    #[cfg(target_os = "linux")]
+    backend::wake_input_loop();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    /// The Windows backend used to exist twice (src/windows_input.rs and
+    /// src/input/windows.rs) and the copies drifted apart. It must only live
+    /// in this module so every fix lands in the code that actually runs.
+    #[test]
+    fn windows_backend_has_a_single_implementation() {
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        assert!(
+            !src.join("windows_input.rs").exists(),
+            "src/windows_input.rs is back; keep the Windows backend in src/input/windows.rs only"
+        );
+    }
 }
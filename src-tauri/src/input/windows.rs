@@ -1,33 +1,67 @@
 #[cfg(target_os = "windows")]
-use crate::shared::{KeyCode, OutputEvent, SharedState};
+use crate::shared::{
+    DeviceInfo, EventRateLimiter, EventSink, KeyCode, Monitors, OutputEvent, Rect, SharedState,
+    MOUSE_BUTTON_MIDDLE, MOUSE_BUTTON_RIGHT,
+};
+#[cfg(target_os = "windows")]
+use std::cell::{Cell, RefCell};
+#[cfg(target_os = "windows")]
+use std::collections::{HashMap, HashSet};
+#[cfg(target_os = "windows")]
 #[cfg(target_os = "windows")]
+use std::sync::atomic::{AtomicIsize, Ordering};
 #[cfg(target_os = "windows")]
 use std::sync::Arc;
 #[cfg(target_os = "windows")]
-use tauri::{AppHandle, Emitter, Manager};
+use std::time::Instant;
 #[cfg(target_os = "windows")]
-use windows::core::s;
+use tauri::WebviewWindow;
+#[cfg(target_os = "windows")]
+use windows::core::{s, PWSTR};
 #[cfg(target_os = "windows")]
 use windows::Win32::Foundation::POINT;
 #[cfg(target_os = "windows")]
-use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows::Win32::Foundation::{CloseHandle, GetLastError, ERROR_CLASS_ALREADY_EXISTS, HANDLE};
+#[cfg(target_os = "windows")]
+use windows::Win32::Foundation::{BOOL, HWND, LPARAM, LRESULT, RECT, TRUE, WPARAM};
+#[cfg(target_os = "windows")]
+use windows::Win32::Graphics::Gdi::{EnumDisplayMonitors, HDC, HMONITOR};
 #[cfg(target_os = "windows")]
 use windows::Win32::System::LibraryLoader::GetModuleHandleA;
+#[cfg(target_os = "windows")]
+use windows::Win32::System::Power::RegisterSuspendResumeNotification;
+#[cfg(target_os = "windows")]
+use windows::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+#[cfg(target_os = "windows")]
+use windows::Win32::UI::HiDpi::{
+    GetDpiForWindow, SetProcessDpiAwarenessContext, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+};
 use windows::Win32::UI::Input::KeyboardAndMouse::{
-    VIRTUAL_KEY, VK_A, VK_C, VK_CONTROL, VK_D, VK_F, VK_LCONTROL, VK_LMENU, VK_LSHIFT, VK_LWIN,
-    VK_MENU, VK_RCONTROL, VK_RMENU, VK_RSHIFT, VK_RWIN, VK_S, VK_SHIFT,
+    GetAsyncKeyState, GetKeyState, GetKeyboardLayout, MapVirtualKeyExW, MAPVK_VSC_TO_VK,
+    VIRTUAL_KEY, VK_0, VK_1, VK_2, VK_3, VK_4, VK_5, VK_6, VK_7, VK_8, VK_9, VK_A, VK_B, VK_C,
+    VK_CAPITAL, VK_CONTROL, VK_D, VK_E, VK_F, VK_F1, VK_F10, VK_F11, VK_F12, VK_F2, VK_F3, VK_F4,
+    VK_F5, VK_F6, VK_F7, VK_F8, VK_F9, VK_G, VK_H, VK_I, VK_J, VK_K, VK_L, VK_LCONTROL, VK_LMENU,
+    VK_LSHIFT, VK_LWIN, VK_M, VK_MENU, VK_N, VK_NUMLOCK, VK_O, VK_P, VK_Q, VK_R, VK_RCONTROL,
+    VK_RMENU, VK_RSHIFT, VK_RWIN, VK_S, VK_SHIFT, VK_T, VK_U, VK_V, VK_W, VK_X, VK_Y, VK_Z,
 };
 #[cfg(target_os = "windows")]
 use windows::Win32::UI::Input::{
-    GetRawInputData, RegisterRawInputDevices, HRAWINPUT, RAWINPUT, RAWINPUTDEVICE, RAWINPUTHEADER,
-    RIDEV_INPUTSINK, RID_INPUT, RIM_TYPEKEYBOARD, RIM_TYPEMOUSE,
+    GetRawInputData, RegisterRawInputDevices, HRAWINPUT, MOUSE_MOVE_ABSOLUTE, MOUSE_STATE,
+    MOUSE_VIRTUAL_DESKTOP, RAWINPUT, RAWINPUTDEVICE, RAWINPUTHEADER, RIDEV_INPUTSINK, RID_INPUT,
+    RIM_TYPEKEYBOARD, RIM_TYPEMOUSE,
 };
 #[cfg(target_os = "windows")]
 use windows::Win32::UI::WindowsAndMessaging::{
-    CreateWindowExA, DefWindowProcA, DispatchMessageA, GetCursorPos, GetForegroundWindow,
-    GetMessageA, GetSystemMetrics, GetWindowLongPtrA, GetWindowRect, RegisterClassA,
-    SetWindowLongPtrA, TranslateMessage, GWLP_USERDATA, HMENU, HWND_MESSAGE, MSG, SM_CXSCREEN,
-    SM_CYSCREEN, WM_DESTROY, WM_INPUT, WNDCLASSA,
+    CreateWindowExA, DefWindowProcA, DestroyWindow, DispatchMessageA, GetCursorPos,
+    GetForegroundWindow, GetMessageA, GetSystemMetrics, GetWindowLongPtrA, GetWindowRect,
+    GetWindowThreadProcessId, PostMessageA, PostQuitMessage, RegisterClassA, SetTimer,
+    SetWindowLongPtrA, TranslateMessage, UnregisterClassA, DEVICE_NOTIFY_WINDOW_HANDLE,
+    GWLP_USERDATA, HMENU, HWND_MESSAGE, MSG, PBT_APMRESUMEAUTOMATIC, RI_MOUSE_HWHEEL,
+    RI_MOUSE_WHEEL, SM_CXSCREEN, SM_CXVIRTUALSCREEN, SM_CYSCREEN, SM_CYVIRTUALSCREEN,
+    SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN, WHEEL_DELTA, WM_CLOSE, WM_DESTROY, WM_INPUT,
+    WM_POWERBROADCAST, WM_TIMER, WNDCLASSA,
 };
 
 #[cfg(target_os = "windows")]
@@ -39,6 +73,42 @@ pub fn detect_screen_size() -> (i32, i32) {
     }
 }
 
+#[cfg(target_os = "windows")]
+pub fn detect_monitors() -> Monitors {
+    unsafe extern "system" fn collect(
+        _monitor: HMONITOR,
+        _hdc: HDC,
+        rect: *mut RECT,
+        data: LPARAM,
+    ) -> BOOL {
+        let rects = &mut *(data.0 as *mut Vec<Rect>);
+        let rect = &*rect;
+        rects.push(Rect {
+            x: rect.left,
+            y: rect.top,
+            width: rect.right - rect.left,
+            height: rect.bottom - rect.top,
+        });
+        TRUE
+    }
+
+    let mut rects: Vec<Rect> = Vec::new();
+    unsafe {
+        let _ = EnumDisplayMonitors(
+            HDC::default(),
+            None,
+            Some(collect),
+            LPARAM(&mut rects as *mut Vec<Rect> as isize),
+        );
+    }
+
+    if rects.is_empty() {
+        let (width, height) = detect_screen_size();
+        return Monitors::single(width, height);
+    }
+    Monitors { rects }
+}
+
 #[cfg(target_os = "windows")]
 pub fn check_fullscreen() -> bool {
     unsafe {
@@ -59,10 +129,155 @@ pub fn check_fullscreen() -> bool {
     }
 }
 
+/// File name (without .exe) of the process owning the foreground window
+#[cfg(target_os = "windows")]
+pub fn foreground_app() -> Option<String> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0 == 0 {
+            return None;
+        }
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        if pid == 0 {
+            return None;
+        }
+
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut path = [0u16; 1024];
+        let mut len = path.len() as u32;
+        let result = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(path.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(process);
+        result.ok()?;
+
+        let path = String::from_utf16_lossy(&path[..len as usize]);
+        std::path::Path::new(&path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn has_input_permission() -> bool {
+    // Raw Input with RIDEV_INPUTSINK works for any desktop process
+    true
+}
+
+/// Raw Input merges every mouse and every keyboard, so there is one entry
+/// for each
+#[cfg(target_os = "windows")]
+pub fn list_devices() -> Vec<DeviceInfo> {
+    ["mouse", "keyboard"]
+        .into_iter()
+        .map(|kind| DeviceInfo {
+            path: "Raw Input".to_string(),
+            name: format!("All {}s", kind),
+            kind: kind.to_string(),
+            readable: true,
+        })
+        .collect()
+}
+
 #[cfg(target_os = "windows")]
 struct ThreadContext {
-    app: AppHandle,
+    sink: Arc<dyn EventSink>,
     state: Arc<SharedState>,
+    /// Per-device flood protection, keyed by the raw input device handle
+    rate_limiters: RefCell<HashMap<isize, EventRateLimiter>>,
+    /// Last click-through state applied to the main window
+    /// (None until the first cursor move)
+    last_interactive: Cell<Option<bool>>,
+    /// Wheel delta short of a whole notch, (horizontal, vertical), carried
+    /// between messages
+    wheel_remainder: Cell<(i32, i32)>,
+}
+
+/// Records one raw input packet from `device` and returns true if that
+/// device is currently over the event-rate cap, in which case it's ignored
+/// (and counted in the stats).
+#[cfg(target_os = "windows")]
+fn is_device_flooding(context: &ThreadContext, device: isize) -> bool {
+    let max_per_sec = context
+        .state
+        .input_state
+        .lock()
+        .unwrap()
+        .max_device_events_per_sec;
+
+    let mut limiters = context.rate_limiters.borrow_mut();
+    let limiter = limiters.entry(device).or_insert_with(EventRateLimiter::new);
+    let flooding = limiter.record(1, max_per_sec, Instant::now());
+    if flooding {
+        if limiter.should_warn() {
+            context.state.emit_error(
+                &context.sink,
+                format!(
+                    "Input device {:#x} sent more than {} events per second; ignoring it until it calms down",
+                    device, max_per_sec
+                ),
+            );
+        }
+        context.state.count_flood_dropped(1);
+    }
+    flooding
+}
+
+/// Scan codes of the letter and digit rows, with the US QWERTY key at each
+/// position. Raw Input's VKey already follows the layout, so physical keys
+/// come from these instead.
+#[cfg(target_os = "windows")]
+const SCANCODE_ROWS: [(u16, &str); 4] = [
+    (0x02, "1234567890"),
+    (0x10, "QWERTYUIOP"),
+    (0x1E, "ASDFGHJKL"),
+    (0x2C, "ZXCVBNM"),
+];
+
+/// Set when a scan code is E0-prefixed (media keys share the letters' codes)
+#[cfg(target_os = "windows")]
+const RI_KEY_E0: u16 = 2;
+
+/// Every letter/digit scan code with its physical key
+#[cfg(target_os = "windows")]
+fn scancode_keys() -> impl Iterator<Item = (u16, KeyCode)> {
+    SCANCODE_ROWS.iter().flat_map(|&(first, keys)| {
+        keys.chars()
+            .enumerate()
+            .filter_map(move |(i, c)| Some((first + i as u16, KeyCode::from_char(c)?)))
+    })
+}
+
+/// The physical letter/digit key for a raw keyboard scan code
+#[cfg(target_os = "windows")]
+fn map_scancode(make_code: u16, flags: u16) -> Option<KeyCode> {
+    if flags & RI_KEY_E0 != 0 {
+        return None;
+    }
+    scancode_keys()
+        .find(|&(code, _)| code == make_code)
+        .map(|(_, key)| key)
+}
+
+/// Maps each physical letter/digit key to the one the foreground window's
+/// keyboard layout types with it
+#[cfg(target_os = "windows")]
+pub fn keyboard_layout() -> HashMap<KeyCode, KeyCode> {
+    unsafe {
+        let thread = GetWindowThreadProcessId(GetForegroundWindow(), None);
+        let layout = GetKeyboardLayout(thread);
+        scancode_keys()
+            .filter_map(|(code, physical)| {
+                let vkey = MapVirtualKeyExW(code as u32, MAPVK_VSC_TO_VK, layout);
+                let logical = map_vkey(VIRTUAL_KEY(vkey as u16)).filter(KeyCode::is_character)?;
+                Some((physical, logical))
+            })
+            .collect()
+    }
 }
 
 #[cfg(target_os = "windows")]
@@ -76,15 +291,69 @@ fn map_vkey(vkey: VIRTUAL_KEY) -> Option<KeyCode> {
         VK_RMENU => Some(KeyCode::RightAlt),
         VK_LWIN => Some(KeyCode::LeftMeta),
         VK_RWIN => Some(KeyCode::RightMeta),
-        VK_F => Some(KeyCode::F),
+        VK_A => Some(KeyCode::A),
+        VK_B => Some(KeyCode::B),
+        VK_C => Some(KeyCode::C),
         VK_D => Some(KeyCode::D),
+        VK_E => Some(KeyCode::E),
+        VK_F => Some(KeyCode::F),
+        VK_G => Some(KeyCode::G),
+        VK_H => Some(KeyCode::H),
+        VK_I => Some(KeyCode::I),
+        VK_J => Some(KeyCode::J),
+        VK_K => Some(KeyCode::K),
+        VK_L => Some(KeyCode::L),
+        VK_M => Some(KeyCode::M),
+        VK_N => Some(KeyCode::N),
+        VK_O => Some(KeyCode::O),
+        VK_P => Some(KeyCode::P),
+        VK_Q => Some(KeyCode::Q),
+        VK_R => Some(KeyCode::R),
         VK_S => Some(KeyCode::S),
-        VK_C => Some(KeyCode::C),
-        VK_A => Some(KeyCode::A),
+        VK_T => Some(KeyCode::T),
+        VK_U => Some(KeyCode::U),
+        VK_V => Some(KeyCode::V),
+        VK_W => Some(KeyCode::W),
+        VK_X => Some(KeyCode::X),
+        VK_Y => Some(KeyCode::Y),
+        VK_Z => Some(KeyCode::Z),
+        VK_0 => Some(KeyCode::Digit0),
+        VK_1 => Some(KeyCode::Digit1),
+        VK_2 => Some(KeyCode::Digit2),
+        VK_3 => Some(KeyCode::Digit3),
+        VK_4 => Some(KeyCode::Digit4),
+        VK_5 => Some(KeyCode::Digit5),
+        VK_6 => Some(KeyCode::Digit6),
+        VK_7 => Some(KeyCode::Digit7),
+        VK_8 => Some(KeyCode::Digit8),
+        VK_9 => Some(KeyCode::Digit9),
+        VK_F1 => Some(KeyCode::F1),
+        VK_F2 => Some(KeyCode::F2),
+        VK_F3 => Some(KeyCode::F3),
+        VK_F4 => Some(KeyCode::F4),
+        VK_F5 => Some(KeyCode::F5),
+        VK_F6 => Some(KeyCode::F6),
+        VK_F7 => Some(KeyCode::F7),
+        VK_F8 => Some(KeyCode::F8),
+        VK_F9 => Some(KeyCode::F9),
+        VK_F10 => Some(KeyCode::F10),
+        VK_F11 => Some(KeyCode::F11),
+        VK_F12 => Some(KeyCode::F12),
+        VK_CAPITAL => Some(KeyCode::CapsLock),
+        VK_NUMLOCK => Some(KeyCode::NumLock),
         _ => None,
     }
 }
 
+/// Keys the OS reports as physically down right now
+#[cfg(target_os = "windows")]
+fn pressed_keys() -> HashSet<KeyCode> {
+    (1..=254u16)
+        .filter(|&vkey| unsafe { GetAsyncKeyState(vkey as i32) } < 0)
+        .filter_map(|vkey| map_vkey(VIRTUAL_KEY(vkey)))
+        .collect()
+}
+
 #[cfg(target_os = "windows")]
 unsafe extern "system" fn wnd_proc(
     hwnd: HWND,
@@ -92,13 +361,73 @@ unsafe extern "system" fn wnd_proc(
     wparam: WPARAM,
     lparam: LPARAM,
 ) -> LRESULT {
+    // A panic can't unwind out of a window procedure (it aborts the whole
+    // app), so catch it here, tear the window down, and re-raise it once the
+    // message loop has ended, where `input::run_input_loop` restarts us
+    match std::panic::catch_unwind(|| handle_message(hwnd, msg, wparam, lparam)) {
+        Ok(result) => result,
+        Err(payload) => {
+            HANDLER_PANIC.with(|panic| *panic.borrow_mut() = Some(payload));
+            let _ = DestroyWindow(hwnd);
+            LRESULT(0)
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+thread_local! {
+    /// A panic caught in wnd_proc, re-raised after the message loop
+    static HANDLER_PANIC: RefCell<Option<Box<dyn std::any::Any + Send>>> = RefCell::new(None);
+}
+
+#[cfg(target_os = "windows")]
+unsafe fn handle_message(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
     let ptr = GetWindowLongPtrA(hwnd, GWLP_USERDATA) as *mut ThreadContext;
 
-    // Only process logic if we have context
-    // Check for WM_DESTROY to free memory?
-    // In this app, thread lives forever, so simple drop is fine. But for correctness:
-    if msg == WM_DESTROY && !ptr.is_null() {
-        let _ = Box::from_raw(ptr); // Drop
+    // The window is destroyed on shutdown: free the context (clearing the
+    // pointer first so no later message can touch it) and end the message loop
+    if msg == WM_DESTROY {
+        if !ptr.is_null() {
+            SetWindowLongPtrA(hwnd, GWLP_USERDATA, 0);
+            drop(Box::from_raw(ptr));
+        }
+        PostQuitMessage(0);
+        return LRESULT(0);
+    }
+
+    // Resume from sleep: re-seed the screen size and re-register raw input,
+    // since display layout and device registrations may not survive suspend.
+    if msg == WM_POWERBROADCAST && !ptr.is_null() {
+        if wparam.0 as u32 == PBT_APMRESUMEAUTOMATIC {
+            let context = &*ptr;
+            log::info!("[Windows Input] Resume from suspend detected. Re-initializing input...");
+
+            context
+                .state
+                .input_state
+                .lock()
+                .unwrap()
+                .set_monitors(detect_monitors());
+
+            if !register_raw_input(hwnd) {
+                context.state.emit_error(&context.sink, RAW_INPUT_ERROR);
+            }
+            emit_ready(&context.sink, &context.state);
+        }
+        return LRESULT(1);
+    }
+
+    // Once a second, see whether the user has gone idle, and release any
+    // held key whose key-up raw input missed (e.g. while re-registering)
+    if msg == WM_TIMER && wparam.0 == IDLE_TIMER_ID && !ptr.is_null() {
+        let context = &*ptr;
+        context.state.check_idle(&context.sink);
+        let pressed = pressed_keys();
+        context.state.reconcile_held_keys(
+            &context.sink,
+            &mut context.state.input_state.lock().unwrap(),
+            |key| pressed.contains(&key),
+        );
         return LRESULT(0);
     }
 
@@ -118,32 +447,52 @@ unsafe extern "system" fn wnd_proc(
             &mut size,
             std::mem::size_of::<RAWINPUTHEADER>() as u32,
         ) != u32::MAX
+            && !is_device_flooding(context, raw.header.hDevice.0)
         {
             if raw.header.dwType == RIM_TYPEMOUSE.0 {
                 let mouse = raw.data.mouse;
-                // MOUSE_MOVE_RELATIVE = 0, MOUSE_MOVE_ABSOLUTE = 1
-                // we want relative usually, unless MOUSE_MOVE_ABSOLUTE is set.
-                // raw.data.mouse.usFlags & 0x01 == MOUSE_MOVE_ABSOLUTE
+                // Physical mice report relative deltas, but under RDP and in
+                // some VMs the device reports absolute normalized positions
+                // instead, where (0, 0) is a real position rather than "no
+                // movement"
+                let is_absolute = (mouse.usFlags.0 & MOUSE_MOVE_ABSOLUTE.0) != 0;
 
                 let rel_x = mouse.lLastX;
                 let rel_y = mouse.lLastY;
 
-                if rel_x != 0 || rel_y != 0 {
-                    let mut point = POINT::default();
-                    unsafe { GetCursorPos(&mut point).ok() }; // Check result?
+                if is_absolute || rel_x != 0 || rel_y != 0 {
+                    let point = if is_absolute {
+                        absolute_to_screen(mouse.usFlags, rel_x, rel_y)
+                    } else {
+                        let mut point = POINT::default();
+                        unsafe { GetCursorPos(&mut point).ok() }; // Check result?
+                        point
+                    };
+
+                    let main_window = context.sink.main_window();
+                    let scale = main_window.as_ref().map_or(1.0, window_scale_factor);
 
                     let mut input_state = context.state.input_state.lock().unwrap();
-                    // Use absolute OS cursor position
-                    input_state.cursor_x = point.x;
-                    input_state.cursor_y = point.y;
+                    // Use absolute OS cursor position, in the webview's logical pixels
+                    let (x, y) = to_logical(point, scale);
+                    let changed = input_state.set_cursor_absolute(x, y);
                     let interactive_rects = input_state.interactive_rects.clone();
+                    let click_through = input_state.click_through;
+                    let full_interactive = input_state.full_interactive;
 
                     // Release lock before window operations to avoid deadlocks
                     drop(input_state);
 
                     // Click-Through Logic
-                    if !interactive_rects.is_empty() {
-                        if let Some(window) = context.app.get_webview_window("main") {
+                    // With no rects reported yet the whole window stays
+                    // interactive, so the character can't become unclickable
+                    let mut is_over_anything = true;
+                    if click_through {
+                        // The manual override owns the window style; forget
+                        // the last hit-test so it's re-applied once it's off
+                        context.last_interactive.set(None);
+                    } else if let Some(window) = main_window {
+                        if !full_interactive && !interactive_rects.is_empty() {
                             // Get Main Window HWND
                             if let Ok(hwnd_main) = window.hwnd() {
                                 let mut win_rect = RECT::default();
@@ -152,71 +501,37 @@ unsafe extern "system" fn wnd_proc(
                                         GetWindowRect(HWND(hwnd_main.0 as isize), &mut win_rect);
                                 };
 
-                                let win_x = win_rect.left;
-                                let win_y = win_rect.top;
-                                let cursor_x = point.x;
-                                let cursor_y = point.y;
-
-                                let mut is_over_anything = false;
-
-                                for rect in &interactive_rects {
-                                    let global_x = win_x + rect.x;
-                                    let global_y = win_y + rect.y;
-
-                                    if cursor_x >= global_x
-                                        && cursor_x <= global_x + rect.width
-                                        && cursor_y >= global_y
-                                        && cursor_y <= global_y + rect.height
-                                    {
-                                        is_over_anything = true;
-                                        break;
-                                    }
-                                }
-
-                                // REVERTED: Click-through disabled per user request due to stability issues.
-                                // Forcing window to be interactive at all times.
-                                // To re-enable, uncomment logic below and ensure frontend sends correct bounds.
-
-                                /*
-                                // Debug logging - Use eprintln to ensure it bypasses stdout buffers if any
-                                eprintln!("[Windows Input] Cursor: {},{} | Global Rect: {},{} {}x{} | Hit: {}",
-                                   cursor_x, cursor_y, win_x + interactive_rects[0].x, win_y + interactive_rects[0].y,
-                                   interactive_rects[0].width, interactive_rects[0].height, is_over_anything);
+                                // Rects are window-relative CSS pixels, so test the
+                                // cursor in window space, scaled to logical pixels
+                                let local_x = ((point.x - win_rect.left) as f64 / scale) as i32;
+                                let local_y = ((point.y - win_rect.top) as f64 / scale) as i32;
+                                is_over_anything = interactive_rects
+                                    .iter()
+                                    .any(|rect| rect.contains(local_x, local_y));
+                            }
+                        }
 
-                                // Update Ignore State
-                                if let Err(e) = window.set_ignore_cursor_events(!is_over_anything) {
-                                    eprintln!("[Windows Input] Failed to set ignore events: {}", e);
+                        // Toggling the window style on every mouse move made the
+                        // window flicker and drop clicks, so only touch it when
+                        // the hit-test result actually changes
+                        if context.last_interactive.get() != Some(is_over_anything) {
+                            match window.set_ignore_cursor_events(!is_over_anything) {
+                                Ok(()) => context.last_interactive.set(Some(is_over_anything)),
+                                Err(e) => {
+                                    log::warn!("[Windows Input] Failed to set ignore events: {}", e)
                                 }
-                                */
-                                let _ = window.set_ignore_cursor_events(false);
                             }
                         }
-                    } else {
-                        // Default to Interactive during revert
-                        if let Some(window) = context.app.get_webview_window("main") {
-                            let _ = window.set_ignore_cursor_events(false);
-                        }
                     }
 
                     // Re-acquire lock for event emission
                     let mut input_state = context.state.input_state.lock().unwrap();
 
-                    // Only emit if changed?
-
-                    // Only emit if changed?
-                    if input_state.cursor_x != input_state.last_reported_x
-                        || input_state.cursor_y != input_state.last_reported_y
-                    {
-                        input_state.last_reported_x = input_state.cursor_x;
-                        input_state.last_reported_y = input_state.cursor_y;
-
-                        let _ = context.app.emit(
-                            "cursor-pos",
-                            OutputEvent::Cursor {
-                                x: input_state.cursor_x,
-                                y: input_state.cursor_y,
-                            },
-                        );
+                    if changed {
+                        context.state.emit_cursor(&context.sink, &mut input_state);
+                        context
+                            .state
+                            .emit_drag_motion(&context.sink, &mut input_state);
                     }
                 }
 
@@ -226,20 +541,23 @@ unsafe extern "system" fn wnd_proc(
                 if buttons != 0 {
                     if (buttons & 0x0001) != 0 {
                         // Down
-                        let input_state = context.state.input_state.lock().unwrap();
-                        let _ = context.app.emit(
+                        let mut input_state = context.state.input_state.lock().unwrap();
+                        input_state.left_button_down(Instant::now());
+                        context.state.emit_click(
+                            &context.sink,
+                            &mut input_state,
                             "mousedown",
-                            OutputEvent::Click {
-                                button: "left".into(),
-                                x: input_state.cursor_x,
-                                y: input_state.cursor_y,
-                            },
+                            "left",
                         );
                     }
                     if (buttons & 0x0002) != 0 {
                         // Up
-                        let input_state = context.state.input_state.lock().unwrap();
-                        let _ = context.app.emit(
+                        let mut input_state = context.state.input_state.lock().unwrap();
+                        context
+                            .state
+                            .release_left_button(&context.sink, &mut input_state);
+                        context.state.emit(
+                            &context.sink,
                             "mouseup",
                             OutputEvent::Click {
                                 button: "left".into(),
@@ -251,20 +569,21 @@ unsafe extern "system" fn wnd_proc(
 
                     if (buttons & 0x0004) != 0 {
                         // Right Down
-                        let input_state = context.state.input_state.lock().unwrap();
-                        let _ = context.app.emit(
+                        let mut input_state = context.state.input_state.lock().unwrap();
+                        input_state.set_mouse_button(MOUSE_BUTTON_RIGHT, true);
+                        context.state.emit_click(
+                            &context.sink,
+                            &mut input_state,
                             "mousedown",
-                            OutputEvent::Click {
-                                button: "right".into(),
-                                x: input_state.cursor_x,
-                                y: input_state.cursor_y,
-                            },
+                            "right",
                         );
                     }
                     if (buttons & 0x0008) != 0 {
                         // Right Up
-                        let input_state = context.state.input_state.lock().unwrap();
-                        let _ = context.app.emit(
+                        let mut input_state = context.state.input_state.lock().unwrap();
+                        input_state.set_mouse_button(MOUSE_BUTTON_RIGHT, false);
+                        context.state.emit(
+                            &context.sink,
                             "mouseup",
                             OutputEvent::Click {
                                 button: "right".into(),
@@ -276,19 +595,70 @@ unsafe extern "system" fn wnd_proc(
 
                     if (buttons & 0x0010) != 0 {
                         // Middle Down
-                        let input_state = context.state.input_state.lock().unwrap();
-                        let _ = context.app.emit(
+                        let mut input_state = context.state.input_state.lock().unwrap();
+                        input_state.set_mouse_button(MOUSE_BUTTON_MIDDLE, true);
+                        context.state.emit_click(
+                            &context.sink,
+                            &mut input_state,
                             "mousedown",
-                            OutputEvent::Click {
-                                button: "middle".into(),
-                                x: input_state.cursor_x,
-                                y: input_state.cursor_y,
-                            },
+                            "middle",
+                        );
+                    }
+                    if (buttons & 0x0020) != 0 {
+                        // Middle Up
+                        let mut input_state = context.state.input_state.lock().unwrap();
+                        input_state.set_mouse_button(MOUSE_BUTTON_MIDDLE, false);
+                    }
+
+                    if (buttons & 0x0040) != 0 {
+                        // Button 4 (Back) Down
+                        let mut input_state = context.state.input_state.lock().unwrap();
+                        context.state.emit_click(
+                            &context.sink,
+                            &mut input_state,
+                            "mousedown",
+                            "back",
                         );
                     }
-                    // Middle Up is 0x0020 if needed
+                    if (buttons & 0x0100) != 0 {
+                        // Button 5 (Forward) Down
+                        let mut input_state = context.state.input_state.lock().unwrap();
+                        context.state.emit_click(
+                            &context.sink,
+                            &mut input_state,
+                            "mousedown",
+                            "forward",
+                        );
+                    }
+
+                    // Raw input has flags for five buttons only: a tilting
+                    // wheel arrives as RI_MOUSE_HWHEEL (horizontal scroll), and
+                    // buttons beyond five are only seen by vendor drivers
+                    if (buttons as u32 & (RI_MOUSE_WHEEL | RI_MOUSE_HWHEEL)) != 0 {
+                        // Wheel delta is a signed value in usButtonData
+                        let delta = mouse.Anonymous.Anonymous.usButtonData as i16;
+                        let (rem_x, rem_y) = context.wheel_remainder.get();
+                        let (dx, dy) = if (buttons as u32 & RI_MOUSE_HWHEEL) != 0 {
+                            let (notches, rem_x) = wheel_notches(rem_x, delta);
+                            context.wheel_remainder.set((rem_x, rem_y));
+                            (notches, 0)
+                        } else {
+                            let (notches, rem_y) = wheel_notches(rem_y, delta);
+                            context.wheel_remainder.set((rem_x, rem_y));
+                            (0, notches)
+                        };
+                        if dx != 0 || dy != 0 {
+                            context.state.emit(
+                                &context.sink,
+                                "scroll",
+                                OutputEvent::Scroll { dx, dy },
+                            );
+                        }
+                    }
 
-                    let _ = context.app.emit("activity", OutputEvent::Activity);
+                    context
+                        .state
+                        .emit(&context.sink, "activity", OutputEvent::Activity);
                 }
             } else if raw.header.dwType == RIM_TYPEKEYBOARD.0 {
                 let kb = raw.data.keyboard;
@@ -297,26 +667,62 @@ unsafe extern "system" fn wnd_proc(
                 let is_break = (flags & 1) != 0; // RI_KEY_BREAK (Key Up)
                 let is_make = !is_break; // RI_KEY_MAKE (Key Down)
 
-                if let Some(shared_key) = map_vkey(vkey) {
+                // Lock keys toggle, so record whether each is on (the low
+                // bit of GetKeyState) rather than tracking their presses
+                {
+                    let mut input_state = context.state.input_state.lock().unwrap();
+                    for (vk, key) in [
+                        (VK_CAPITAL, KeyCode::CapsLock),
+                        (VK_NUMLOCK, KeyCode::NumLock),
+                    ] {
+                        input_state.set_lock(key, (GetKeyState(vk.0 as i32) & 1) != 0);
+                    }
+                }
+
+                // Letters and digits by position, so shortcuts can choose
+                // between physical and layout matching
+                let shared_key = map_scancode(kb.MakeCode, flags).or_else(|| map_vkey(vkey));
+                if let Some(shared_key) = shared_key {
                     let mut input_state = context.state.input_state.lock().unwrap();
                     if is_make {
-                        input_state.held_modifiers.insert(shared_key);
-                        // Check Shortcut
-                        if let Some(shortcut) = input_state.check_shortcut(shared_key) {
-                            let _ = context.app.emit(
-                                "shortcut",
-                                OutputEvent::Shortcut {
-                                    name: shortcut.to_string(),
-                                },
-                            );
+                        // Held keys auto-repeat; only report the initial press
+                        if input_state.held_modifiers.insert(shared_key) {
+                            context
+                                .state
+                                .emit_key(&context.sink, &input_state, shared_key, true);
+                        }
+                        // Check Shortcut (once per press, not per auto-repeat)
+                        if let Some(shortcut) = input_state.press_shortcut_key(shared_key) {
+                            context
+                                .state
+                                .emit_shortcut(&context.sink, &input_state, &shortcut);
                         }
                     } else {
-                        input_state.held_modifiers.remove(&shared_key);
+                        input_state.shortcut_key_released(shared_key);
+                        if input_state.held_modifiers.remove(&shared_key) {
+                            context
+                                .state
+                                .emit_key(&context.sink, &input_state, shared_key, false);
+                        }
                     }
                 }
 
+                // Double-tap shortcuts; any other key breaks a tap
+                let tap_key = shared_key.unwrap_or(KeyCode::Unknown);
+                let mut input_state = context.state.input_state.lock().unwrap();
+                if let Some(shortcut) =
+                    input_state.track_modifier_tap(tap_key, is_make, Instant::now())
+                {
+                    context
+                        .state
+                        .emit_shortcut(&context.sink, &input_state, &shortcut);
+                }
+                drop(input_state);
+
                 if is_make {
-                    let _ = context.app.emit("activity", OutputEvent::Activity);
+                    context
+                        .state
+                        .emit(&context.sink, "activity", OutputEvent::Activity);
                 }
             }
         }
@@ -326,20 +732,172 @@ unsafe extern "system" fn wnd_proc(
 }
 
 #[cfg(target_os = "windows")]
-pub fn run_input_loop(app_handle: AppHandle, shared_state: Arc<SharedState>) {
-    let (width, height) = detect_screen_size();
-
-    // Initial Ready Call
-    let _ = app_handle.emit(
+fn emit_ready(sink: &dyn EventSink, shared_state: &SharedState) {
+    let (screen_width, screen_height, monitors) = {
+        let input_state = shared_state.input_state.lock().unwrap();
+        (
+            input_state.screen_width,
+            input_state.screen_height,
+            input_state.monitors.rects.clone(),
+        )
+    };
+    // Screen and monitor sizes are physical pixels; the cursor is logical
+    let scale_factor = sink.main_window().as_ref().map_or(1.0, window_scale_factor);
+
+    shared_state.emit(
+        sink,
         "ready",
         OutputEvent::Ready {
             mice_count: 1,
             keyboards_count: 1,
-            screen_width: width,
-            screen_height: height,
+            device_names: Vec::new(),
+            screen_width,
+            screen_height,
+            monitors,
+            scale_factor,
         },
     );
-    println!("[Windows Input] Starting Input Loop");
+}
+
+/// Timer id for the once-a-second idle check on the hidden window
+#[cfg(target_os = "windows")]
+const IDLE_TIMER_ID: usize = 1;
+
+/// Shown to the user when RegisterRawInputDevices fails
+#[cfg(target_os = "windows")]
+const RAW_INPUT_ERROR: &str =
+    "Could not register for raw mouse and keyboard input. Restart the app; if it keeps happening, check that security software isn't blocking input access";
+
+/// Registers the hidden window as a sink for global mouse and keyboard raw input.
+#[cfg(target_os = "windows")]
+unsafe fn register_raw_input(hwnd: HWND) -> bool {
+    let devices = [
+        RAWINPUTDEVICE {
+            usUsagePage: 0x01,        // Generic Desktop Controls
+            usUsage: 0x02,            // Mouse
+            dwFlags: RIDEV_INPUTSINK, // Receive input even when not in foreground
+            hwndTarget: hwnd,
+        },
+        RAWINPUTDEVICE {
+            usUsagePage: 0x01,
+            usUsage: 0x06, // Keyboard
+            dwFlags: RIDEV_INPUTSINK,
+            hwndTarget: hwnd,
+        },
+    ];
+
+    RegisterRawInputDevices(&devices, std::mem::size_of::<RAWINPUTDEVICE>() as u32).is_ok()
+}
+
+/// Opts the process into per-monitor DPI awareness (v2). Fails harmlessly
+/// if the awareness was already set, e.g. by the application manifest.
+#[cfg(target_os = "windows")]
+pub fn enable_dpi_awareness() {
+    unsafe {
+        if let Err(e) = SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) {
+            log::warn!("[Windows Input] DPI awareness not changed: {}", e);
+        }
+    }
+}
+
+/// Scale factor of the monitor the window is on (1.0 = 96 DPI)
+#[cfg(target_os = "windows")]
+fn window_scale_factor(window: &WebviewWindow) -> f64 {
+    let Ok(hwnd) = window.hwnd() else {
+        return 1.0;
+    };
+    match unsafe { GetDpiForWindow(HWND(hwnd.0 as isize)) } {
+        0 => 1.0,
+        dpi => dpi as f64 / 96.0,
+    }
+}
+
+/// Scales a MOUSE_MOVE_ABSOLUTE position (0–65535 on each axis) to physical
+/// screen pixels, across the whole virtual desktop if the flags say so
+/// (multi-monitor RDP) or else the primary monitor
+#[cfg(target_os = "windows")]
+fn absolute_to_screen(flags: MOUSE_STATE, x: i32, y: i32) -> POINT {
+    let (left, top, width, height) = unsafe {
+        if (flags.0 & MOUSE_VIRTUAL_DESKTOP.0) != 0 {
+            (
+                GetSystemMetrics(SM_XVIRTUALSCREEN),
+                GetSystemMetrics(SM_YVIRTUALSCREEN),
+                GetSystemMetrics(SM_CXVIRTUALSCREEN),
+                GetSystemMetrics(SM_CYVIRTUALSCREEN),
+            )
+        } else {
+            (
+                0,
+                0,
+                GetSystemMetrics(SM_CXSCREEN),
+                GetSystemMetrics(SM_CYSCREEN),
+            )
+        }
+    };
+    let scale = |value: i32, size: i32| (value as i64 * (size - 1).max(0) as i64 / 65535) as i32;
+    POINT {
+        x: left + scale(x, width),
+        y: top + scale(y, height),
+    }
+}
+
+/// Converts a physical screen position to the webview's logical pixels
+#[cfg(target_os = "windows")]
+fn to_logical(point: POINT, scale: f64) -> (i32, i32) {
+    (
+        (point.x as f64 / scale).round() as i32,
+        (point.y as f64 / scale).round() as i32,
+    )
+}
+
+/// Adds a raw wheel delta to the leftover `remainder` and returns the whole
+/// notches, with what's left for the next message. High-resolution wheels
+/// report fractions of WHEEL_DELTA, so only whole notches count. Turning
+/// the wheel the other way drops the leftover.
+#[cfg(target_os = "windows")]
+fn wheel_notches(remainder: i32, delta: i16) -> (i32, i32) {
+    let delta = delta as i32;
+    let total = if remainder.signum() == -delta.signum() {
+        delta
+    } else {
+        remainder + delta
+    };
+    (total / WHEEL_DELTA as i32, total % WHEEL_DELTA as i32)
+}
+
+/// Handle of the hidden input window while the loop runs (0 otherwise).
+/// Stored as an integer because HWND can't be shared across threads.
+#[cfg(target_os = "windows")]
+static INPUT_HWND: AtomicIsize = AtomicIsize::new(0);
+
+/// Closes the hidden input window from another thread. Its WM_DESTROY
+/// posts WM_QUIT, which makes the message loop in run_input_loop return.
+#[cfg(target_os = "windows")]
+pub fn wake_input_loop() {
+    let hwnd = INPUT_HWND.load(Ordering::Acquire);
+    if hwnd != 0 {
+        unsafe {
+            let _ = PostMessageA(HWND(hwnd), WM_CLOSE, WPARAM(0), LPARAM(0));
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn run_input_loop(sink: Arc<dyn EventSink>, shared_state: Arc<SharedState>) {
+    // Initial Ready Call
+    emit_ready(&sink, &shared_state);
+
+    // Raw Input moves are already reported at the real OS position, so the
+    // startup sync can happen right away instead of on the first move
+    let mut point = POINT::default();
+    if unsafe { GetCursorPos(&mut point) }.is_ok() {
+        let scale = sink.main_window().as_ref().map_or(1.0, window_scale_factor);
+        let (x, y) = to_logical(point, scale);
+        let mut input_state = shared_state.input_state.lock().unwrap();
+        input_state.set_cursor_absolute(x, y);
+        shared_state.emit_cursor(&sink, &mut input_state);
+    }
+    log::info!("[Windows Input] Starting Input Loop");
 
     unsafe {
         let instance = GetModuleHandleA(None).unwrap();
@@ -352,9 +910,14 @@ pub fn run_input_loop(app_handle: AppHandle, shared_state: Arc<SharedState>) {
             ..Default::default()
         };
 
+        // A relaunch within the same process finds the class still
+        // registered, which is fine since it points at the same wnd_proc
         if RegisterClassA(&wnd_class) == 0 {
-            eprintln!("[Windows Input] Failed to register window class.");
-            return;
+            if GetLastError() != ERROR_CLASS_ALREADY_EXISTS {
+                log::error!("[Windows Input] Failed to register window class.");
+                return;
+            }
+            log::info!("[Windows Input] Window class already registered, reusing it.");
         }
 
         let hwnd = CreateWindowExA(
@@ -373,36 +936,36 @@ pub fn run_input_loop(app_handle: AppHandle, shared_state: Arc<SharedState>) {
         );
 
         if hwnd.0 == 0 {
-            eprintln!("[Windows Input] Failed to create window.");
+            log::error!("[Windows Input] Failed to create window.");
             return;
         }
 
         // Store context in Window
         let context = Box::new(ThreadContext {
-            app: app_handle,
+            sink,
             state: shared_state,
+            rate_limiters: RefCell::new(HashMap::new()),
+            last_interactive: Cell::new(None),
+            wheel_remainder: Cell::new((0, 0)),
         });
-        SetWindowLongPtrA(hwnd, GWLP_USERDATA, Box::into_raw(context) as isize);
+        let context_ptr = Box::into_raw(context);
+        SetWindowLongPtrA(hwnd, GWLP_USERDATA, context_ptr as isize);
+        INPUT_HWND.store(hwnd.0, Ordering::Release);
 
         // Register Raw Input
-        let devices = [
-            RAWINPUTDEVICE {
-                usUsagePage: 0x01,        // Generic Desktop Controls
-                usUsage: 0x02,            // Mouse
-                dwFlags: RIDEV_INPUTSINK, // Receive input even when not in foreground
-                hwndTarget: hwnd,
-            },
-            RAWINPUTDEVICE {
-                usUsagePage: 0x01,
-                usUsage: 0x06, // Keyboard
-                dwFlags: RIDEV_INPUTSINK,
-                hwndTarget: hwnd,
-            },
-        ];
-
-        if RegisterRawInputDevices(&devices, std::mem::size_of::<RAWINPUTDEVICE>() as u32).is_err()
-        {
-            eprintln!("[Windows Input] Failed to register raw input devices.");
+        if !register_raw_input(hwnd) {
+            let context = &*context_ptr;
+            context.state.emit_error(&context.sink, RAW_INPUT_ERROR);
+        }
+
+        if SetTimer(hwnd, IDLE_TIMER_ID, 1000, None) == 0 {
+            log::warn!("[Windows Input] Failed to start the idle timer.");
+        }
+
+        // Message-only windows don't receive broadcasts, so ask explicitly
+        // for WM_POWERBROADCAST suspend/resume notifications.
+        if RegisterSuspendResumeNotification(HANDLE(hwnd.0), DEVICE_NOTIFY_WINDOW_HANDLE).is_err() {
+            log::warn!("[Windows Input] Failed to register for resume notifications.");
         }
 
         // Message Loop
@@ -411,5 +974,15 @@ pub fn run_input_loop(app_handle: AppHandle, shared_state: Arc<SharedState>) {
             TranslateMessage(&msg);
             DispatchMessageA(&msg);
         }
+
+        INPUT_HWND.store(0, Ordering::Release);
+        if UnregisterClassA(class_name, instance).is_err() {
+            log::warn!("[Windows Input] Failed to unregister window class.");
+        }
+        log::info!("[Windows Input] Input loop stopped.");
+    }
+
+    if let Some(payload) = HANDLER_PANIC.with(|panic| panic.borrow_mut().take()) {
+        std::panic::resume_unwind(payload);
     }
 }
@@ -19,9 +19,14 @@
 // =============================================================================
 
 use std::fs;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::thread;
-use tauri::{AppHandle, Manager, State};
+use std::time::{Duration, Instant};
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{
+    AppHandle, Emitter, Manager, PhysicalPosition, State, WebviewWindow, WindowEvent, Wry,
+};
 
 // ... (omitting lines for brevity in prompt, but I will target the imports block)
 
@@ -34,10 +39,19 @@ use tauri::{AppHandle, Manager, State};
 /// Shared types and state structures used across modules
 mod shared;
 
+/// Typed user configuration (config.json)
+mod config;
+
 /// OS-agnostic input handling module (dispatches to linux/windows backends)
 mod input;
 
-use shared::{InputState, SharedState};
+use config::AppConfig;
+use shared::{
+    Anchor, Axis, EventSink, InputState, InputStats, RecordingSink, SharedState, ShortcutBinding,
+    StdoutSink,
+};
+#[cfg(unix)]
+use shared::{SocketEncoding, SocketSink};
 
 // =============================================================================
 // Configuration Management (IPC Commands)
@@ -51,33 +65,25 @@ use shared::{InputState, SharedState};
 // =============================================================================
 
 /// Saves user configuration to disk as JSON.
+/// Fails if the frontend sent a malformed config or the file can't be written.
 ///
 /// # Frontend Usage
 /// ```javascript
 /// await invoke('save_config', { config: { theme: 'fox', geminiApiKey: '...' } });
 /// ```
 #[tauri::command]
-fn save_config(app_handle: AppHandle, config: serde_json::Value) -> bool {
-    // Resolve the app config directory path (platform-specific)
-    let config_path = app_handle
-        .path()
-        .app_config_dir()
-        .unwrap()
-        .join("config.json");
-
-    // Ensure the parent directory exists
-    if let Some(parent) = config_path.parent() {
-        let _ = fs::create_dir_all(parent);
-    }
+fn save_config(
+    app_handle: AppHandle,
+    state: State<Arc<SharedState>>,
+    config: AppConfig,
+) -> Result<(), String> {
+    config.save(&app_handle)?;
 
-    // Serialize and write the config as pretty-printed JSON
-    if let Ok(json) = serde_json::to_string_pretty(&config) {
-        if fs::write(&config_path, json).is_ok() {
-            println!("[Config] Saved to {:?}", config_path);
-            return true;
-        }
-    }
-    false
+    // Apply rebound shortcuts and privacy settings immediately
+    config.apply(&mut state.input_state.lock().unwrap());
+    config.apply_shared(&state);
+    input::refresh_input_loop();
+    Ok(())
 }
 
 /// Loads user configuration from disk.
@@ -88,29 +94,80 @@ fn save_config(app_handle: AppHandle, config: serde_json::Value) -> bool {
 /// const config = await invoke('load_config');
 /// ```
 #[tauri::command]
-fn load_config(app_handle: AppHandle) -> serde_json::Value {
-    // Resolve the app config directory path
-    let config_path = app_handle
-        .path()
-        .app_config_dir()
-        .unwrap()
-        .join("config.json");
-
-    // Try to read and parse the config file
-    if config_path.exists() {
-        if let Ok(content) = fs::read_to_string(&config_path) {
-            if let Ok(json) = serde_json::from_str(&content) {
-                println!("[Config] Loaded from {:?}", config_path);
-                return json;
-            }
-        }
-    }
+fn load_config(app_handle: AppHandle, state: State<Arc<SharedState>>) -> AppConfig {
+    // Fall back to defaults if the file doesn't exist or parsing fails
+    let config = AppConfig::load(&app_handle).unwrap_or_default();
 
-    // Return default config if file doesn't exist or parsing fails
-    serde_json::json!({
-        "theme": "fox",
-        "geminiApiKey": ""
-    })
+    // Pick up any edited settings
+    config.apply(&mut state.input_state.lock().unwrap());
+    config.apply_shared(&state);
+    input::refresh_input_loop();
+    config
+}
+
+/// Re-reads config.json and applies it to the running input thread
+/// (shortcuts, sensitivity, smoothing, idle timeout, ...), then emits
+/// ConfigReloaded on "config-reloaded". Screen size and monitor layout
+/// don't come from the config and are left as detected.
+///
+/// # Frontend Usage
+/// ```javascript
+/// await invoke('reload_config');
+/// ```
+#[tauri::command]
+fn reload_config(app_handle: AppHandle, state: State<Arc<SharedState>>) {
+    let config = AppConfig::load(&app_handle).unwrap_or_default();
+    config.apply(&mut state.input_state.lock().unwrap());
+    config.apply_shared(&state);
+    input::refresh_input_loop();
+    state.emit(
+        &app_handle,
+        "config-reloaded",
+        shared::OutputEvent::ConfigReloaded,
+    );
+}
+
+/// Returns the shortcut bindings currently in effect (the defaults if the
+/// config defines none), for the settings UI's shortcut table.
+///
+/// # Frontend Usage
+/// ```javascript
+/// const bindings = await invoke('get_shortcuts');
+/// ```
+#[tauri::command]
+fn get_shortcuts(state: State<Arc<SharedState>>) -> Vec<ShortcutBinding> {
+    state.input_state.lock().unwrap().shortcuts.clone()
+}
+
+/// Replaces the shortcut bindings, live and in the config, then emits
+/// ConfigReloaded. Fails without changing anything if a binding is invalid
+/// or two bindings use the same key combo. An empty list restores the
+/// defaults.
+///
+/// # Frontend Usage
+/// ```javascript
+/// await invoke('set_shortcuts', {
+///   bindings: [{ modifiers: ['meta', 'shift'], key: 'F', action: 'toggle_chat' }],
+/// });
+/// ```
+#[tauri::command]
+fn set_shortcuts(
+    app_handle: AppHandle,
+    state: State<Arc<SharedState>>,
+    bindings: Vec<ShortcutBinding>,
+) -> Result<(), String> {
+    ShortcutBinding::check_all(&bindings)?;
+
+    let mut config = AppConfig::load(&app_handle).unwrap_or_default();
+    config.shortcuts = bindings;
+    config.save(&app_handle)?;
+    state.input_state.lock().unwrap().shortcuts = config.shortcut_bindings();
+    state.emit(
+        &app_handle,
+        "config-reloaded",
+        shared::OutputEvent::ConfigReloaded,
+    );
+    Ok(())
 }
 
 /// Updates the cursor position in shared state.
@@ -125,6 +182,120 @@ fn sync_cursor(state: State<Arc<SharedState>>, x: i32, y: i32) {
     let mut input = state.input_state.lock().unwrap();
     input.cursor_x = x;
     input.cursor_y = y;
+    // The frontend already knows this position, so no startup jump is needed
+    input.cursor_synced = true;
+}
+
+/// Returns the tracked global cursor position, so the frontend can place
+/// the character before the first cursor event arrives.
+/// On Windows and macOS this is the real cursor from the start; on Linux it
+/// is the screen centre until the first absolute position is known.
+///
+/// # Frontend Usage
+/// ```javascript
+/// const [x, y] = await invoke('get_cursor_position');
+/// ```
+#[tauri::command]
+fn get_cursor_position(state: State<Arc<SharedState>>) -> (i32, i32) {
+    let input = state.input_state.lock().unwrap();
+    (input.cursor_x, input.cursor_y)
+}
+
+/// Locks the tracked cursor to one axis ("x" freezes horizontal movement,
+/// "y" vertical), or unlocks it when `axis` is null. Used for precise
+/// character placement.
+///
+/// # Frontend Usage
+/// ```javascript
+/// await invoke('set_axis_lock', { axis: 'y' }); // horizontal moves only
+/// await invoke('set_axis_lock', { axis: null });
+/// ```
+#[tauri::command]
+fn set_axis_lock(state: State<Arc<SharedState>>, axis: Option<String>) -> Result<(), String> {
+    let axis = match axis {
+        Some(name) => {
+            Some(Axis::from_name(&name).ok_or_else(|| format!("Unknown axis: {}", name))?)
+        }
+        None => None,
+    };
+    state.input_state.lock().unwrap().lock_axis = axis;
+    Ok(())
+}
+
+/// Returns the last `limit` emitted events as JSON strings, oldest first,
+/// each with how many milliseconds ago it was emitted. Only records while
+/// `debugEvents` is enabled in the config; otherwise the list is empty.
+///
+/// # Frontend Usage
+/// ```javascript
+/// const trace = await invoke('recent_events', { limit: 50 });
+/// ```
+#[tauri::command]
+fn recent_events(state: State<Arc<SharedState>>, limit: usize) -> Vec<String> {
+    state
+        .recent_events(limit)
+        .into_iter()
+        .map(|(age, event)| {
+            serde_json::json!({ "agoMs": age.as_millis() as u64, "event": event }).to_string()
+        })
+        .collect()
+}
+
+/// Returns event counts since startup or the last `reset_stats`: cursor
+/// events (and their rate), clicks, shortcuts, and moves merged or dropped
+/// by throttling, pausing or the attention radius.
+///
+/// # Frontend Usage
+/// ```javascript
+/// const stats = await invoke('get_stats');
+/// console.log(`${stats.cursorEventsPerSecond.toFixed(1)} cursor events/s`);
+/// ```
+#[tauri::command]
+fn get_stats(state: State<Arc<SharedState>>) -> InputStats {
+    state.stats()
+}
+
+/// Zeroes the counters reported by `get_stats`
+///
+/// # Frontend Usage
+/// ```javascript
+/// await invoke('reset_stats');
+/// ```
+#[tauri::command]
+fn reset_stats(state: State<Arc<SharedState>>) {
+    state.reset_stats();
+}
+
+/// Fires a shortcut action as if its key combo had been pressed, emitting
+/// the same `shortcut` event (and running the same Rust handler), for
+/// testing frontend reactions without the physical keys. Only available in
+/// debug builds, or with `simulatedShortcuts` enabled in the config.
+/// Fails for actions no shortcut binding uses.
+///
+/// # Frontend Usage
+/// ```javascript
+/// await invoke('trigger_shortcut', { name: 'toggle_chat' });
+/// ```
+#[tauri::command]
+fn trigger_shortcut(
+    app_handle: AppHandle,
+    state: State<Arc<SharedState>>,
+    name: String,
+) -> Result<(), String> {
+    {
+        let input = state.input_state.lock().unwrap();
+        if !cfg!(debug_assertions) && !input.simulated_shortcuts {
+            return Err(
+                "trigger_shortcut is disabled; set simulatedShortcuts in the config".into(),
+            );
+        }
+        if !input.shortcuts.iter().any(|binding| binding.action == name) {
+            return Err(format!("Unknown shortcut action: {}", name));
+        }
+    }
+    log::info!("[Shortcut] Simulating {}", name);
+    state.trigger_action(&app_handle, &name);
+    Ok(())
 }
 
 /// Updates the interactive bounds of UI elements.
@@ -139,9 +310,14 @@ fn sync_cursor(state: State<Arc<SharedState>>, x: i32, y: i32) {
 /// });
 /// ```
 #[tauri::command]
-fn update_interactive_bounds(state: State<Arc<SharedState>>, rects: Vec<shared::Rect>) {
+fn update_interactive_bounds(
+    app_handle: AppHandle,
+    state: State<Arc<SharedState>>,
+    rects: Vec<shared::Rect>,
+) {
     let mut input_guard = state.input_state.lock().unwrap();
     input_guard.interactive_rects = rects;
+    state.update_hover(&app_handle, &mut input_guard);
 }
 
 /// Updates the specific bounds of the character for click detection.
@@ -152,7 +328,14 @@ fn update_interactive_bounds(state: State<Arc<SharedState>>, rects: Vec<shared::
 /// await invoke('update_character_bounds', { x: 100, y: 100, w: 50, h: 50 });
 /// ```
 #[tauri::command]
-fn update_character_bounds(state: State<Arc<SharedState>>, x: i32, y: i32, w: i32, h: i32) {
+fn update_character_bounds(
+    app_handle: AppHandle,
+    state: State<Arc<SharedState>>,
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+) {
     let mut input_guard = state.input_state.lock().unwrap();
     // For now, we just replace the interactive rects with this single character rect.
     // In the future, we might want to support multiple rects via `update_interactive_bounds`
@@ -164,6 +347,89 @@ fn update_character_bounds(state: State<Arc<SharedState>>, x: i32, y: i32, w: i3
         width: w,
         height: h,
     }];
+    state.update_hover(&app_handle, &mut input_guard);
+}
+
+/// Tells the backend where the character is on screen (its center, in
+/// global coordinates). With `attentionRadius` configured, cursor-pos events
+/// are only sent while the pointer is within that distance of it, plus one
+/// when it leaves.
+///
+/// # Frontend Usage
+/// ```javascript
+/// await invoke('set_character_position', { x: 1700, y: 900 });
+/// ```
+#[tauri::command]
+fn set_character_position(state: State<Arc<SharedState>>, x: i32, y: i32) {
+    state.input_state.lock().unwrap().character_position = Some((x, y));
+}
+
+/// Turns full cursor tracking on or off. While on, every cursor move is
+/// reported regardless of `attentionRadius`, for features that follow the
+/// pointer across the whole screen.
+///
+/// # Frontend Usage
+/// ```javascript
+/// await invoke('set_full_tracking', { enabled: true });
+/// ```
+#[tauri::command]
+fn set_full_tracking(state: State<Arc<SharedState>>, enabled: bool) {
+    state.input_state.lock().unwrap().full_tracking = enabled;
+}
+
+/// Stops the input thread, then exits the app. Use this instead of closing
+/// the window so the input loop releases its devices before the process ends.
+///
+/// # Frontend Usage
+/// ```javascript
+/// await invoke('shutdown');
+/// ```
+#[tauri::command]
+fn shutdown(app_handle: AppHandle, state: State<Arc<SharedState>>) {
+    input::stop_input_loop(&state);
+    app_handle.exit(0);
+}
+
+/// How long `await_next_click` waits before giving up, if no timeout is given
+const CALIBRATION_TIMEOUT_SECS: u64 = 30;
+
+/// Arms a one-shot capture of the next global click, for setup flows where
+/// the user clicks to place the character.
+///
+/// The next click from any backend is reported once on the
+/// `calibration-click` channel (instead of `click`) and the capture disarms.
+/// If no click arrives within the timeout, `calibration-cancelled` is emitted.
+///
+/// # Frontend Usage
+/// ```javascript
+/// await listen('calibration-click', (e) => placeCharacter(e.payload.x, e.payload.y));
+/// await invoke('await_next_click', { timeoutSecs: 15 });
+/// ```
+#[tauri::command]
+fn await_next_click(
+    app_handle: AppHandle,
+    state: State<Arc<SharedState>>,
+    timeout_secs: Option<u64>,
+) {
+    let id = state.input_state.lock().unwrap().arm_calibration();
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(CALIBRATION_TIMEOUT_SECS));
+
+    let shared_state = state.inner().clone();
+    thread::spawn(move || {
+        thread::sleep(timeout);
+        let expired = shared_state
+            .input_state
+            .lock()
+            .unwrap()
+            .cancel_calibration(id);
+        if expired {
+            shared_state.emit(
+                &app_handle,
+                "calibration-cancelled",
+                shared::OutputEvent::CalibrationCancelled,
+            );
+        }
+    });
 }
 
 /// Returns the path to the external themes directory.
@@ -212,11 +478,558 @@ fn list_external_themes(app_handle: AppHandle) -> Vec<String> {
 /// # Platform Support
 /// - Linux: Uses X11 to query window states
 /// - Windows: Uses Win32 API to check for fullscreen windows
+/// - macOS: Uses the CoreGraphics window list
 #[tauri::command]
 fn check_fullscreen() -> bool {
     input::check_fullscreen()
 }
 
+/// Lists the detected input devices, so the settings UI can show whether
+/// the mouse and keyboard were recognized.
+///
+/// # Frontend Usage
+/// ```javascript
+/// const devices = await invoke('list_devices');
+/// // [{ path: '/dev/input/event3', name: 'Logitech G305', kind: 'mouse', readable: true }, ...]
+/// ```
+#[tauri::command]
+fn list_devices() -> Vec<shared::DeviceInfo> {
+    input::list_devices()
+}
+
+/// Moves the character window so its top-left corner is at (x, y),
+/// in physical screen pixels.
+///
+/// # Frontend Usage
+/// ```javascript
+/// await invoke('move_character', { x: 100, y: 200 });
+/// ```
+#[tauri::command]
+fn move_character(app_handle: AppHandle, x: i32, y: i32) -> Result<(), String> {
+    let window = main_window(&app_handle)?;
+    window
+        .set_position(PhysicalPosition::new(x, y))
+        .map_err(|e| e.to_string())
+}
+
+/// Makes the character window ignore the mouse so clicks reach the desktop
+/// below it, or makes it interactive again. Overrides the per-region
+/// hit-testing and is saved in the config, so it survives restarts.
+///
+/// # Frontend Usage
+/// ```javascript
+/// await invoke('set_click_through', { enabled: true });
+/// ```
+#[tauri::command]
+fn set_click_through(
+    app_handle: AppHandle,
+    state: State<Arc<SharedState>>,
+    enabled: bool,
+) -> Result<(), String> {
+    main_window(&app_handle)?
+        .set_ignore_cursor_events(enabled)
+        .map_err(|e| e.to_string())?;
+    state.input_state.lock().unwrap().click_through = enabled;
+
+    let mut config = AppConfig::load(&app_handle).unwrap_or_default();
+    config.click_through = enabled;
+    config.save(&app_handle)
+}
+
+/// Makes the whole window take clicks, ignoring the interactive rects, e.g.
+/// while the chat input has focus. Disabling it goes back to the rects last
+/// sent with `update_interactive_bounds`/`update_character_bounds`, so the
+/// frontend doesn't have to save and restore them. `set_click_through`
+/// still takes precedence. Applied from the next cursor move. Only the
+/// Windows backend hit-tests clicks; elsewhere the frontend already decides.
+///
+/// # Frontend Usage
+/// ```javascript
+/// await invoke('set_full_interactive', { enabled: true });
+/// ```
+#[tauri::command]
+fn set_full_interactive(state: State<Arc<SharedState>>, enabled: bool) {
+    state.input_state.lock().unwrap().full_interactive = enabled;
+}
+
+/// Centers the character window on the monitor it is currently on.
+/// This is also what the Meta+Shift+C (`center_character`) shortcut does.
+#[tauri::command]
+fn center_character(app_handle: AppHandle) -> Result<(), String> {
+    center_window(&app_handle).map(|_| ())
+}
+
+/// Centers the character window on its current monitor and returns its new
+/// position (top-left, physical pixels)
+fn center_window(app_handle: &AppHandle) -> Result<(i32, i32), String> {
+    let window = main_window(app_handle)?;
+    let area = match window.current_monitor() {
+        Ok(Some(monitor)) => monitor_rect(&monitor),
+        _ => {
+            let (width, height) = input::detect_screen_size();
+            shared::Rect {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            }
+        }
+    };
+    place_window(&window, area, Anchor::Center)
+}
+
+/// Moves the character window to a monitor, placed at `anchor`
+/// ("top-left", "top", "top-right", "left", "center", "right",
+/// "bottom-left", "bottom" or "bottom-right").
+/// `index` counts monitors in the order of `availableMonitors()`.
+///
+/// # Frontend Usage
+/// ```javascript
+/// await invoke('move_to_monitor', { index: 1, anchor: 'bottom-right' });
+/// ```
+#[tauri::command]
+fn move_to_monitor(app_handle: AppHandle, index: usize, anchor: String) -> Result<(), String> {
+    let anchor = Anchor::from_name(&anchor).ok_or_else(|| format!("Unknown anchor: {}", anchor))?;
+    let window = main_window(&app_handle)?;
+    let monitors = window.available_monitors().map_err(|e| e.to_string())?;
+    let monitor = monitors
+        .get(index)
+        .ok_or_else(|| format!("No monitor {} ({} connected)", index, monitors.len()))?;
+    place_window(&window, monitor_rect(monitor), anchor).map(|_| ())
+}
+
+/// Pins the character to a corner of its current monitor ("top-left",
+/// "top-right", "bottom-left" or "bottom-right"), moving it there now and
+/// again whenever that monitor's resolution changes. Saved in the config.
+/// `null` unpins it, leaving the window where it is.
+///
+/// # Frontend Usage
+/// ```javascript
+/// await invoke('set_anchor', { corner: 'bottom-right' });
+/// await invoke('set_anchor', { corner: null });
+/// ```
+#[tauri::command]
+fn set_anchor(
+    app_handle: AppHandle,
+    state: State<Arc<SharedState>>,
+    corner: Option<String>,
+) -> Result<(), String> {
+    let anchor = match &corner {
+        Some(name) => Some(
+            Anchor::from_name(name)
+                .filter(Anchor::is_corner)
+                .ok_or_else(|| format!("Unknown corner: {}", name))?,
+        ),
+        None => None,
+    };
+
+    if let Some(anchor) = anchor {
+        let window = main_window(&app_handle)?;
+        let Ok(Some(monitor)) = window.current_monitor() else {
+            return Err("Can't tell which monitor the window is on".to_string());
+        };
+        place_window(&window, monitor_rect(&monitor), anchor)?;
+    }
+    state.input_state.lock().unwrap().window_anchor = anchor;
+
+    let mut config = AppConfig::load(&app_handle).unwrap_or_default();
+    config.anchor = corner.map(|name| name.to_ascii_lowercase());
+    config.save(&app_handle)
+}
+
+/// Moves the window to `anchor` within `area` (physical pixels) and
+/// returns where it put it
+fn place_window(
+    window: &WebviewWindow,
+    area: shared::Rect,
+    anchor: Anchor,
+) -> Result<(i32, i32), String> {
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+    let (x, y) = anchor.place(area, size.width as i32, size.height as i32);
+    window
+        .set_position(PhysicalPosition::new(x, y))
+        .map_err(|e| e.to_string())?;
+    Ok((x, y))
+}
+
+/// A monitor's bounds in physical pixels
+fn monitor_rect(monitor: &tauri::Monitor) -> shared::Rect {
+    shared::Rect {
+        x: monitor.position().x,
+        y: monitor.position().y,
+        width: monitor.size().width as i32,
+        height: monitor.size().height as i32,
+    }
+}
+
+/// Registers the shortcut actions Rust handles itself, so they work even
+/// while the webview is busy. The frontend still receives the Shortcut event.
+fn register_window_actions(app_handle: &AppHandle, state: &SharedState) {
+    let app = app_handle.clone();
+    state.register_action("center_character", move || match center_window(&app) {
+        // The frontend saves the new position from this
+        Ok((x, y)) => app.state::<Arc<SharedState>>().emit(
+            &app,
+            "character-centered",
+            shared::OutputEvent::CharacterCentered { x, y },
+        ),
+        Err(e) => log::warn!("[Shortcut] center_character failed: {}", e),
+    });
+
+    let app = app_handle.clone();
+    state.register_action("toggle_visibility", move || {
+        let Ok(window) = main_window(&app) else {
+            return;
+        };
+        let result = if window.is_visible().unwrap_or(true) {
+            window.hide()
+        } else {
+            window.show()
+        };
+        if let Err(e) = result {
+            log::warn!("[Shortcut] toggle_visibility failed: {}", e);
+        }
+    });
+
+    // Remember the pause across restarts
+    let app = app_handle.clone();
+    state.register_action("toggle_pause", move || {
+        let mut config = AppConfig::load(&app).unwrap_or_default();
+        config.paused = app.state::<Arc<SharedState>>().is_paused();
+        if let Err(e) = config.save(&app) {
+            log::warn!("[Shortcut] Could not save the pause setting: {}", e);
+        }
+    });
+
+    let app = app_handle.clone();
+    state.register_action("quit", move || shutdown(app.clone(), app.state()));
+}
+
+// =============================================================================
+// Tray Icon & Context Menu
+// =============================================================================
+// A native menu with the most common actions, shown from the tray icon and,
+// via `show_context_menu`, at the cursor (e.g. on a right-click of the
+// character). Each item fires its action exactly like the matching shortcut:
+// a `shortcut` event for the frontend plus any Rust handler registered in
+// register_window_actions.
+// =============================================================================
+
+/// (action, label) of each menu item, in menu order
+const MENU_ACTIONS: [(&str, &str); 5] = [
+    ("open_settings", "Settings"),
+    ("center_character", "Center Character"),
+    ("toggle_visibility", "Toggle Visibility"),
+    ("toggle_pause", "Pause/Resume Tracking"),
+    ("quit", "Quit"),
+];
+
+/// Builds the action menu. Item ids are the action names.
+fn build_action_menu(app_handle: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let menu = Menu::new(app_handle)?;
+    for (action, label) in MENU_ACTIONS {
+        menu.append(&MenuItem::with_id(
+            app_handle,
+            action,
+            label,
+            true,
+            None::<&str>,
+        )?)?;
+    }
+    Ok(menu)
+}
+
+/// Adds the tray icon and routes every menu item click to its action
+fn setup_tray(app_handle: &AppHandle) -> tauri::Result<()> {
+    let mut tray = TrayIconBuilder::with_id("main")
+        .tooltip("AI Character Assistant")
+        .menu(&build_action_menu(app_handle)?);
+    if let Some(icon) = app_handle.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app_handle)?;
+
+    // Covers the tray menu and context menus alike
+    app_handle.on_menu_event(|app, event| {
+        app.state::<Arc<SharedState>>()
+            .trigger_action(app, event.id().as_ref());
+    });
+    Ok(())
+}
+
+/// Pops up the action menu (Settings, Center Character, ...) at the cursor.
+///
+/// # Frontend Usage
+/// ```javascript
+/// await invoke('show_context_menu');
+/// ```
+#[tauri::command]
+fn show_context_menu(app_handle: AppHandle) -> Result<(), String> {
+    let menu = build_action_menu(&app_handle).map_err(|e| e.to_string())?;
+    main_window(&app_handle)?
+        .popup_menu(&menu)
+        .map_err(|e| e.to_string())
+}
+
+/// The character window, or an error for commands that need it
+fn main_window(app_handle: &AppHandle) -> Result<WebviewWindow, String> {
+    app_handle
+        .get_webview_window("main")
+        .ok_or_else(|| "Main window not found".to_string())
+}
+
+/// Re-resolves which monitor the character window is on.
+/// Called on every window move so dragging the character from one display to
+/// another (e.g. 1080p -> 4K) updates the backend's bounds and DPI scale,
+/// and emits `monitor-changed` when the window crosses a monitor boundary.
+/// Also records the window position, which hover hit-testing needs.
+fn track_window_monitor(app_handle: &AppHandle, window: &WebviewWindow, state: &SharedState) {
+    if let Ok(position) = window.outer_position() {
+        let mut input = state.input_state.lock().unwrap();
+        input.window_position = Some((position.x, position.y));
+        state.update_hover(app_handle, &mut input);
+    }
+
+    let Ok(Some(monitor)) = window.current_monitor() else {
+        return;
+    };
+
+    let bounds = monitor_rect(&monitor);
+    let scale_factor = monitor.scale_factor();
+
+    // A new size at the same origin is a resolution change (or startup),
+    // after which a pinned window goes back to its corner. A new origin is
+    // another monitor, where the user dragged it on purpose.
+    let reanchor = {
+        let mut input = state.input_state.lock().unwrap();
+        if input.window_monitor == Some(bounds) && input.window_scale_factor == scale_factor {
+            return;
+        }
+        let previous = input.window_monitor.replace(bounds);
+        input.window_scale_factor = scale_factor;
+        input.window_anchor.filter(|_| {
+            previous.map_or(true, |previous| {
+                (previous.x, previous.y) == (bounds.x, bounds.y)
+            })
+        })
+    };
+
+    log::info!(
+        "[Window] Now on monitor {:?} at {},{} {}x{} (scale {})",
+        monitor.name(),
+        bounds.x,
+        bounds.y,
+        bounds.width,
+        bounds.height,
+        scale_factor
+    );
+    state.emit(
+        app_handle,
+        "monitor-changed",
+        shared::OutputEvent::MonitorChanged {
+            name: monitor.name().cloned(),
+            bounds,
+            scale_factor,
+        },
+    );
+
+    if let Some(anchor) = reanchor {
+        if let Err(e) = place_window(window, bounds, anchor) {
+            log::warn!("[Window] Failed to re-apply the {:?} anchor: {}", anchor, e);
+        }
+    }
+}
+
+// =============================================================================
+// Diagnostics (IPC Commands)
+// =============================================================================
+
+/// Runs an end-to-end check of the input pipeline and returns a checklist
+/// the UI can render as ✅/❌ items. Consolidates the scattered startup
+/// checks into one report for "the character isn't following my mouse".
+///
+/// # Frontend Usage
+/// ```javascript
+/// const report = await invoke('run_self_test');
+/// report.items.forEach(i => console.log(i.passed ? '✅' : '❌', i.name, i.detail));
+/// ```
+#[tauri::command]
+fn run_self_test(state: State<Arc<SharedState>>) -> shared::SelfTestReport {
+    let mut items = Vec::new();
+
+    // 1. Permission to read global input
+    let permitted = input::has_input_permission();
+    items.push(shared::SelfTestItem {
+        name: "Input permission".into(),
+        passed: permitted,
+        detail: if permitted {
+            "Global input can be read".into()
+        } else if cfg!(target_os = "macos") {
+            "Grant Accessibility access in System Settings > Privacy & Security".into()
+        } else {
+            "Cannot open /dev/input devices. Run: sudo usermod -a -G input $USER, then log out and back in".into()
+        },
+    });
+
+    // 2. Input thread reported Ready, with at least one device
+    let ready = state.ready_devices();
+    items.push(shared::SelfTestItem {
+        name: "Input thread ready".into(),
+        passed: ready.is_some(),
+        detail: match ready {
+            Some(_) => "The input thread started and reported Ready".into(),
+            None => "The input thread never reported Ready".into(),
+        },
+    });
+
+    let (mice, keyboards) = ready.unwrap_or((0, 0));
+    items.push(shared::SelfTestItem {
+        name: "Devices discovered".into(),
+        passed: mice + keyboards > 0,
+        detail: format!("{} pointer source(s), {} keyboard(s)", mice, keyboards),
+    });
+
+    // 3. Screen size is plausible
+    let (width, height) = {
+        let input = state.input_state.lock().unwrap();
+        (input.screen_width, input.screen_height)
+    };
+    let plausible = (320..=32768).contains(&width) && (240..=32768).contains(&height);
+    items.push(shared::SelfTestItem {
+        name: "Screen size detected".into(),
+        passed: plausible,
+        detail: if (width, height) == (1920, 1080) {
+            format!("{}x{} (may be the fallback default)", width, height)
+        } else {
+            format!("{}x{}", width, height)
+        },
+    });
+
+    shared::SelfTestReport::new(items)
+}
+
+// =============================================================================
+// Headless Mode (--json-stdout / --socket)
+// =============================================================================
+// Runs only the input loop, writing every event as one line of JSON, for
+// scripting and for integrators that don't use the webview:
+//
+//     ai-character-assistant --json-stdout | jq .
+//     ai-character-assistant --socket /tmp/character.sock
+//
+// With --socket, every client connected to the Unix socket gets the stream,
+// so several consumers can subscribe and can restart independently.
+// --msgpack-socket serves the same stream as MessagePack on a second socket;
+// both can be given at once.
+// The Tauri log plugin isn't running, so log records go to stderr through
+// StderrLogger instead, and stdout carries nothing but events.
+// No window is created, so config.json is not loaded (its location comes
+// from the Tauri app) and every setting keeps its default.
+// =============================================================================
+
+/// Writes log records to stderr in headless mode
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{} {}", record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static STDERR_LOGGER: StderrLogger = StderrLogger;
+
+/// Runs the input loop with events written to `sink`; blocks until killed
+fn run_headless(sink: Arc<dyn EventSink>) {
+    let (screen_width, screen_height) = input::detect_screen_size();
+    let mut input_state = InputState::new(screen_width, screen_height);
+    input_state.set_monitors(input::detect_monitors());
+    let shared_state = Arc::new(SharedState::new(input_state));
+
+    input::run_input_loop(sink, shared_state);
+}
+
+/// Serves events as JSON on the Unix socket at `json_path` and as
+/// MessagePack on the one at `msgpack_path`; blocks until killed
+#[cfg(unix)]
+fn run_socket(json_path: Option<&str>, msgpack_path: Option<&str>) {
+    let sink = SocketSink::new();
+    let mut listening = false;
+    let sockets = [
+        (json_path, SocketEncoding::Json),
+        (msgpack_path, SocketEncoding::MessagePack),
+    ];
+    for (path, encoding) in sockets {
+        let Some(path) = path else {
+            continue;
+        };
+        match sink.listen(std::path::Path::new(path), encoding) {
+            Ok(()) => {
+                log::info!(
+                    "[Backend] Serving input events on {} ({:?})",
+                    path,
+                    encoding
+                );
+                listening = true;
+            }
+            Err(e) => log::warn!("[Backend] Could not bind socket {}: {}", path, e),
+        }
+    }
+    if listening {
+        run_headless(Arc::new(sink));
+    }
+}
+
+#[cfg(not(unix))]
+fn run_socket(_json_path: Option<&str>, _msgpack_path: Option<&str>) {
+    log::warn!("[Backend] --socket is only supported on Linux and macOS");
+}
+
+/// The sink the background threads emit to: the app itself, or with
+/// `--record-events <path>` the app wrapped in a RecordingSink
+fn event_sink(app_handle: &AppHandle, record_path: Option<&str>) -> Arc<dyn EventSink> {
+    if let Some(path) = record_path {
+        match RecordingSink::create(app_handle.clone(), std::path::Path::new(path)) {
+            Ok(sink) => {
+                log::info!("[Backend] Recording events to {}", path);
+                return Arc::new(sink);
+            }
+            Err(e) => log::warn!("[Backend] Could not record events to {}: {}", path, e),
+        }
+    }
+    Arc::new(app_handle.clone())
+}
+
+/// Emits the events of a `--record-events` file at their original cadence
+/// (`--replay-events <path>`), in place of live input, so timing-dependent
+/// frontend bugs can be reproduced. Returns when the recording ends.
+fn replay_events(app_handle: &AppHandle, path: &str) {
+    let events = match shared::read_recording(std::path::Path::new(path)) {
+        Ok(events) => events,
+        Err(e) => {
+            log::warn!("[Replay] Could not read {}: {}", path, e);
+            return;
+        }
+    };
+    log::info!("[Replay] Replaying {} events from {}", events.len(), path);
+    let start = Instant::now();
+    for recorded in events {
+        let due = start + Duration::from_millis(recorded.ms);
+        if let Some(wait) = due.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+        let _ = app_handle.emit(&recorded.channel, &recorded.event);
+    }
+    log::info!("[Replay] Finished {}", path);
+}
+
 // =============================================================================
 // Main Application Entry Point
 // =============================================================================
@@ -234,6 +1047,53 @@ fn check_fullscreen() -> bool {
 // =============================================================================
 
 fn main() {
+    // =========================================================
+    // Windows: Per-Monitor DPI Awareness
+    // =========================================================
+    // Cursor positions are converted to the webview's logical pixels using
+    // the window's DPI, which is only accurate if Windows doesn't virtualize
+    // coordinates for us. This MUST run before any window is created.
+    #[cfg(target_os = "windows")]
+    input::enable_dpi_awareness();
+
+    // Headless mode skips the webview entirely (see run_headless)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let socket_flags = ["--socket", "--msgpack-socket"];
+    let socket = args.iter().any(|arg| socket_flags.contains(&arg.as_str()));
+    let headless = socket || args.iter().any(|arg| arg == "--json-stdout");
+    if headless && log::set_logger(&STDERR_LOGGER).is_ok() {
+        log::set_max_level(log::LevelFilter::Info);
+    }
+    if socket {
+        let socket_path = |flag: &str| {
+            let i = args.iter().position(|arg| arg == flag)?;
+            let path = args.get(i + 1).map(String::as_str);
+            if path.is_none() {
+                log::warn!("[Backend] {} needs a path", flag);
+            }
+            path
+        };
+        run_socket(socket_path("--socket"), socket_path("--msgpack-socket"));
+        return;
+    }
+    if args.iter().any(|arg| arg == "--json-stdout") {
+        run_headless(Arc::new(StdoutSink));
+        return;
+    }
+
+    // Debug flags: record the emitted events to a file, or play a
+    // recording back instead of tracking live input
+    let flag_value = |flag: &str| {
+        let i = args.iter().position(|arg| arg == flag)?;
+        let value = args.get(i + 1).cloned();
+        if value.is_none() {
+            log::warn!("[Backend] {} needs a path", flag);
+        }
+        value
+    };
+    let record_path = flag_value("--record-events");
+    let replay_path = flag_value("--replay-events");
+
     // =========================================================
     // Wayland Fallback: Force XWayland if layer-shell unsupported
     // =========================================================
@@ -265,18 +1125,37 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             save_config,
             load_config,
+            reload_config,
+            get_shortcuts,
+            set_shortcuts,
             check_fullscreen,
+            list_devices,
             sync_cursor,
+            get_cursor_position,
+            set_axis_lock,
+            recent_events,
+            get_stats,
+            reset_stats,
+            trigger_shortcut,
             update_interactive_bounds,
             update_character_bounds,
+            set_character_position,
+            set_full_tracking,
+            shutdown,
             get_themes_dir,
-            update_interactive_bounds,
-            update_character_bounds,
-            get_themes_dir,
-            list_external_themes
+            list_external_themes,
+            await_next_click,
+            run_self_test,
+            move_character,
+            center_character,
+            set_click_through,
+            set_full_interactive,
+            set_anchor,
+            move_to_monitor,
+            show_context_menu
         ])
         // Setup hook: Runs once before the main window is created
-        .setup(|app| {
+        .setup(move |app| {
             let app_handle = app.handle().clone();
 
             // =========================================================
@@ -285,9 +1164,25 @@ fn main() {
             // Detect the screen resolution and create a shared state
             // that both the main thread and input thread can access.
             let (screen_width, screen_height) = input::detect_screen_size();
-            let shared_state = Arc::new(SharedState {
-                input_state: Mutex::new(InputState::new(screen_width, screen_height)),
-            });
+            let mut input_state = InputState::new(screen_width, screen_height);
+            input_state.set_monitors(input::detect_monitors());
+
+            // Load settings before the input thread starts, so rebound
+            // shortcuts work before the frontend has loaded
+            let config = AppConfig::load(&app_handle);
+            if let Some(config) = &config {
+                config.apply(&mut input_state);
+            }
+            let shared_state = Arc::new(SharedState::new(input_state));
+            if let Some(config) = &config {
+                config.apply_shared(&shared_state);
+                // Stay paused across restarts
+                shared_state.set_paused(&app_handle, config.paused);
+            }
+            register_window_actions(&app_handle, &shared_state);
+            if let Err(e) = setup_tray(&app_handle) {
+                log::warn!("[Backend] Could not create the tray icon: {}", e);
+            }
 
             // =========================================================
             // Enforce Always-on-Top
@@ -297,21 +1192,74 @@ fn main() {
             // On native X11 sessions, it works out of the box.
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.set_always_on_top(true);
+
+                // Restore the manual click-through setting
+                if config.as_ref().is_some_and(|config| config.click_through) {
+                    let _ = window.set_ignore_cursor_events(true);
+                }
             }
 
             // Register the shared state with Tauri so IPC commands can access it
             app.manage(shared_state.clone());
 
+            // =========================================================
+            // Track Which Monitor the Character Is On
+            // =========================================================
+            // Dragging the window across displays changes its bounds and
+            // DPI scale, so re-resolve the monitor on every move. The same
+            // handler stops the input thread when the window closes.
+            if let Some(window) = app.get_webview_window("main") {
+                track_window_monitor(&app_handle, &window, &shared_state);
+
+                let geometry_app = app_handle.clone();
+                let geometry_window = window.clone();
+                let geometry_state = shared_state.clone();
+                window.on_window_event(move |event| match event {
+                    WindowEvent::Moved(_) | WindowEvent::ScaleFactorChanged { .. } => {
+                        track_window_monitor(&geometry_app, &geometry_window, &geometry_state);
+                    }
+                    // Closing the character window quits the app, so stop the
+                    // input thread rather than leaking it
+                    WindowEvent::CloseRequested { .. } | WindowEvent::Destroyed => {
+                        input::stop_input_loop(&geometry_state);
+                    }
+                    _ => {}
+                });
+            }
+
             // =========================================================
             // Launch Input Monitoring Thread
             // =========================================================
             // This background thread reads raw input from /dev/input on Linux
             // or the Win32 API on Windows. It tracks the global cursor position
             // and detects global keyboard shortcuts, then emits events to the frontend.
-            let app_handle_clone = app_handle.clone();
-            thread::spawn(move || {
-                input::run_input_loop(app_handle_clone, shared_state);
-            });
+            // A replay stands in for live input, so the two don't mix
+            if let Some(path) = replay_path {
+                let replay_app = app_handle.clone();
+                thread::spawn(move || replay_events(&replay_app, &path));
+            } else {
+                let sink = event_sink(&app_handle, record_path.as_deref());
+                let input_sink = sink.clone();
+                let trail_state = shared_state.clone();
+                let settle_state = shared_state.clone();
+                let foreground_state = shared_state.clone();
+                thread::spawn(move || {
+                    input::run_input_loop(input_sink, shared_state);
+                });
+
+                // Evenly spaced cursor samples for trail effects (idle unless
+                // `cursorTrailIntervalMs` is set)
+                let trail_sink = sink.clone();
+                thread::spawn(move || trail_state.run_cursor_trail(&trail_sink));
+
+                // Corrects the last predicted cursor report once the pointer
+                // stops (idle unless `cursorPredictionMs` is set)
+                let settle_sink = sink.clone();
+                thread::spawn(move || settle_state.run_prediction_settle(&settle_sink));
+
+                // Foreground app, for ForegroundApp events and per-app profiles
+                thread::spawn(move || input::watch_foreground_app(&sink, &foreground_state));
+            }
 
             // =========================================================
             // Platform-Specific Settings
@@ -9,15 +9,25 @@
 // KEY COMPONENTS:
 // - KeyCode: Platform-agnostic representation of keyboard keys
 // - Rect: Rectangle for UI element bounds checking
+// - Monitors: Layout of all connected displays
+// - ShortcutBinding: User-configurable modifier+key → action mapping
 // - OutputEvent: Events emitted from backend to frontend
 // - InputState: Mutable state tracking cursor, modifiers, and shortcuts
+// - EventSink: Where emitted events go (the webview, or stdout)
+// - EventInterceptor: Extension hook that can inspect/drop/replace events
 // - SharedState: Thread-safe wrapper around InputState
+// - InputStats: Event counters for performance tuning
+// - SelfTestReport: Checklist returned by the input pipeline self-test
 //
 // =============================================================================
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::sync::Mutex;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
 
 // =============================================================================
 // KeyCode Enumeration
@@ -46,20 +56,409 @@ pub enum KeyCode {
     LeftMeta, // Super/Windows key
     RightMeta,
 
-    // Letter keys used in shortcuts
-    F, // Toggle chat (Meta+Shift+F)
-    D, // Toggle drag mode (Meta+Shift+D)
-    S, // Toggle screensaver (Meta+Shift+S)
-    C, // Center character on screen (Meta+Shift+C)
-    A, // Reserved for future use
+    // Letter keys
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+
+    // Digit keys (top row, not numpad)
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+
+    // Function keys
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+
+    // Lock keys. These toggle rather than act while held, so shortcuts test
+    // whether the lock is on (see InputState::locks_on), not whether the key
+    // is down
+    CapsLock,
+    NumLock,
 
     // Legacy/unused variants (kept for compatibility)
     #[allow(dead_code)]
     SKey, // Duplicate of S (kept to avoid breaking changes)
-    #[allow(dead_code)]
     Unknown, // Fallback for unmapped keys
 }
 
+impl KeyCode {
+    /// Returns the snake_case name of a modifier key (e.g. "right_meta"),
+    /// or None for non-modifier keys
+    pub fn modifier_name(&self) -> Option<&'static str> {
+        match self {
+            KeyCode::LeftShift => Some("left_shift"),
+            KeyCode::RightShift => Some("right_shift"),
+            KeyCode::LeftCtrl => Some("left_ctrl"),
+            KeyCode::RightCtrl => Some("right_ctrl"),
+            KeyCode::LeftAlt => Some("left_alt"),
+            KeyCode::RightAlt => Some("right_alt"),
+            KeyCode::LeftMeta => Some("left_meta"),
+            KeyCode::RightMeta => Some("right_meta"),
+            _ => None,
+        }
+    }
+
+    /// True for keys with toggle semantics (Caps Lock, Num Lock)
+    pub fn is_lock(&self) -> bool {
+        matches!(self, KeyCode::CapsLock | KeyCode::NumLock)
+    }
+
+    /// True for letters and top-row digits, the keys whose characters
+    /// depend on the keyboard layout
+    pub fn is_character(&self) -> bool {
+        self.name().chars().count() == 1
+    }
+
+    /// Display name of the key: "A", "7", "F5", or the modifier name
+    /// (e.g. "left_shift")
+    pub fn name(&self) -> String {
+        if let Some(name) = self.modifier_name() {
+            return name.to_string();
+        }
+        let debug = format!("{:?}", self);
+        match debug.strip_prefix("Digit") {
+            Some(digit) => digit.to_string(),
+            None => debug,
+        }
+    }
+
+    /// Maps a character to its key, case-insensitively (e.g. 'g' → G).
+    /// Returns None for characters with no KeyCode variant.
+    pub fn from_char(c: char) -> Option<KeyCode> {
+        match c.to_ascii_uppercase() {
+            'A' => Some(KeyCode::A),
+            'B' => Some(KeyCode::B),
+            'C' => Some(KeyCode::C),
+            'D' => Some(KeyCode::D),
+            'E' => Some(KeyCode::E),
+            'F' => Some(KeyCode::F),
+            'G' => Some(KeyCode::G),
+            'H' => Some(KeyCode::H),
+            'I' => Some(KeyCode::I),
+            'J' => Some(KeyCode::J),
+            'K' => Some(KeyCode::K),
+            'L' => Some(KeyCode::L),
+            'M' => Some(KeyCode::M),
+            'N' => Some(KeyCode::N),
+            'O' => Some(KeyCode::O),
+            'P' => Some(KeyCode::P),
+            'Q' => Some(KeyCode::Q),
+            'R' => Some(KeyCode::R),
+            'S' => Some(KeyCode::S),
+            'T' => Some(KeyCode::T),
+            'U' => Some(KeyCode::U),
+            'V' => Some(KeyCode::V),
+            'W' => Some(KeyCode::W),
+            'X' => Some(KeyCode::X),
+            'Y' => Some(KeyCode::Y),
+            'Z' => Some(KeyCode::Z),
+            '0' => Some(KeyCode::Digit0),
+            '1' => Some(KeyCode::Digit1),
+            '2' => Some(KeyCode::Digit2),
+            '3' => Some(KeyCode::Digit3),
+            '4' => Some(KeyCode::Digit4),
+            '5' => Some(KeyCode::Digit5),
+            '6' => Some(KeyCode::Digit6),
+            '7' => Some(KeyCode::Digit7),
+            '8' => Some(KeyCode::Digit8),
+            '9' => Some(KeyCode::Digit9),
+            _ => None,
+        }
+    }
+
+    /// Maps a key name from config to its key: a single letter or digit
+    /// ("G", "7") or a function key ("F5"), case-insensitively
+    pub fn from_name(name: &str) -> Option<KeyCode> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return KeyCode::from_char(c);
+        }
+        match name.to_ascii_uppercase().as_str() {
+            "F1" => Some(KeyCode::F1),
+            "F2" => Some(KeyCode::F2),
+            "F3" => Some(KeyCode::F3),
+            "F4" => Some(KeyCode::F4),
+            "F5" => Some(KeyCode::F5),
+            "F6" => Some(KeyCode::F6),
+            "F7" => Some(KeyCode::F7),
+            "F8" => Some(KeyCode::F8),
+            "F9" => Some(KeyCode::F9),
+            "F10" => Some(KeyCode::F10),
+            "F11" => Some(KeyCode::F11),
+            "F12" => Some(KeyCode::F12),
+            "CAPSLOCK" | "CAPS_LOCK" => Some(KeyCode::CapsLock),
+            "NUMLOCK" | "NUM_LOCK" => Some(KeyCode::NumLock),
+            _ => None,
+        }
+    }
+
+    /// Modifier keys matched by a binding's modifier name.
+    /// Generic names ("shift") match either side; sided names
+    /// ("left_shift") match only that key.
+    pub fn from_modifier_name(name: &str) -> &'static [KeyCode] {
+        match name.to_ascii_lowercase().as_str() {
+            "shift" => &[KeyCode::LeftShift, KeyCode::RightShift],
+            "ctrl" | "control" => &[KeyCode::LeftCtrl, KeyCode::RightCtrl],
+            "alt" | "option" => &[KeyCode::LeftAlt, KeyCode::RightAlt],
+            "meta" | "super" | "cmd" | "win" => &[KeyCode::LeftMeta, KeyCode::RightMeta],
+            "left_shift" => &[KeyCode::LeftShift],
+            "right_shift" => &[KeyCode::RightShift],
+            "left_ctrl" => &[KeyCode::LeftCtrl],
+            "right_ctrl" => &[KeyCode::RightCtrl],
+            "left_alt" => &[KeyCode::LeftAlt],
+            "right_alt" => &[KeyCode::RightAlt],
+            "left_meta" => &[KeyCode::LeftMeta],
+            "right_meta" => &[KeyCode::RightMeta],
+            // Satisfied while the lock is on, not while the key is held
+            "caps_lock" => &[KeyCode::CapsLock],
+            "num_lock" => &[KeyCode::NumLock],
+            _ => &[],
+        }
+    }
+}
+
+// =============================================================================
+// Shortcut Bindings (User-Configurable)
+// =============================================================================
+//
+// A shortcut fires `action` when `key` is pressed while every listed modifier
+// is held. Bindings are read from the "shortcutBindings" list in config.json
+// (see AppConfig in config.rs; the "shortcuts" key is already used by the
+// frontend's global-shortcut settings):
+//
+// ```json
+// "shortcutBindings": [
+//     { "modifiers": ["meta", "shift"], "key": "F", "action": "toggle_chat" }
+// ]
+// ```
+//
+// The "caps_lock" and "num_lock" modifiers are different: they match while
+// that lock is ON, whether or not the key is held. For example
+// `{ "modifiers": ["caps_lock"], "key": "F1", ... }` fires on F1 only while
+// Caps Lock is on.
+//
+// Setting `doubleTap` instead fires the action when the modifier named by
+// `key` is tapped twice on its own (no other key pressed in between):
+//
+// ```json
+// { "modifiers": [], "key": "left_shift", "action": "toggle_chat", "doubleTap": true }
+// ```
+//
+// When the config has no bindings, the four Meta+Shift defaults are used.
+//
+// By default a letter or digit `key` names a physical key, by its position
+// on a US QWERTY keyboard: "Q" is the key right of Tab, even where an AZERTY
+// layout types "a" with it. Setting "shortcutKeyMode" to "logical" matches
+// the character the active layout types instead, so "A" is the key that
+// types "a". The layout is read when input tracking starts (X11 keymap on
+// Linux, the foreground window's layout on Windows); macOS always matches
+// physically for now.
+//
+// The frontend handles most actions, but window actions ("center_character",
+// "toggle_visibility", "quit") also run in Rust so they work while the webview
+// is busy (see register_window_actions in main.rs). Listing an action in
+// "frontendOnlyActions" leaves it to the frontend alone.
+//
+// =============================================================================
+
+/// How letter and digit shortcut keys are matched (see above)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShortcutKeyMode {
+    /// By key position, ignoring the keyboard layout
+    Physical,
+    /// By the character the active keyboard layout types
+    Logical,
+}
+
+impl ShortcutKeyMode {
+    /// Parses "physical" or "logical" (case-insensitively)
+    pub fn from_name(name: &str) -> Option<ShortcutKeyMode> {
+        match name.to_ascii_lowercase().as_str() {
+            "physical" => Some(ShortcutKeyMode::Physical),
+            "logical" => Some(ShortcutKeyMode::Logical),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ShortcutBinding {
+    /// Modifier names that must all be held, e.g. ["meta", "shift"]
+    pub modifiers: Vec<String>,
+    /// The key that triggers the shortcut, e.g. "F", "7" or "F5"
+    pub key: String,
+    /// Shortcut name emitted to the frontend, e.g. "toggle_chat"
+    pub action: String,
+    /// Fire on a double tap of the modifier named by `key` (e.g. "shift")
+    /// instead of on a key press
+    #[serde(
+        rename = "doubleTap",
+        default,
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub double_tap: bool,
+}
+
+impl ShortcutBinding {
+    fn new(modifiers: &[&str], key: &str, action: &str) -> Self {
+        Self {
+            modifiers: modifiers.iter().map(|m| m.to_string()).collect(),
+            key: key.to_string(),
+            action: action.to_string(),
+            double_tap: false,
+        }
+    }
+
+    /// The built-in Meta+Shift bindings, used when config defines none
+    pub fn defaults() -> Vec<ShortcutBinding> {
+        vec![
+            Self::new(&["meta", "shift"], "F", "toggle_chat"),
+            Self::new(&["meta", "shift"], "D", "toggle_drag"),
+            Self::new(&["meta", "shift"], "S", "toggle_screensaver"),
+            Self::new(&["meta", "shift"], "C", "center_character"),
+            Self::new(&["meta", "shift"], "P", "toggle_pause"),
+        ]
+    }
+
+    /// The key this binding triggers on
+    pub fn key_code(&self) -> Option<KeyCode> {
+        KeyCode::from_name(&self.key)
+    }
+
+    /// True if the key and every modifier name are recognised. Double-tap
+    /// bindings need a modifier as the key and no other modifiers.
+    pub fn is_valid(&self) -> bool {
+        if self.double_tap {
+            return self.modifiers.is_empty() && !KeyCode::from_modifier_name(&self.key).is_empty();
+        }
+        self.key_code().is_some()
+            && self
+                .modifiers
+                .iter()
+                .all(|m| !KeyCode::from_modifier_name(m).is_empty())
+    }
+
+    /// Checks bindings that are about to replace the live ones: each must
+    /// be valid, and no two may use the same modifiers and key
+    pub fn check_all(bindings: &[ShortcutBinding]) -> Result<(), String> {
+        for (i, binding) in bindings.iter().enumerate() {
+            if !binding.is_valid() {
+                return Err(format!(
+                    "Invalid shortcut for {}: {}",
+                    binding.action,
+                    binding.combo_name()
+                ));
+            }
+            if let Some(other) = bindings[..i]
+                .iter()
+                .find(|other| other.conflicts_with(binding))
+            {
+                return Err(format!(
+                    "{} and {} are both bound to {}",
+                    other.action,
+                    binding.action,
+                    binding.combo_name()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// True if both bindings fire on the same key combo, however their
+    /// names are spelled ("ctrl" vs "Control", "f" vs "F")
+    fn conflicts_with(&self, other: &ShortcutBinding) -> bool {
+        let modifiers = |binding: &ShortcutBinding| {
+            binding
+                .modifiers
+                .iter()
+                .map(|m| KeyCode::from_modifier_name(m))
+                .collect::<HashSet<_>>()
+        };
+        let same_key = if self.double_tap {
+            KeyCode::from_modifier_name(&self.key) == KeyCode::from_modifier_name(&other.key)
+        } else {
+            self.key_code() == other.key_code()
+        };
+        self.double_tap == other.double_tap && same_key && modifiers(self) == modifiers(other)
+    }
+
+    /// The combo as written in the binding, e.g. "meta+shift+F"
+    fn combo_name(&self) -> String {
+        if self.double_tap {
+            return format!("double-tap {}", self.key);
+        }
+        let mut parts = self.modifiers.clone();
+        parts.push(self.key.clone());
+        parts.join("+")
+    }
+}
+
+/// Shortcut actions to switch off while one of `apps` is in the foreground
+/// (e.g. so Meta+Shift+F doesn't open the chat inside a game)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AppProfile {
+    /// Foreground app names as reported in ForegroundApp events (X11
+    /// WM_CLASS, Windows process name, macOS app name), case-insensitive
+    pub apps: Vec<String>,
+
+    /// Actions to disable; "*" disables every shortcut but `toggle_pause`
+    pub disabled_actions: Vec<String>,
+}
+
+impl AppProfile {
+    fn matches(&self, app: &str) -> bool {
+        self.apps.iter().any(|name| name.eq_ignore_ascii_case(app))
+    }
+
+    fn disables(&self, action: &str) -> bool {
+        self.disabled_actions
+            .iter()
+            .any(|disabled| disabled == action || (disabled == "*" && action != "toggle_pause"))
+    }
+}
+
 // =============================================================================
 // Rectangle (UI Bounds)
 // =============================================================================
@@ -73,7 +472,7 @@ pub enum KeyCode {
 //
 // =============================================================================
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
 pub struct Rect {
     pub x: i32,      // Top-left X coordinate (screen space)
     pub y: i32,      // Top-left Y coordinate (screen space)
@@ -83,18 +482,203 @@ pub struct Rect {
 
 impl Rect {
     /// Checks if a point (x, y) is inside this rectangle
-    #[allow(dead_code)] // May be used in future for click detection
     pub fn contains(&self, x: i32, y: i32) -> bool {
         x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
     }
 }
 
+// =============================================================================
+// Device Listing
+// =============================================================================
+
+/// One input device as seen by the backend, for `list_devices`.
+/// On Windows and macOS, where the OS hides individual devices, there is one
+/// synthetic entry per kind.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DeviceInfo {
+    /// Device node (e.g. "/dev/input/event3"), or the OS API it comes through
+    pub path: String,
+    pub name: String,
+    /// "mouse", "keyboard" or "absolute-pointer" (touchscreen or tablet)
+    pub kind: String,
+    /// Whether input can actually be read from it
+    pub readable: bool,
+}
+
+// =============================================================================
+// Input Statistics
+// =============================================================================
+
+/// Event counts since the last reset, for `get_stats`. Shows whether
+/// throttling keeps the IPC traffic down.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InputStats {
+    /// Time covered by the counts
+    pub seconds: f64,
+    /// Cursor events delivered to the frontend
+    pub cursor_events: u64,
+    pub cursor_events_per_second: f64,
+    pub clicks: u64,
+    pub shortcuts: u64,
+    /// Relative moves merged into a later Cursor event by throttling
+    pub coalesced_cursor_moves: u64,
+    /// Cursor events held back because the cursor was outside the
+    /// attention radius
+    pub suppressed_cursor_events: u64,
+    /// Events discarded while paused or by an interceptor
+    pub dropped_events: u64,
+    /// Raw device events discarded because the device was over
+    /// `max_device_events_per_sec`
+    pub flood_dropped_events: u64,
+}
+
+/// Counters behind InputStats, updated from the emit paths
+#[derive(Default)]
+struct StatCounters {
+    cursor_events: AtomicU64,
+    clicks: AtomicU64,
+    shortcuts: AtomicU64,
+    suppressed_cursor_events: AtomicU64,
+    dropped_events: AtomicU64,
+    flood_dropped_events: AtomicU64,
+}
+
+impl StatCounters {
+    fn bump(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// =============================================================================
+// Monitor Layout
+// =============================================================================
+//
+// All connected displays in global screen coordinates. The real cursor can
+// roam across every monitor, so the tracked cursor is clamped against this
+// layout rather than a single screen. Monitors of different sizes leave
+// "dead zones" inside their bounding box that the cursor can't reach; points
+// there snap to the nearest monitor.
+//
+// =============================================================================
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Monitors {
+    pub rects: Vec<Rect>,
+}
+
+impl Monitors {
+    /// A single monitor at the origin
+    pub fn single(width: i32, height: i32) -> Self {
+        Self {
+            rects: vec![Rect {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            }],
+        }
+    }
+
+    /// Bounding box enclosing every monitor
+    pub fn bounds(&self) -> Rect {
+        let Some(first) = self.rects.first() else {
+            return Rect::default();
+        };
+        let (mut left, mut top) = (first.x, first.y);
+        let (mut right, mut bottom) = (first.x + first.width, first.y + first.height);
+        for rect in &self.rects[1..] {
+            left = left.min(rect.x);
+            top = top.min(rect.y);
+            right = right.max(rect.x + rect.width);
+            bottom = bottom.max(rect.y + rect.height);
+        }
+        Rect {
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+        }
+    }
+
+    /// Returns the closest on-screen point to (x, y): the point itself if it
+    /// lies on a monitor, otherwise the nearest point on the nearest monitor
+    pub fn clamp(&self, x: i32, y: i32) -> (i32, i32) {
+        self.rects
+            .iter()
+            .map(|rect| {
+                let cx = x.clamp(rect.x, rect.x + rect.width - 1);
+                let cy = y.clamp(rect.y, rect.y + rect.height - 1);
+                let distance = (cx - x).pow(2) as i64 + (cy - y).pow(2) as i64;
+                (distance, cx, cy)
+            })
+            .min_by_key(|&(distance, _, _)| distance)
+            .map_or((x, y), |(_, cx, cy)| (cx, cy))
+    }
+
+    /// The layout in logical pixels, for a display scale of `scale_factor`
+    /// physical pixels per logical one. Edges are rounded separately so
+    /// neighbouring monitors stay adjacent.
+    pub fn to_logical(&self, scale_factor: f64) -> Self {
+        let to_logical = |v: i32| (v as f64 / scale_factor).round() as i32;
+        Self {
+            rects: self
+                .rects
+                .iter()
+                .map(|rect| {
+                    let (x, y) = (to_logical(rect.x), to_logical(rect.y));
+                    Rect {
+                        x,
+                        y,
+                        width: to_logical(rect.x + rect.width) - x,
+                        height: to_logical(rect.y + rect.height) - y,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Snaps (x, y) onto an outer edge of the desktop when it lies within
+    /// `zone` pixels of one. Returns the point and whether it is at such an
+    /// edge. Edges shared with a neighbouring monitor don't count, since the
+    /// cursor carries on across them.
+    pub fn snap_to_edge(&self, x: i32, y: i32, zone: i32) -> (i32, i32, bool) {
+        let on_screen = |px: i32, py: i32| {
+            self.rects.iter().any(|rect| {
+                px >= rect.x
+                    && px < rect.x + rect.width
+                    && py >= rect.y
+                    && py < rect.y + rect.height
+            })
+        };
+        let Some(rect) = self.rects.iter().find(|rect| {
+            x >= rect.x && x < rect.x + rect.width && y >= rect.y && y < rect.y + rect.height
+        }) else {
+            return (x, y, false);
+        };
+        let (right, bottom) = (rect.x + rect.width - 1, rect.y + rect.height - 1);
+        let (mut sx, mut sy, mut at_edge) = (x, y, false);
+        if x - rect.x <= zone && !on_screen(rect.x - 1, y) {
+            (sx, at_edge) = (rect.x, true);
+        } else if right - x <= zone && !on_screen(right + 1, y) {
+            (sx, at_edge) = (right, true);
+        }
+        if y - rect.y <= zone && !on_screen(x, rect.y - 1) {
+            (sy, at_edge) = (rect.y, true);
+        } else if bottom - y <= zone && !on_screen(x, bottom + 1) {
+            (sy, at_edge) = (bottom, true);
+        }
+        (sx, sy, at_edge)
+    }
+}
+
 // =============================================================================
 // Output Events (Backend → Frontend)
 // =============================================================================
 //
 // Events emitted from the Rust backend to the TypeScript frontend.
-// These are sent via Tauri's event system using `app_handle.emit()`.
+// These are sent via Tauri's event system using `app_handle.emit()` (or written
+// to stdout as JSON lines in `--json-stdout` mode, see EventSink).
 //
 // The frontend listens for these events using:
 // ```typescript
@@ -107,36 +691,283 @@ impl Rect {
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum OutputEvent {
     /// Emitted when the global cursor position changes
-    /// Frontend uses this for hover effects and tracking
-    Cursor { x: i32, y: i32 },
+    /// Frontend uses this for hover effects and tracking.
+    /// `buttons` has a bit set for each mouse button held at the time
+    /// (MOUSE_BUTTON_LEFT/RIGHT/MIDDLE, the same bits as a DOM
+    /// MouseEvent's `buttons`).
+    /// `at_edge` is set when the cursor is on (or within `edge_dead_zone`
+    /// pixels of, and snapped onto) an outer edge of the desktop.
+    /// With `cursor_prediction` set, x/y lead the real position slightly
+    /// while the cursor moves, and the real position is sent again once
+    /// it stops.
+    Cursor {
+        x: i32,
+        y: i32,
+        buttons: u8,
+        at_edge: bool,
+    },
+
+    /// Emitted alongside Cursor when `cursor_velocity` is on. `vx`/`vy` are
+    /// the smoothed pointer velocity in pixels per second, for animations
+    /// that follow the direction of motion.
+    CursorMotion {
+        x: i32,
+        y: i32,
+        vx: f64,
+        vy: f64,
+    },
+
+    /// Emitted every `cursor_trail_interval` while the cursor is moving,
+    /// easing toward its latest position, so trail effects get evenly
+    /// spaced samples instead of following the irregular hardware reports
+    CursorTrail {
+        x: i32,
+        y: i32,
+    },
+
+    /// Emitted instead of the very first Cursor event after startup (when
+    /// `startup_cursor_mode` is Jump). The tracked position may be far from
+    /// where the frontend assumed, so it should snap rather than animate.
+    CursorJump {
+        x: i32,
+        y: i32,
+    },
 
     /// Emitted when a global keyboard shortcut is detected
     /// Examples: "toggle_chat", "toggle_drag", "toggle_screensaver"
-    Shortcut { name: String },
+    /// `modifiers` lists the exact modifier keys held when it fired,
+    /// e.g. ["left_shift", "right_meta"]
+    Shortcut {
+        name: String,
+        modifiers: Vec<String>,
+    },
 
     /// Emitted when a click is detected on an interactive region
     /// The frontend uses this as a fallback for click detection
+    /// `button` is "left", "right", "middle", "back" or "forward"; extra
+    /// buttons are "task" (Linux BTN_TASK) or "button<n>" (the nth button),
+    /// so tilt-click and other extra buttons can be told apart.
     Click {
-        button: String, // "left", "right", "middle"
+        button: String,
         x: i32,
         y: i32,
     },
 
+    /// Emitted when a non-modifier key is pressed or released (not on
+    /// auto-repeat). `key` is empty unless the user opted in to
+    /// `emit_keystrokes`, so by default only the fact that typing happened
+    /// reaches the frontend.
+    KeyDown {
+        key: String,
+    },
+    KeyUp {
+        key: String,
+    },
+
+    /// Emitted when a modifier is pressed or released and that changes
+    /// which kinds are held (left and right count as one)
+    ModifierState {
+        meta: bool,
+        shift: bool,
+        ctrl: bool,
+        alt: bool,
+    },
+
+    /// Emitted when the scroll wheel moves, in wheel notches.
+    /// Positive `dy` scrolls up (away from the user), positive `dx` right.
+    Scroll {
+        dx: i32,
+        dy: i32,
+    },
+
     /// Periodic heartbeat to indicate the input thread is alive
     Heartbeat,
 
     /// Emitted once when the input monitoring thread starts
-    /// Tells the frontend the screen resolution and device counts
+    /// Tells the frontend the screen resolution, monitor layout and device
+    /// counts. `screen_width`/`screen_height` span all monitors.
+    /// `device_names` lists the devices being read, for debugging (Linux
+    /// only; empty on platforms where the OS merges devices for us).
+    /// `scale_factor` is how many physical pixels make up one logical pixel
+    /// of the desktop (1.0 on macOS, where everything is in points). Windows
+    /// reports the cursor in logical pixels already; Linux reports physical
+    /// ones, which the frontend divides by it to match its own coordinates.
     Ready {
         mice_count: usize,
         keyboards_count: usize,
+        device_names: Vec<String>,
         screen_width: i32,
         screen_height: i32,
+        monitors: Vec<Rect>,
+        scale_factor: f64,
+    },
+
+    /// Emitted when input devices are plugged in or removed after startup
+    DevicesChanged {
+        mice_count: usize,
+        keyboards_count: usize,
+        device_names: Vec<String>,
+    },
+
+    /// Describes one input device: emitted when it is discovered and
+    /// whenever its battery level changes. `battery` is a percentage, or
+    /// None for wired devices and ones that don't report it.
+    #[allow(dead_code)] // Only the Linux backend reads device batteries
+    DeviceInfo {
+        name: String,
+        battery: Option<u8>,
     },
 
     /// Emitted when any input activity is detected
     /// Can be used by the frontend to prevent screensavers
     Activity,
+
+    /// Emitted once when there has been no input for `idle_timeout`.
+    /// `seconds` is how long the user has been away.
+    Idle {
+        seconds: u64,
+    },
+
+    /// Emitted on the first input after an Idle event
+    Active,
+
+    /// Emitted when a different app comes to the foreground. `name` is its
+    /// X11 WM_CLASS (Linux), process name (Windows) or app name (macOS),
+    /// or None if it can't be determined.
+    ForegroundApp {
+        name: Option<String>,
+    },
+
+    /// Emitted after `reload_config` applied config.json to the input thread,
+    /// or `set_shortcuts` replaced the shortcut bindings
+    ConfigReloaded,
+
+    /// Emitted when the `toggle_pause` shortcut suspends input monitoring.
+    /// Nothing else is emitted until Resumed.
+    Paused,
+
+    /// Emitted when the `toggle_pause` shortcut resumes input monitoring
+    Resumed,
+
+    /// Emitted instead of a regular click when `await_next_click` has armed
+    /// calibration. Reports the global position of that single click.
+    CalibrationClick {
+        x: i32,
+        y: i32,
+        button: String,
+    },
+
+    /// Emitted when calibration times out before any click arrives
+    CalibrationCancelled,
+
+    /// Emitted on each cursor move while the second press of a double-click
+    /// is still held ("double-click then drag", like selecting text)
+    DoubleClickDrag {
+        x: i32,
+        y: i32,
+    },
+
+    /// Emitted when a held left button first moves past the drag
+    /// threshold. Carries the position of the original press.
+    DragStart {
+        x: i32,
+        y: i32,
+    },
+
+    /// Emitted on each cursor move while a drag is in progress
+    DragMove {
+        x: i32,
+        y: i32,
+    },
+
+    /// Emitted when the left button is released after a drag
+    DragEnd {
+        x: i32,
+        y: i32,
+    },
+
+    /// Emitted after a DragEnd that dropped the character within
+    /// `snap_threshold` of a monitor edge: where to move the window
+    /// (top-left, physical pixels, as for `move_character`) so the
+    /// character sits flush against that edge
+    SnapTo {
+        x: i32,
+        y: i32,
+    },
+
+    /// Emitted on each update of a touchpad gesture. `kind` is "swipe" or
+    /// "pinch"; `dx`/`dy` are the motion since the last update and `scale`
+    /// is the pinch's finger spread relative to its start (1.0 for swipes).
+    #[allow(dead_code)] // Only the Linux libinput touchpad reader emits it
+    Gesture {
+        kind: String,
+        scale: f64,
+        dx: f64,
+        dy: f64,
+    },
+
+    /// Emitted when the cursor moves onto a different interactive region.
+    /// `index` is the region's position in the last `interactive_rects`
+    /// update, or None once the cursor is over none of them.
+    HoverRegion {
+        index: Option<usize>,
+    },
+
+    /// Emitted after the center_character shortcut moved the window: its
+    /// new top-left corner (physical pixels), for the frontend to save
+    CharacterCentered {
+        x: i32,
+        y: i32,
+    },
+
+    /// Emitted when the character window crosses onto a different monitor
+    /// Carries that monitor's bounds and DPI scale so the frontend can
+    /// re-layout for the new display
+    MonitorChanged {
+        name: Option<String>,
+        bounds: Rect,
+        scale_factor: f64,
+    },
+
+    /// Emitted when input monitoring can't work properly (no readable
+    /// devices, raw input registration failed, ...). `message` is
+    /// user-facing and says how to fix the problem where possible.
+    Error {
+        message: String,
+    },
+}
+
+// =============================================================================
+// Self-Test Report
+// =============================================================================
+//
+// Result of the `run_self_test` command: one checklist item per stage of the
+// input pipeline, so the UI can show the user exactly which step is broken
+// when "the character isn't following my mouse".
+//
+// =============================================================================
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SelfTestItem {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub items: Vec<SelfTestItem>,
+}
+
+impl SelfTestReport {
+    /// Builds a report; it passes only if every item passed
+    pub fn new(items: Vec<SelfTestItem>) -> Self {
+        Self {
+            passed: items.iter().all(|item| item.passed),
+            items,
+        }
+    }
 }
 
 // =============================================================================
@@ -160,16 +991,28 @@ pub struct InputState {
     /// Current global cursor Y position (screen coordinates)
     pub cursor_y: i32,
 
-    /// Screen width in pixels (detected at startup)
+    /// Width of the area spanned by all monitors, in pixels
     pub screen_width: i32,
 
-    /// Screen height in pixels (detected at startup)
+    /// Height of the area spanned by all monitors, in pixels
     pub screen_height: i32,
 
+    /// Layout of every connected monitor; the cursor is clamped to these
+    pub monitors: Monitors,
+
     /// Set of currently held modifier keys (Shift, Ctrl, Alt, Meta)
     /// Used for detecting keyboard shortcuts like Meta+Shift+F
     pub held_modifiers: HashSet<KeyCode>,
 
+    /// Lock keys (Caps Lock, Num Lock) that are currently ON. This is toggle
+    /// state read from the keyboard LEDs / OS, unrelated to whether the key
+    /// is held: pressing Caps Lock adds it here until the next press.
+    pub locks_on: HashSet<KeyCode>,
+
+    /// Trigger key of the last shortcut fired, until that key is released.
+    /// Auto-repeat (or a stuck key) must not fire the same shortcut again.
+    last_fired_shortcut: Option<KeyCode>,
+
     /// Last cursor position that was reported to the frontend
     /// Used to avoid spamming events when cursor hasn't moved
     pub last_reported_x: i32,
@@ -178,79 +1021,3121 @@ pub struct InputState {
     /// Interactive regions defined by the frontend
     /// These are the UI elements that should respond to clicks
     pub interactive_rects: Vec<Rect>,
-}
 
-/// Thread-safe wrapper around InputState
-pub struct SharedState {
-    pub input_state: Mutex<InputState>,
-}
+    /// Manual click-through override (`set_click_through`). While on, the
+    /// whole window lets clicks pass through, whatever `interactive_rects` say.
+    pub click_through: bool,
 
-impl InputState {
-    /// Creates a new InputState with the given screen dimensions
-    pub fn new(screen_width: i32, screen_height: i32) -> Self {
-        Self {
-            cursor_x: screen_width / 2,
-            cursor_y: screen_height / 2,
-            screen_width,
-            screen_height,
-            held_modifiers: HashSet::new(),
-            last_reported_x: -1,
-            last_reported_y: -1,
-            interactive_rects: Vec::new(),
-        }
-    }
+    /// Set while the frontend needs the whole window to take clicks (e.g.
+    /// while the chat input is focused). Hit-testing then treats the window
+    /// as one interactive rect; `interactive_rects` is kept for afterwards.
+    pub full_interactive: bool,
 
-    /// Updates the cursor position by a delta (relative movement).
-    /// Clamps to screen bounds and returns true if the position changed.
-    pub fn update_cursor(&mut self, delta_x: i32, delta_y: i32) -> bool {
-        self.cursor_x += delta_x;
-        self.cursor_y += delta_y;
+    /// The character's on-screen position, set by the frontend. Cursor
+    /// events are only sent within `attention_radius` of it.
+    pub character_position: Option<(i32, i32)>,
 
-        // Clamp to screen bounds
-        self.cursor_x = self.cursor_x.clamp(0, self.screen_width - 1);
-        self.cursor_y = self.cursor_y.clamp(0, self.screen_height - 1);
+    /// Distance in pixels from the character beyond which cursor moves
+    /// aren't reported (0 = report every move)
+    pub attention_radius: u32,
 
-        // Check if position changed since last report
-        let changed =
-            self.cursor_x != self.last_reported_x || self.cursor_y != self.last_reported_y;
-        if changed {
-            self.last_reported_x = self.cursor_x;
-            self.last_reported_y = self.cursor_y;
-        }
-        changed
-    }
+    /// Cursor reports within this many pixels of an outer screen edge are
+    /// snapped onto the edge and flagged `at_edge` (0 = only exactly on it)
+    pub edge_dead_zone: u32,
 
-    /// Checks if a specific modifier key is currently held down
-    pub fn is_modifier_held(&self, key: KeyCode) -> bool {
-        self.held_modifiers.contains(&key)
-    }
+    /// Set while a frontend feature needs every cursor move, overriding
+    /// `attention_radius`
+    pub full_tracking: bool,
 
-    /// Checks if a key press triggers a global shortcut.
-    /// Returns the shortcut name if matched, or None.
-    ///
-    /// Current shortcuts:
-    /// - Meta+Shift+F: "toggle_chat"
-    /// - Meta+Shift+D: "toggle_drag"
-    /// - Meta+Shift+S: "toggle_screensaver"
-    /// - Meta+Shift+C: "center_character"
-    pub fn check_shortcut(&self, trigger_key: KeyCode) -> Option<&'static str> {
-        // Check if Meta (Super/Windows) key is held
-        let meta_held =
-            self.is_modifier_held(KeyCode::LeftMeta) || self.is_modifier_held(KeyCode::RightMeta);
-        // Check if Shift key is held
-        let shift_held =
-            self.is_modifier_held(KeyCode::LeftShift) || self.is_modifier_held(KeyCode::RightShift);
-
-        // Shortcuts require Meta+Shift+Key
-        if meta_held && shift_held {
-            match trigger_key {
-                KeyCode::F => return Some("toggle_chat"),
-                KeyCode::D => return Some("toggle_drag"),
-                KeyCode::S => return Some("toggle_screensaver"),
-                KeyCode::C => return Some("center_character"),
-                _ => {}
-            }
-        }
-        None
+    /// Whether the last reported cursor position was inside the radius
+    cursor_attending: bool,
+
+    /// Id of the currently armed one-shot calibration capture, if any.
+    /// Each arm gets a fresh id so a stale timeout can't cancel a newer one.
+    calibration_armed: Option<u64>,
+    calibration_counter: u64,
+
+    /// Whether the left mouse button is currently held down
+    pub left_button_held: bool,
+
+    /// MOUSE_BUTTON_* bits of the buttons currently held
+    pub mouse_buttons: u8,
+
+    /// Time and position of the previous left press, for double-click detection
+    last_left_press: Option<(Instant, i32, i32)>,
+
+    /// True while the second press of a double-click is still held
+    double_click_held: bool,
+
+    /// Where the held left button was pressed (None when not held)
+    drag_origin: Option<(i32, i32)>,
+
+    /// True once the held left button has moved past DRAG_THRESHOLD
+    dragging: bool,
+
+    /// Per-device event rate above which that device's events are dropped
+    /// (a safety valve against malfunctioning hardware). 0 disables the cap.
+    pub max_device_events_per_sec: u32,
+
+    /// Bounds of the monitor the character window currently sits on
+    /// (None until the first window-move is seen)
+    pub window_monitor: Option<Rect>,
+
+    /// DPI scale factor of that monitor (1.0 = 96 DPI)
+    pub window_scale_factor: f64,
+
+    /// Top-left corner of the character window in physical pixels
+    /// (None until the window is first tracked)
+    pub window_position: Option<(i32, i32)>,
+
+    /// Corner the window is pinned to (`set_anchor`), re-applied when its
+    /// monitor's resolution changes
+    pub window_anchor: Option<Anchor>,
+
+    /// Interactive region the cursor was last reported over
+    hovered_region: Option<usize>,
+
+    /// Distance in physical pixels from a monitor edge within which a
+    /// dragged character snaps to it (0 = no snapping)
+    pub snap_threshold: u32,
+
+    /// Which edges snap
+    pub snap_edges: SnapEdges,
+
+    /// Largest movement (per axis, in pixels) accepted from a single
+    /// relative update. Anything beyond is treated as a hardware glitch and
+    /// discarded, so a bad report can't teleport the cursor. 0 disables.
+    pub max_delta_per_event: i32,
+
+    /// When a clamped delta was last logged, so a misbehaving device
+    /// can't flood the log
+    last_delta_warning: Option<Instant>,
+
+    /// Multiplier for relative movement, to match the OS pointer speed
+    /// (the compositor's acceleration isn't applied to raw deltas)
+    pub sensitivity: f32,
+
+    /// Sub-pixel movement left over after scaling by `sensitivity`, carried
+    /// into the next delta so slow motion isn't rounded away
+    sensitivity_remainder: (f32, f32),
+
+    /// Axis the tracked cursor can't move along (for precise placement);
+    /// movement on it is ignored, from relative and absolute sources alike
+    pub lock_axis: Option<Axis>,
+
+    /// How the first cursor report after startup is delivered
+    pub startup_cursor_mode: StartupCursorMode,
+
+    /// Whether the tracked cursor has been synced with the real one yet.
+    /// Until then the first report is handled per `startup_cursor_mode`.
+    pub cursor_synced: bool,
+
+    /// Active shortcut bindings, checked in order on every key press
+    pub shortcuts: Vec<ShortcutBinding>,
+
+    /// A modifier pressed on its own that may become a tap, with when
+    modifier_tap_start: Option<(KeyCode, Instant)>,
+
+    /// The last completed modifier tap and when it was released
+    last_modifier_tap: Option<(KeyCode, Instant)>,
+
+    /// Shortcut actions left entirely to the frontend, skipping their Rust
+    /// handler (e.g. to replace what "quit" does)
+    pub frontend_only_actions: Vec<String>,
+
+    /// Per-app shortcut overrides, checked against `foreground_app`
+    pub app_profiles: Vec<AppProfile>,
+
+    /// Whether shortcut keys match physically or by layout character
+    pub shortcut_key_mode: ShortcutKeyMode,
+
+    /// Physical letter/digit key → the key the active layout types with it,
+    /// for ShortcutKeyMode::Logical. Keys not listed type themselves.
+    pub keyboard_layout: HashMap<KeyCode, KeyCode>,
+
+    /// The app in the foreground, as last seen by the foreground watcher
+    pub foreground_app: Option<String>,
+
+    /// Whether KeyDown/KeyUp events carry the actual key (privacy opt-in)
+    pub emit_keystrokes: bool,
+
+    /// Keep shortcut keys from reaching the focused app (Linux only; grabs
+    /// the keyboards, see the Linux backend for the tradeoffs)
+    pub consume_shortcuts: bool,
+
+    /// How long without input before Idle is emitted (zero disables)
+    pub idle_timeout: Duration,
+
+    /// Whether `trigger_shortcut` may fire shortcuts in a release build
+    pub simulated_shortcuts: bool,
+
+    /// Longest a backend that polls with a timeout (Linux) waits for input
+    /// right after activity; the wait grows toward `max_poll_timeout` as
+    /// the user stays away (see `adaptive_poll_timeout`)
+    pub poll_timeout: Duration,
+    pub max_poll_timeout: Duration,
+
+    /// Whether relative movement is eased toward instead of reported raw
+    cursor_smoothing: bool,
+
+    /// Eased position while smoothing is catching up to the real cursor
+    /// (None when the reported position is the real one)
+    smoothed_cursor: Option<(f64, f64)>,
+
+    /// Minimum time between two Cursor events from relative movement.
+    /// Moves in between are coalesced into the next report; zero disables.
+    pub cursor_emit_interval: Duration,
+
+    /// When `update_cursor` last reported a move
+    last_cursor_emit: Option<Instant>,
+
+    /// True while a throttled move is waiting to be reported
+    cursor_emit_pending: bool,
+
+    /// Moves that landed while another was still pending, so only the later
+    /// one got reported (see `InputStats`)
+    coalesced_cursor_moves: u64,
+
+    /// Whether CursorMotion events (with velocity) are emitted too
+    pub cursor_velocity: bool,
+
+    /// Smoothed cursor velocity in pixels per second
+    velocity: (f64, f64),
+
+    /// Time and position of the previous cursor report, for the velocity
+    last_velocity_sample: Option<(Instant, i32, i32)>,
+
+    /// How far ahead Cursor events extrapolate the position along the
+    /// velocity, to make up for IPC and render latency (zero = off)
+    pub cursor_prediction: Duration,
+
+    /// True while the last Cursor event was a predicted point rather than
+    /// the real position, which must be re-sent once the pointer stops
+    prediction_unsettled: bool,
+
+    /// Time between CursorTrail samples (zero = trail off)
+    pub cursor_trail_interval: Duration,
+
+    /// Where the trail currently is, None while it's off
+    trail_position: Option<(f64, f64)>,
+}
+
+/// How the first cursor report after startup is delivered.
+///
+/// Before it, the backend only knows its guessed starting position (screen
+/// centre on Linux), so a regular Cursor event would make the character
+/// visibly jump across the screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StartupCursorMode {
+    /// Report it as a regular Cursor event
+    Emit,
+    /// Drop it; Cursor events start from the next movement
+    Suppress,
+    /// Report it as a CursorJump so the frontend can snap to it
+    Jump,
+}
+
+impl StartupCursorMode {
+    /// Parses "emit", "suppress" or "jump" (case-insensitively)
+    pub fn from_name(name: &str) -> Option<StartupCursorMode> {
+        match name.to_ascii_lowercase().as_str() {
+            "emit" => Some(StartupCursorMode::Emit),
+            "suppress" => Some(StartupCursorMode::Suppress),
+            "jump" => Some(StartupCursorMode::Jump),
+            _ => None,
+        }
+    }
+}
+
+/// A cursor axis, for locking movement to the other one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Axis {
+    /// Horizontal
+    X,
+    /// Vertical
+    Y,
+}
+
+impl Axis {
+    /// Parses "x" or "y" (case-insensitively)
+    pub fn from_name(name: &str) -> Option<Axis> {
+        match name.to_ascii_lowercase().as_str() {
+            "x" => Some(Axis::X),
+            "y" => Some(Axis::Y),
+            _ => None,
+        }
+    }
+}
+
+/// Where on a monitor to place the character window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Parses names like "bottom-right", "top" or "center"
+    /// (case-insensitively)
+    pub fn from_name(name: &str) -> Option<Anchor> {
+        match name.to_ascii_lowercase().as_str() {
+            "top-left" => Some(Anchor::TopLeft),
+            "top" => Some(Anchor::Top),
+            "top-right" => Some(Anchor::TopRight),
+            "left" => Some(Anchor::Left),
+            "center" => Some(Anchor::Center),
+            "right" => Some(Anchor::Right),
+            "bottom-left" => Some(Anchor::BottomLeft),
+            "bottom" => Some(Anchor::Bottom),
+            "bottom-right" => Some(Anchor::BottomRight),
+            _ => None,
+        }
+    }
+
+    /// True for the four corners, the anchors the window can be pinned to
+    pub fn is_corner(&self) -> bool {
+        matches!(
+            self,
+            Anchor::TopLeft | Anchor::TopRight | Anchor::BottomLeft | Anchor::BottomRight
+        )
+    }
+
+    /// Top-left position for a `width` x `height` window placed at this
+    /// anchor inside `area`
+    pub fn place(self, area: Rect, width: i32, height: i32) -> (i32, i32) {
+        let x = match self {
+            Anchor::TopLeft | Anchor::Left | Anchor::BottomLeft => area.x,
+            Anchor::Top | Anchor::Center | Anchor::Bottom => area.x + (area.width - width) / 2,
+            Anchor::TopRight | Anchor::Right | Anchor::BottomRight => area.x + area.width - width,
+        };
+        let y = match self {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => area.y,
+            Anchor::Left | Anchor::Center | Anchor::Right => area.y + (area.height - height) / 2,
+            Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => {
+                area.y + area.height - height
+            }
+        };
+        (x, y)
+    }
+}
+
+/// Which monitor edges a dropped character snaps to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapEdges {
+    pub left: bool,
+    pub right: bool,
+    pub top: bool,
+    pub bottom: bool,
+}
+
+impl SnapEdges {
+    pub const ALL: SnapEdges = SnapEdges {
+        left: true,
+        right: true,
+        top: true,
+        bottom: true,
+    };
+
+    /// Parses edge names ("left", "right", "top", "bottom"),
+    /// case-insensitively. Unknown names are logged and skipped.
+    pub fn from_names(names: &[String]) -> SnapEdges {
+        let mut edges = SnapEdges {
+            left: false,
+            right: false,
+            top: false,
+            bottom: false,
+        };
+        for name in names {
+            match name.to_ascii_lowercase().as_str() {
+                "left" => edges.left = true,
+                "right" => edges.right = true,
+                "top" => edges.top = true,
+                "bottom" => edges.bottom = true,
+                _ => log::warn!("[Config] Ignoring unknown snap edge: {:?}", name),
+            }
+        }
+        edges
+    }
+}
+
+/// How often the input loop advances deferred cursor reports (smoothing and
+/// throttled moves), ~60 Hz
+#[allow(dead_code)] // Only the Linux backend reports relative movement
+pub const CURSOR_TICK: Duration = Duration::from_millis(16);
+
+/// Default time without input before the user counts as idle (5 minutes)
+pub const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 300;
+
+/// Default poll timeout right after input, and the most it backs off to
+pub const DEFAULT_POLL_TIMEOUT_MS: u64 = 1000;
+pub const DEFAULT_MAX_POLL_TIMEOUT_MS: u64 = 5000;
+
+/// Poll timeout for a loop that last saw input `since_input` ago: a tenth
+/// of that, kept between `base` and `max`, so a long-idle loop wakes rarely
+/// but one that just saw input notices quickly. Never sleeps past the point
+/// where Idle is due (`until_idle`), so Idle isn't delayed by the backoff.
+#[allow(dead_code)] // Only the Linux backend polls with a timeout
+pub fn adaptive_poll_timeout(
+    since_input: Duration,
+    base: Duration,
+    max: Duration,
+    until_idle: Option<Duration>,
+) -> Duration {
+    let timeout = (since_input / 10).clamp(base, max.max(base));
+    match until_idle {
+        // At least a tick, so a due Idle doesn't turn into a busy loop
+        Some(left) => timeout.min(left.max(CURSOR_TICK)),
+        None => timeout,
+    }
+}
+
+/// Default minimum time between two Activity events. Idle tracking still
+/// sees every input; this only limits how often the frontend hears of it.
+pub const DEFAULT_ACTIVITY_INTERVAL_MS: u64 = 1000;
+
+/// How many emitted events are kept for `recent_events` when debug event
+/// recording is on
+const RECENT_EVENTS_CAPACITY: usize = 200;
+
+/// Default minimum time between Cursor events from relative movement
+/// (60 per second is plenty for the character to follow smoothly)
+pub const DEFAULT_CURSOR_EMIT_INTERVAL_MS: u64 = 16;
+
+/// Fraction of the remaining distance covered on each smoothing tick
+const SMOOTHING_FACTOR: f64 = 0.3;
+
+/// Weight of the newest sample in the cursor velocity moving average
+const VELOCITY_SMOOTHING: f64 = 0.4;
+
+/// Fraction of the remaining distance the trail covers per sample
+const TRAIL_EASING: f64 = 0.5;
+
+/// How often the trail thread checks whether the trail was turned on
+const TRAIL_DISABLED_POLL: Duration = Duration::from_millis(250);
+
+/// Below this speed (pixels per second) the cursor isn't predicted, so a
+/// pointer coming to rest doesn't jitter around its real position
+const PREDICTION_MIN_SPEED: f64 = 100.0;
+
+/// A gap between cursor reports longer than this means the pointer had
+/// stopped, so the velocity restarts from zero instead of blending
+const VELOCITY_RESET_GAP: Duration = Duration::from_millis(150);
+
+/// Bits of `InputState::mouse_buttons` and the Cursor event's `buttons`
+pub const MOUSE_BUTTON_LEFT: u8 = 1;
+pub const MOUSE_BUTTON_RIGHT: u8 = 2;
+pub const MOUSE_BUTTON_MIDDLE: u8 = 4;
+
+/// Maximum time between two left presses for them to count as a double-click
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Maximum distance (per axis) the cursor may travel between the two presses
+const DOUBLE_CLICK_SLOP: i32 = 4;
+
+/// Distance (per axis) the cursor must travel with the left button held
+/// before the press becomes a drag, so a slightly shaky click stays a click
+const DRAG_THRESHOLD: i32 = 4;
+
+/// Longest a modifier may be held and still count as a tap, so holding
+/// Shift (e.g. while reading) never counts
+const MODIFIER_TAP_MAX_HOLD: Duration = Duration::from_millis(250);
+
+/// Maximum time between releasing the first tap and releasing the second
+const DOUBLE_TAP_INTERVAL: Duration = Duration::from_millis(400);
+
+/// What a cursor move did to the drag in progress
+#[derive(Debug, PartialEq)]
+enum DragStep {
+    /// The move started a drag from the press at (x, y)
+    Start(i32, i32),
+    /// The move continued a drag
+    Move,
+}
+
+/// Default per-device event cap. A 1000Hz mouse produces roughly 3000
+/// events/sec (X, Y and SYN per report), so this leaves plenty of headroom.
+pub const DEFAULT_MAX_DEVICE_EVENTS_PER_SEC: u32 = 20_000;
+
+/// Default glitch filter for a single relative update. Real flicks on a
+/// high-DPI mouse stay well below this between two reports.
+pub const DEFAULT_MAX_DELTA_PER_EVENT: i32 = 1000;
+
+/// Minimum time between two log lines about capped deltas
+const DELTA_WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
+// =============================================================================
+// Event Rate Limiter (Flood Protection)
+// =============================================================================
+//
+// Counts events from a single device over one-second windows. Once a device
+// exceeds the cap, the backend stops emitting its events for the rest of the
+// window so a haywire device can't peg a core or flood the frontend.
+//
+// =============================================================================
+
+pub struct EventRateLimiter {
+    window_start: Instant,
+    count: u32,
+    warned: bool,
+}
+
+impl EventRateLimiter {
+    pub fn new() -> Self {
+        Self {
+            window_start: Instant::now(),
+            count: 0,
+            warned: false,
+        }
+    }
+
+    /// Records `events` new events from the device.
+    /// Returns true if the device is over `max_per_sec` in the current window.
+    pub fn record(&mut self, events: u32, max_per_sec: u32, now: Instant) -> bool {
+        if now.duration_since(self.window_start) >= Duration::from_secs(1) {
+            self.window_start = now;
+            self.count = 0;
+        }
+        self.count = self.count.saturating_add(events);
+        max_per_sec > 0 && self.count > max_per_sec
+    }
+
+    /// Returns true only the first time it's called, so a flooding device
+    /// produces a single warning instead of one per batch.
+    pub fn should_warn(&mut self) -> bool {
+        !std::mem::replace(&mut self.warned, true)
+    }
+}
+
+// =============================================================================
+// Event Sinks
+// =============================================================================
+//
+// The input backends emit through an EventSink rather than straight to Tauri.
+// Normally the sink is the AppHandle, which forwards each event to the
+// webview on its channel. With `--json-stdout` the app runs headless and
+// StdoutSink writes every event as one line of JSON instead, so integrators
+// can consume the event stream without a webview:
+//
+// ```json
+// {"type":"cursor","x":812,"y":440,"buttons":0,"at_edge":false}
+// ```
+//
+// =============================================================================
+
+/// Destination for events emitted by the input thread
+pub trait EventSink: Send + Sync {
+    /// Delivers an event on the given channel
+    fn send(&self, channel: &str, event: &OutputEvent);
+
+    /// The character window, if this sink has one (used for hit-testing)
+    #[allow(dead_code)] // Only the Windows backend hit-tests the window itself
+    fn main_window(&self) -> Option<WebviewWindow> {
+        None
+    }
+}
+
+impl EventSink for AppHandle {
+    fn send(&self, channel: &str, event: &OutputEvent) {
+        let _ = self.emit(channel, event);
+    }
+
+    fn main_window(&self) -> Option<WebviewWindow> {
+        self.get_webview_window("main")
+    }
+}
+
+/// Lets backends pass their `Arc<dyn EventSink>` wherever a sink is expected
+impl<S: EventSink + ?Sized> EventSink for std::sync::Arc<S> {
+    fn send(&self, channel: &str, event: &OutputEvent) {
+        (**self).send(channel, event);
+    }
+
+    fn main_window(&self) -> Option<WebviewWindow> {
+        (**self).main_window()
+    }
+}
+
+/// Writes each event to stdout as newline-delimited JSON (`--json-stdout`)
+pub struct StdoutSink;
+
+impl EventSink for StdoutSink {
+    fn send(&self, _channel: &str, event: &OutputEvent) {
+        let Ok(line) = serde_json::to_string(event) else {
+            return;
+        };
+        // A closed pipe just means nobody is listening any more
+        let _ = writeln!(std::io::stdout().lock(), "{}", line);
+    }
+}
+
+/// One line of an event recording: an event and the channel it was sent
+/// on, `ms` milliseconds after recording started
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedEvent {
+    pub ms: u64,
+    pub channel: String,
+    pub event: serde_json::Value,
+}
+
+/// Passes events on to another sink while appending each one to a file as
+/// a RecordedEvent line (`--record-events <path>`), so a session can be
+/// played back at its original cadence with `--replay-events <path>`
+pub struct RecordingSink<S> {
+    inner: S,
+    file: Mutex<std::fs::File>,
+    started: Instant,
+}
+
+impl<S: EventSink> RecordingSink<S> {
+    /// Starts a new recording at `path`, replacing any existing file
+    pub fn create(inner: S, path: &std::path::Path) -> std::io::Result<Self> {
+        Ok(Self {
+            inner,
+            file: Mutex::new(std::fs::File::create(path)?),
+            started: Instant::now(),
+        })
+    }
+}
+
+impl<S: EventSink> EventSink for RecordingSink<S> {
+    fn send(&self, channel: &str, event: &OutputEvent) {
+        self.inner.send(channel, event);
+        let Ok(event) = serde_json::to_value(event) else {
+            return;
+        };
+        let recorded = RecordedEvent {
+            ms: self.started.elapsed().as_millis() as u64,
+            channel: channel.to_string(),
+            event,
+        };
+        if let Ok(line) = serde_json::to_string(&recorded) {
+            let _ = writeln!(self.file.lock().unwrap(), "{}", line);
+        }
+    }
+
+    fn main_window(&self) -> Option<WebviewWindow> {
+        self.inner.main_window()
+    }
+}
+
+/// Reads a recording written by RecordingSink, skipping lines that don't
+/// parse (e.g. one cut short when the app was killed mid-write)
+pub fn read_recording(path: &std::path::Path) -> std::io::Result<Vec<RecordedEvent>> {
+    let text = std::fs::read_to_string(path)?;
+    let mut events = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(event) => events.push(event),
+            Err(e) => log::warn!("[Replay] Skipping line {}: {}", number + 1, e),
+        }
+    }
+    Ok(events)
+}
+
+/// How long a write to one socket client may block before it's dropped
+#[cfg(unix)]
+const SOCKET_WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// How a socket's clients get their events, chosen by the socket they
+/// connect to (`--socket` or `--msgpack-socket`)
+#[cfg(unix)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SocketEncoding {
+    /// Newline-delimited JSON, like StdoutSink
+    Json,
+    /// One MessagePack map per event, back to back (MessagePack values
+    /// delimit themselves)
+    MessagePack,
+}
+
+/// Serves the same events as StdoutSink on Unix domain sockets, to every
+/// client connected at the time. Each socket has its own encoding, so a
+/// client picks JSON or MessagePack by where it connects. Clients may come
+/// and go; one that disconnects or stops reading is dropped without
+/// affecting the others.
+#[cfg(unix)]
+pub struct SocketSink {
+    clients: Arc<Mutex<Vec<(std::os::unix::net::UnixStream, SocketEncoding)>>>,
+}
+
+#[cfg(unix)]
+impl SocketSink {
+    /// A sink with no sockets yet; add them with `listen`
+    pub fn new() -> Self {
+        Self {
+            clients: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Binds a socket whose clients get `encoding`, replacing a stale one
+    /// left at `path`, and starts accepting clients in the background
+    pub fn listen(&self, path: &std::path::Path, encoding: SocketEncoding) -> std::io::Result<()> {
+        let _ = std::fs::remove_file(path);
+        let listener = std::os::unix::net::UnixListener::bind(path)?;
+
+        let accepted = self.clients.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        // A client that stops reading must not stall the input thread
+                        let _ = stream.set_write_timeout(Some(SOCKET_WRITE_TIMEOUT));
+                        accepted.lock().unwrap().push((stream, encoding));
+                    }
+                    Err(e) => log::warn!("[Socket] Accept failed: {}", e),
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+impl EventSink for SocketSink {
+    fn send(&self, _channel: &str, event: &OutputEvent) {
+        // Each encoding is produced at most once, and only if a client wants it
+        let (mut json, mut msgpack) = (None, None);
+        self.clients
+            .lock()
+            .unwrap()
+            .retain_mut(|(client, encoding)| {
+                let bytes = match encoding {
+                    SocketEncoding::Json => json.get_or_insert_with(|| {
+                        let mut line = serde_json::to_vec(event).unwrap_or_default();
+                        line.push(b'\n');
+                        line
+                    }),
+                    SocketEncoding::MessagePack => msgpack
+                        .get_or_insert_with(|| rmp_serde::to_vec_named(event).unwrap_or_default()),
+                };
+                client.write_all(bytes).is_ok()
+            });
+    }
+}
+
+// =============================================================================
+// Event Interceptors (Extension Point)
+// =============================================================================
+//
+// Interceptors let custom code sit in the emit path between the input backends
+// and the frontend. Each registered interceptor sees every OutputEvent in
+// registration order and decides whether to let it through, swallow it, or
+// swap it for a different event (e.g. custom gesture recognition or
+// rate-limiting).
+//
+// When no interceptors are registered, SharedState::emit skips the interceptor
+// lock with a single atomic load. The rest of emit (readiness, pause,
+// Activity coalescing, idle tracking) runs before that check either way.
+//
+// =============================================================================
+
+/// What an interceptor wants to happen to an event
+#[allow(dead_code)] // Drop/Replace are only constructed by user interceptors
+pub enum InterceptAction {
+    /// Let the event continue unchanged to the next interceptor / frontend
+    PassThrough,
+    /// Swallow the event; later interceptors and the frontend never see it
+    Drop,
+    /// Substitute a different event, which later interceptors then receive
+    Replace(OutputEvent),
+}
+
+/// Hook for observing or rewriting events before they are emitted
+pub trait EventInterceptor: Send {
+    fn on_event(&mut self, event: &OutputEvent) -> InterceptAction;
+}
+
+/// Rust-side handler for a shortcut action (see SharedState::register_action)
+pub type ActionHandler = Arc<dyn Fn() + Send + Sync>;
+
+/// Thread-safe wrapper around InputState
+pub struct SharedState {
+    pub input_state: Mutex<InputState>,
+
+    /// Registered interceptors, run in order on every emitted event
+    interceptors: Mutex<Vec<Box<dyn EventInterceptor>>>,
+
+    /// Fast-path flag so the emit path can skip the interceptor lock
+    has_interceptors: AtomicBool,
+
+    /// Device counts from the most recent Ready/DevicesChanged event
+    /// (None until Ready is emitted).
+    /// Kept outside InputState because emit runs with that lock held.
+    ready_devices: Mutex<Option<(usize, usize)>>,
+
+    /// Set when the app is closing; the input loop exits once it sees it
+    shutdown: AtomicBool,
+
+    /// While set, no events are emitted except Paused/Resumed. The input
+    /// loop keeps reading (so device buffers don't fill up) and keeps
+    /// detecting shortcuts, so `toggle_pause` can turn it back off.
+    paused: AtomicBool,
+
+    /// Last reported (meta, shift, ctrl, alt), so ModifierState is only
+    /// sent when one of them changes.
+    /// Kept outside InputState because emit_key only borrows it.
+    modifier_state: Mutex<(bool, bool, bool, bool)>,
+
+    /// When the last Activity or Cursor event was emitted
+    last_activity: Mutex<Instant>,
+
+    /// True between an Idle event and the next activity
+    idle: AtomicBool,
+
+    /// Minimum time between two Activity events reaching the sink
+    /// (the `activityIntervalMs` config setting; zero = send them all)
+    activity_interval: Mutex<Duration>,
+
+    /// When an Activity event last reached the sink
+    last_activity_emit: Mutex<Option<Instant>>,
+
+    /// Shortcut actions handled in Rust as well as by the frontend
+    actions: Mutex<HashMap<String, ActionHandler>>,
+
+    /// Whether emitted events are recorded in `recent_events`
+    /// (the `debugEvents` config flag)
+    debug_events: AtomicBool,
+
+    /// The last RECENT_EVENTS_CAPACITY events as delivered to the sink,
+    /// oldest first. Only filled while `debug_events` is set.
+    recent_events: Mutex<VecDeque<(Instant, OutputEvent)>>,
+
+    /// Event counts for `stats`, and when they were last reset
+    counters: StatCounters,
+    counting_since: Mutex<Instant>,
+}
+
+impl SharedState {
+    /// Creates the shared state with no interceptors registered
+    pub fn new(input_state: InputState) -> Self {
+        Self {
+            input_state: Mutex::new(input_state),
+            interceptors: Mutex::new(Vec::new()),
+            has_interceptors: AtomicBool::new(false),
+            ready_devices: Mutex::new(None),
+            shutdown: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            modifier_state: Mutex::new((false, false, false, false)),
+            last_activity: Mutex::new(Instant::now()),
+            idle: AtomicBool::new(false),
+            activity_interval: Mutex::new(Duration::from_millis(DEFAULT_ACTIVITY_INTERVAL_MS)),
+            last_activity_emit: Mutex::new(None),
+            actions: Mutex::new(HashMap::new()),
+            debug_events: AtomicBool::new(false),
+            recent_events: Mutex::new(VecDeque::new()),
+            counters: StatCounters::default(),
+            counting_since: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Runs `handler` whenever a shortcut fires `action`, unless the config
+    /// marks that action frontend-only. The Shortcut event is still emitted,
+    /// so the frontend can react too.
+    pub fn register_action(&self, action: &str, handler: impl Fn() + Send + Sync + 'static) {
+        self.actions
+            .lock()
+            .unwrap()
+            .insert(action.to_string(), Arc::new(handler));
+    }
+
+    /// Runs the Rust handler for a shortcut action, if there is one
+    fn run_action(&self, state: &InputState, action: &str) {
+        if state.frontend_only_actions.iter().any(|a| a == action) {
+            return;
+        }
+        let Some(handler) = self.actions.lock().unwrap().get(action).cloned() else {
+            return;
+        };
+        // Callers hold the input_state lock, and window calls can wait on the
+        // main thread, which may itself be waiting for that lock
+        std::thread::spawn(move || handler());
+    }
+
+    /// Turns event recording for `recent_events` on or off. Turning it off
+    /// also drops anything recorded so far.
+    pub fn set_debug_events(&self, enabled: bool) {
+        self.debug_events.store(enabled, Ordering::Release);
+        if !enabled {
+            self.recent_events.lock().unwrap().clear();
+        }
+    }
+
+    /// Sets the minimum time between two Activity events (zero = no limit)
+    pub fn set_activity_interval(&self, interval: Duration) {
+        *self.activity_interval.lock().unwrap() = interval;
+    }
+
+    /// Whether an Activity event may be sent now, given `activity_interval`.
+    /// Claims the slot if so.
+    fn activity_emit_due(&self) -> bool {
+        let interval = *self.activity_interval.lock().unwrap();
+        let mut last_emit = self.last_activity_emit.lock().unwrap();
+        let now = Instant::now();
+        if last_emit.is_some_and(|at| now.duration_since(at) < interval) {
+            return false;
+        }
+        *last_emit = Some(now);
+        true
+    }
+
+    /// The last `limit` recorded events, oldest first, each paired with how
+    /// long ago it was emitted. Empty unless debug event recording is on.
+    pub fn recent_events(&self, limit: usize) -> Vec<(Duration, OutputEvent)> {
+        let recent = self.recent_events.lock().unwrap();
+        let now = Instant::now();
+        recent
+            .iter()
+            .skip(recent.len().saturating_sub(limit))
+            .map(|(at, event)| (now.duration_since(*at), event.clone()))
+            .collect()
+    }
+
+    /// Event counts since startup or the last `reset_stats`.
+    /// Must be called without input_state locked.
+    pub fn stats(&self) -> InputStats {
+        let coalesced_cursor_moves = self.input_state.lock().unwrap().coalesced_cursor_moves;
+        let seconds = self.counting_since.lock().unwrap().elapsed().as_secs_f64();
+        let count = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let cursor_events = count(&self.counters.cursor_events);
+        InputStats {
+            seconds,
+            cursor_events,
+            cursor_events_per_second: if seconds > 0.0 {
+                cursor_events as f64 / seconds
+            } else {
+                0.0
+            },
+            clicks: count(&self.counters.clicks),
+            shortcuts: count(&self.counters.shortcuts),
+            coalesced_cursor_moves,
+            suppressed_cursor_events: count(&self.counters.suppressed_cursor_events),
+            dropped_events: count(&self.counters.dropped_events),
+            flood_dropped_events: count(&self.counters.flood_dropped_events),
+        }
+    }
+
+    /// Counts raw events a backend discarded from a flooding device
+    pub fn count_flood_dropped(&self, events: u64) {
+        self.counters
+            .flood_dropped_events
+            .fetch_add(events, Ordering::Relaxed);
+    }
+
+    /// Zeroes the event counts. Must be called without input_state locked.
+    pub fn reset_stats(&self) {
+        self.input_state.lock().unwrap().coalesced_cursor_moves = 0;
+        let counters = &self.counters;
+        for counter in [
+            &counters.cursor_events,
+            &counters.clicks,
+            &counters.shortcuts,
+            &counters.suppressed_cursor_events,
+            &counters.dropped_events,
+            &counters.flood_dropped_events,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+        *self.counting_since.lock().unwrap() = Instant::now();
+    }
+
+    /// Sends an event to the sink, recording it first if debugging is on
+    fn deliver(&self, sink: &dyn EventSink, channel: &str, event: OutputEvent) {
+        match event {
+            OutputEvent::Cursor { .. } => StatCounters::bump(&self.counters.cursor_events),
+            OutputEvent::Click { .. } => StatCounters::bump(&self.counters.clicks),
+            OutputEvent::Shortcut { .. } => StatCounters::bump(&self.counters.shortcuts),
+            _ => {}
+        }
+        if self.debug_events.load(Ordering::Acquire) {
+            let mut recent = self.recent_events.lock().unwrap();
+            if recent.len() == RECENT_EVENTS_CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back((Instant::now(), event.clone()));
+        }
+        sink.send(channel, &event);
+    }
+
+    /// Makes the state usable again after the input loop panicked: any lock
+    /// it held stays poisoned otherwise, and every later `lock().unwrap()`
+    /// would panic too
+    pub fn recover_from_panic(&self) {
+        self.input_state.clear_poison();
+        self.interceptors.clear_poison();
+        self.ready_devices.clear_poison();
+        self.modifier_state.clear_poison();
+        self.last_activity.clear_poison();
+        self.activity_interval.clear_poison();
+        self.last_activity_emit.clear_poison();
+        self.actions.clear_poison();
+        self.recent_events.clear_poison();
+        self.counting_since.clear_poison();
+    }
+
+    /// Asks the input loop to exit. Use `input::stop_input_loop`, which also
+    /// wakes backends that block waiting for input.
+    pub fn request_shutdown(&self) {
+        self.shutdown.store(true, Ordering::Release);
+    }
+
+    /// True once shutdown has been requested
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.load(Ordering::Acquire)
+    }
+
+    /// True while input monitoring is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Pauses or resumes input monitoring, emitting Paused/Resumed if that
+    /// changed anything
+    pub fn set_paused(&self, sink: &dyn EventSink, paused: bool) {
+        if self.paused.swap(paused, Ordering::AcqRel) == paused {
+            return;
+        }
+        if paused {
+            log::info!("[Input] Monitoring paused");
+            self.deliver(sink, "paused", OutputEvent::Paused);
+        } else {
+            log::info!("[Input] Monitoring resumed");
+            // The pause isn't time away from the keyboard
+            *self.last_activity.lock().unwrap() = Instant::now();
+            self.deliver(sink, "resumed", OutputEvent::Resumed);
+        }
+    }
+
+    /// Returns the latest (mice_count, keyboards_count),
+    /// or None if the input thread never reported Ready
+    pub fn ready_devices(&self) -> Option<(usize, usize)> {
+        *self.ready_devices.lock().unwrap()
+    }
+
+    /// Registers an interceptor that will run after any already registered
+    #[allow(dead_code)] // Extension point for custom builds
+    pub fn register_interceptor(&self, interceptor: Box<dyn EventInterceptor>) {
+        self.interceptors.lock().unwrap().push(interceptor);
+        self.has_interceptors.store(true, Ordering::Release);
+    }
+
+    /// Emits an event to the sink on the given channel, passing it
+    /// through any registered interceptors first.
+    pub fn emit(&self, sink: &dyn EventSink, channel: &str, event: OutputEvent) {
+        if let OutputEvent::Ready {
+            mice_count,
+            keyboards_count,
+            ..
+        }
+        | OutputEvent::DevicesChanged {
+            mice_count,
+            keyboards_count,
+            ..
+        } = event
+        {
+            *self.ready_devices.lock().unwrap() = Some((mice_count, keyboards_count));
+        }
+
+        if self.is_paused() {
+            StatCounters::bump(&self.counters.dropped_events);
+            return;
+        }
+
+        match event {
+            OutputEvent::Activity => {
+                self.record_activity(sink);
+                // Typing produces one per key; the frontend only needs to
+                // know the user is active, not about every keystroke
+                if !self.activity_emit_due() {
+                    return;
+                }
+            }
+            OutputEvent::Cursor { .. } => self.record_activity(sink),
+            _ => {}
+        }
+
+        if !self.has_interceptors.load(Ordering::Acquire) {
+            self.deliver(sink, channel, event);
+            return;
+        }
+
+        let mut event = event;
+        for interceptor in self.interceptors.lock().unwrap().iter_mut() {
+            match interceptor.on_event(&event) {
+                InterceptAction::PassThrough => {}
+                InterceptAction::Drop => {
+                    StatCounters::bump(&self.counters.dropped_events);
+                    return;
+                }
+                InterceptAction::Replace(replacement) => event = replacement,
+            }
+        }
+        self.deliver(sink, channel, event);
+    }
+
+    /// Resets the idle clock, emitting Active first if the user was idle.
+    /// Must not lock input_state: emit is called with it held.
+    fn record_activity(&self, sink: &dyn EventSink) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+        if self.idle.swap(false, Ordering::AcqRel) {
+            self.emit(sink, "active", OutputEvent::Active);
+        }
+    }
+
+    /// How long the input loop may wait for input before it next has to
+    /// wake (see `adaptive_poll_timeout`). Must be called without
+    /// input_state locked.
+    #[allow(dead_code)] // Only the Linux backend polls with a timeout
+    pub fn poll_timeout(&self) -> Duration {
+        let (base, max, idle_timeout) = {
+            let state = self.input_state.lock().unwrap();
+            (
+                state.poll_timeout,
+                state.max_poll_timeout,
+                state.idle_timeout,
+            )
+        };
+        let since_input = self.last_activity.lock().unwrap().elapsed();
+        let until_idle = if idle_timeout.is_zero() || self.idle.load(Ordering::Acquire) {
+            None
+        } else {
+            Some(idle_timeout.saturating_sub(since_input))
+        };
+        adaptive_poll_timeout(since_input, base, max, until_idle)
+    }
+
+    /// Emits Idle once if there has been no activity for `idle_timeout`.
+    /// Backends call this periodically (about once a second) while no input
+    /// arrives. Must be called without input_state locked.
+    pub fn check_idle(&self, sink: &dyn EventSink) {
+        let timeout = self.input_state.lock().unwrap().idle_timeout;
+        if timeout.is_zero() || self.idle.load(Ordering::Acquire) || self.is_paused() {
+            return;
+        }
+
+        let idle_for = self.last_activity.lock().unwrap().elapsed();
+        if idle_for >= timeout {
+            self.idle.store(true, Ordering::Release);
+            // Nobody holds a key through a whole idle timeout; anything
+            // still marked held lost its key-up
+            self.reconcile_held_keys(sink, &mut self.input_state.lock().unwrap(), |_| false);
+            self.emit(
+                sink,
+                "idle",
+                OutputEvent::Idle {
+                    seconds: idle_for.as_secs(),
+                },
+            );
+        }
+    }
+
+    /// Records the app now in the foreground, emitting ForegroundApp if it
+    /// changed. Shortcuts follow the matching app profile from then on.
+    pub fn set_foreground_app(&self, sink: &dyn EventSink, name: Option<String>) {
+        let mut state = self.input_state.lock().unwrap();
+        if state.foreground_app == name {
+            return;
+        }
+        state.foreground_app = name.clone();
+        self.emit(sink, "foreground-app", OutputEvent::ForegroundApp { name });
+    }
+
+    /// Emits CursorTrail samples every `cursor_trail_interval` while the
+    /// cursor moves. Blocks until shutdown, so run it on its own thread.
+    pub fn run_cursor_trail(&self, sink: &dyn EventSink) {
+        while !self.is_shutting_down() {
+            let (interval, sample) = {
+                let mut state = self.input_state.lock().unwrap();
+                (state.cursor_trail_interval, state.next_trail_sample())
+            };
+            if let Some((x, y)) = sample {
+                self.emit(sink, "cursor-trail", OutputEvent::CursorTrail { x, y });
+            }
+            std::thread::sleep(if interval.is_zero() {
+                TRAIL_DISABLED_POLL
+            } else {
+                interval
+            });
+        }
+    }
+
+    /// Re-sends the real cursor position once the pointer stops after a
+    /// predicted report, which would otherwise leave the character looking
+    /// past the cursor. Blocks until shutdown, so run it on its own thread.
+    pub fn run_prediction_settle(&self, sink: &dyn EventSink) {
+        while !self.is_shutting_down() {
+            let enabled = {
+                let mut state = self.input_state.lock().unwrap();
+                if state.take_prediction_settle(Instant::now()) {
+                    self.emit_cursor(sink, &mut state);
+                }
+                !state.cursor_prediction.is_zero()
+            };
+            std::thread::sleep(if enabled {
+                CURSOR_TICK
+            } else {
+                TRAIL_DISABLED_POLL
+            });
+        }
+    }
+
+    /// Drops held keys that `is_down` says are released (see
+    /// `InputState::drop_released_keys`), emitting a KeyUp for each
+    pub fn reconcile_held_keys(
+        &self,
+        sink: &dyn EventSink,
+        state: &mut InputState,
+        is_down: impl Fn(KeyCode) -> bool,
+    ) {
+        for key in state.drop_released_keys(is_down) {
+            log::debug!("[Input] Key {:?} was stuck down; releasing it", key);
+            self.emit_key(sink, state, key, false);
+        }
+    }
+
+    /// Reports a problem to the frontend on the "error" channel (and the log)
+    pub fn emit_error(&self, sink: &dyn EventSink, message: impl Into<String>) {
+        let message = message.into();
+        log::error!("[Input] Error: {}", message);
+        self.emit(sink, "error", OutputEvent::Error { message });
+    }
+
+    /// Emits the last reported cursor position (the real one, or the eased
+    /// one while smoothing). The first report after startup is delivered per
+    /// `startup_cursor_mode`, after which the cursor counts as synced.
+    pub fn emit_cursor(&self, sink: &dyn EventSink, state: &mut InputState) {
+        let (x, y) = (state.last_reported_x, state.last_reported_y);
+        if !std::mem::replace(&mut state.cursor_synced, true) {
+            match state.startup_cursor_mode {
+                StartupCursorMode::Emit => {}
+                StartupCursorMode::Suppress => return,
+                StartupCursorMode::Jump => {
+                    self.emit(sink, "cursor-jump", OutputEvent::CursorJump { x, y });
+                    return;
+                }
+            }
+        }
+        self.update_hover(sink, state);
+        let (vx, vy) = state.track_velocity(x, y, Instant::now());
+        if state.should_report_cursor(x, y) {
+            let buttons = state.mouse_buttons;
+            let (ahead_x, ahead_y) = state.predict_cursor(x, y);
+            state.prediction_unsettled = (ahead_x, ahead_y) != (x, y);
+            let zone = state.edge_dead_zone.min(i32::MAX as u32) as i32;
+            // Windows reports the cursor in logical pixels but the monitors
+            // in physical ones
+            let (edge_x, edge_y, at_edge) = if cfg!(target_os = "windows") {
+                state
+                    .monitors
+                    .to_logical(state.window_scale_factor)
+                    .snap_to_edge(ahead_x, ahead_y, zone)
+            } else {
+                state.monitors.snap_to_edge(ahead_x, ahead_y, zone)
+            };
+            let cursor = OutputEvent::Cursor {
+                x: edge_x,
+                y: edge_y,
+                buttons,
+                at_edge,
+            };
+            self.emit(sink, "cursor-pos", cursor);
+            if state.cursor_velocity {
+                self.emit(
+                    sink,
+                    "cursor-motion",
+                    OutputEvent::CursorMotion { x, y, vx, vy },
+                );
+            }
+        } else {
+            StatCounters::bump(&self.counters.suppressed_cursor_events);
+        }
+    }
+
+    /// Emits a button press at the current cursor position.
+    /// If a calibration capture is armed, the press is reported once as a
+    /// CalibrationClick instead and the capture is disarmed.
+    pub fn emit_click(
+        &self,
+        sink: &dyn EventSink,
+        state: &mut InputState,
+        channel: &str,
+        button: &str,
+    ) {
+        if state.take_calibration() {
+            self.emit(
+                sink,
+                "calibration-click",
+                OutputEvent::CalibrationClick {
+                    x: state.cursor_x,
+                    y: state.cursor_y,
+                    button: button.into(),
+                },
+            );
+            return;
+        }
+
+        self.emit(
+            sink,
+            channel,
+            OutputEvent::Click {
+                button: button.into(),
+                x: state.cursor_x,
+                y: state.cursor_y,
+            },
+        );
+    }
+
+    /// Emits a triggered shortcut along with the exact modifiers that were
+    /// held when it fired. While paused, only `toggle_pause` does anything.
+    pub fn emit_shortcut(&self, sink: &dyn EventSink, state: &InputState, name: &str) {
+        if name == "toggle_pause" {
+            self.set_paused(sink, !self.is_paused());
+            // Only the Rust handler (which saves the setting) runs; the
+            // Paused/Resumed event is all the frontend gets
+            self.run_action(state, name);
+            return;
+        }
+        if self.is_paused() {
+            return;
+        }
+        self.emit(
+            sink,
+            "shortcut",
+            OutputEvent::Shortcut {
+                name: name.to_string(),
+                modifiers: state.held_modifier_names(),
+            },
+        );
+        self.run_action(state, name);
+    }
+
+    /// Emits HoverRegion if the cursor is now over a different interactive
+    /// region than last reported. Call after the cursor, window position or
+    /// rects change.
+    pub fn update_hover(&self, sink: &dyn EventSink, state: &mut InputState) {
+        let index = state.region_under_cursor();
+        if index != state.hovered_region {
+            state.hovered_region = index;
+            self.emit(sink, "hover-region", OutputEvent::HoverRegion { index });
+        }
+    }
+
+    /// Fires `action` as if its shortcut had been pressed (e.g. from a menu)
+    pub fn trigger_action(&self, sink: &dyn EventSink, action: &str) {
+        let state = self.input_state.lock().unwrap();
+        self.emit_shortcut(sink, &state, action);
+    }
+
+    /// Emits a KeyDown/KeyUp for a non-modifier key transition, with the key
+    /// name redacted unless `emit_keystrokes` is on. Call after updating
+    /// `held_modifiers`; modifier transitions are reported as ModifierState.
+    pub fn emit_key(&self, sink: &dyn EventSink, state: &InputState, key: KeyCode, down: bool) {
+        if key.modifier_name().is_some() {
+            self.emit_modifier_state(sink, state);
+            return;
+        }
+        let key = if state.emit_keystrokes {
+            key.name()
+        } else {
+            String::new()
+        };
+        if down {
+            self.emit(sink, "key-down", OutputEvent::KeyDown { key });
+        } else {
+            self.emit(sink, "key-up", OutputEvent::KeyUp { key });
+        }
+    }
+
+    /// Emits ModifierState if the held modifiers differ from the last report
+    fn emit_modifier_state(&self, sink: &dyn EventSink, state: &InputState) {
+        let held = |name| {
+            KeyCode::from_modifier_name(name)
+                .iter()
+                .any(|key| state.is_modifier_held(*key))
+        };
+        let current = (held("meta"), held("shift"), held("ctrl"), held("alt"));
+        if std::mem::replace(&mut *self.modifier_state.lock().unwrap(), current) == current {
+            return;
+        }
+        let (meta, shift, ctrl, alt) = current;
+        self.emit(
+            sink,
+            "modifier-state",
+            OutputEvent::ModifierState {
+                meta,
+                shift,
+                ctrl,
+                alt,
+            },
+        );
+    }
+
+    /// Emits the drag events for a cursor move: DragStart/DragMove while the
+    /// left button is held, plus DoubleClickDrag during a double-click hold.
+    /// Call after every reported cursor move.
+    pub fn emit_drag_motion(&self, sink: &dyn EventSink, state: &mut InputState) {
+        let (x, y) = (state.cursor_x, state.cursor_y);
+
+        if let Some(step) = state.drag_step() {
+            if let DragStep::Start(start_x, start_y) = step {
+                self.emit(
+                    sink,
+                    "drag-start",
+                    OutputEvent::DragStart {
+                        x: start_x,
+                        y: start_y,
+                    },
+                );
+            }
+            self.emit(sink, "drag-move", OutputEvent::DragMove { x, y });
+        }
+
+        if state.is_double_click_dragging() {
+            self.emit(
+                sink,
+                "double-click-drag",
+                OutputEvent::DoubleClickDrag { x, y },
+            );
+        }
+    }
+
+    /// Records a left button release, emitting DragEnd if it ended a drag,
+    /// then SnapTo if that drag dropped the character near a monitor edge
+    pub fn release_left_button(&self, sink: &dyn EventSink, state: &mut InputState) {
+        if state.left_button_up() {
+            self.emit(
+                sink,
+                "drag-end",
+                OutputEvent::DragEnd {
+                    x: state.cursor_x,
+                    y: state.cursor_y,
+                },
+            );
+
+            // Only a drag that carried the character ends over it
+            if state.region_under_cursor().is_some() {
+                if let Some((x, y)) = state.edge_snap_position() {
+                    self.emit(sink, "snap-to", OutputEvent::SnapTo { x, y });
+                }
+            }
+        }
+    }
+}
+
+impl InputState {
+    /// Creates a new InputState with the given screen dimensions
+    pub fn new(screen_width: i32, screen_height: i32) -> Self {
+        Self {
+            cursor_x: screen_width / 2,
+            cursor_y: screen_height / 2,
+            screen_width,
+            screen_height,
+            monitors: Monitors::single(screen_width, screen_height),
+            held_modifiers: HashSet::new(),
+            locks_on: HashSet::new(),
+            last_fired_shortcut: None,
+            last_reported_x: -1,
+            last_reported_y: -1,
+            interactive_rects: Vec::new(),
+            click_through: false,
+            full_interactive: false,
+            character_position: None,
+            attention_radius: 0,
+            edge_dead_zone: 0,
+            full_tracking: false,
+            cursor_attending: true,
+            calibration_armed: None,
+            calibration_counter: 0,
+            left_button_held: false,
+            mouse_buttons: 0,
+            last_left_press: None,
+            double_click_held: false,
+            drag_origin: None,
+            dragging: false,
+            max_device_events_per_sec: DEFAULT_MAX_DEVICE_EVENTS_PER_SEC,
+            max_delta_per_event: DEFAULT_MAX_DELTA_PER_EVENT,
+            last_delta_warning: None,
+            window_monitor: None,
+            window_scale_factor: 1.0,
+            window_position: None,
+            window_anchor: None,
+            hovered_region: None,
+            snap_threshold: 0,
+            snap_edges: SnapEdges::ALL,
+            sensitivity: 1.0,
+            sensitivity_remainder: (0.0, 0.0),
+            lock_axis: None,
+            startup_cursor_mode: StartupCursorMode::Jump,
+            cursor_synced: false,
+            shortcuts: ShortcutBinding::defaults(),
+            modifier_tap_start: None,
+            last_modifier_tap: None,
+            frontend_only_actions: Vec::new(),
+            app_profiles: Vec::new(),
+            shortcut_key_mode: ShortcutKeyMode::Physical,
+            keyboard_layout: HashMap::new(),
+            foreground_app: None,
+            emit_keystrokes: false,
+            consume_shortcuts: false,
+            idle_timeout: Duration::from_secs(DEFAULT_IDLE_TIMEOUT_SECS),
+            simulated_shortcuts: false,
+            poll_timeout: Duration::from_millis(DEFAULT_POLL_TIMEOUT_MS),
+            max_poll_timeout: Duration::from_millis(DEFAULT_MAX_POLL_TIMEOUT_MS),
+            cursor_smoothing: false,
+            smoothed_cursor: None,
+            cursor_emit_interval: Duration::from_millis(DEFAULT_CURSOR_EMIT_INTERVAL_MS),
+            last_cursor_emit: None,
+            cursor_velocity: false,
+            velocity: (0.0, 0.0),
+            last_velocity_sample: None,
+            cursor_prediction: Duration::ZERO,
+            prediction_unsettled: false,
+            cursor_trail_interval: Duration::ZERO,
+            trail_position: None,
+            cursor_emit_pending: false,
+            coalesced_cursor_moves: 0,
+        }
+    }
+
+    /// Updates the cursor position by a delta (relative movement).
+    /// Clamps to screen bounds and returns true if the position changed.
+    /// Reports are throttled to one per `cursor_emit_interval`; a move that
+    /// arrives sooner returns false and is reported later by
+    /// `take_throttled_cursor`. With smoothing enabled this always returns
+    /// false: the move is reported gradually by `tick_smoothing` instead.
+    pub fn update_cursor(&mut self, delta_x: i32, delta_y: i32) -> bool {
+        let (raw_x, raw_y) = (delta_x, delta_y);
+        let delta_x = self.filter_delta(delta_x);
+        let delta_y = self.filter_delta(delta_y);
+        if (delta_x, delta_y) != (raw_x, raw_y) {
+            let now = Instant::now();
+            if self
+                .last_delta_warning
+                .map_or(true, |at| now.duration_since(at) >= DELTA_WARNING_INTERVAL)
+            {
+                self.last_delta_warning = Some(now);
+                log::warn!(
+                    "[Input] Capped an implausible cursor jump of ({}, {}) to ({}, {}) (maxDeltaPerEvent = {})",
+                    raw_x,
+                    raw_y,
+                    delta_x,
+                    delta_y,
+                    self.max_delta_per_event
+                );
+            }
+        }
+        self.apply_cursor_delta(delta_x, delta_y);
+
+        if self.cursor_smoothing && self.cursor_synced {
+            if self.smoothed_cursor.is_none() {
+                self.smoothed_cursor =
+                    Some((self.last_reported_x as f64, self.last_reported_y as f64));
+            }
+            return false;
+        }
+        if std::mem::replace(&mut self.cursor_emit_pending, true) {
+            self.coalesced_cursor_moves += 1;
+        }
+        self.take_throttled_cursor()
+    }
+
+    /// Reports a move held back by throttling once the interval has passed.
+    /// Returns true if the cursor should be emitted now.
+    pub fn take_throttled_cursor(&mut self) -> bool {
+        let now = Instant::now();
+        let due = self.last_cursor_emit.map_or(true, |at| {
+            now.duration_since(at) >= self.cursor_emit_interval
+        });
+        if !self.cursor_emit_pending || !due {
+            return false;
+        }
+
+        self.cursor_emit_pending = false;
+        let changed = self.take_cursor_change();
+        if changed {
+            self.last_cursor_emit = Some(now);
+        }
+        changed
+    }
+
+    /// Moves the cursor to an absolute screen position (touchscreens,
+    /// tablets, OS-reported positions). Clamps to the monitors and returns
+    /// true if the position changed since the last report.
+    /// This is the real position, so it's never smoothed.
+    pub fn set_cursor_absolute(&mut self, x: i32, y: i32) -> bool {
+        let x = if self.lock_axis == Some(Axis::X) {
+            self.cursor_x
+        } else {
+            x
+        };
+        let y = if self.lock_axis == Some(Axis::Y) {
+            self.cursor_y
+        } else {
+            y
+        };
+        (self.cursor_x, self.cursor_y) = self.monitors.clamp(x, y);
+        self.smoothed_cursor = None;
+        self.cursor_emit_pending = false;
+        self.take_cursor_change()
+    }
+
+    /// Returns true if the cursor moved since the last report, and marks
+    /// the current position as reported
+    fn take_cursor_change(&mut self) -> bool {
+        self.mark_reported(self.cursor_x, self.cursor_y)
+    }
+
+    /// Marks (x, y) as the last reported position, returning true if it
+    /// differs from the previous one
+    fn mark_reported(&mut self, x: i32, y: i32) -> bool {
+        let changed = x != self.last_reported_x || y != self.last_reported_y;
+        if changed {
+            self.last_reported_x = x;
+            self.last_reported_y = y;
+        }
+        changed
+    }
+
+    /// Folds a cursor report at (x, y) into the smoothed velocity and
+    /// returns the new (vx, vy) in pixels per second
+    fn track_velocity(&mut self, x: i32, y: i32, now: Instant) -> (f64, f64) {
+        let previous = self.last_velocity_sample.replace((now, x, y));
+        let Some((at, prev_x, prev_y)) = previous else {
+            return self.velocity;
+        };
+        let elapsed = now.duration_since(at);
+        if elapsed.is_zero() {
+            return self.velocity;
+        }
+        if elapsed > VELOCITY_RESET_GAP {
+            self.velocity = (0.0, 0.0);
+        }
+
+        let secs = elapsed.as_secs_f64();
+        let sample = ((x - prev_x) as f64 / secs, (y - prev_y) as f64 / secs);
+        self.velocity = (
+            self.velocity.0 + (sample.0 - self.velocity.0) * VELOCITY_SMOOTHING,
+            self.velocity.1 + (sample.1 - self.velocity.1) * VELOCITY_SMOOTHING,
+        );
+        self.velocity
+    }
+
+    /// Where the cursor at (x, y) will be `cursor_prediction` from now at
+    /// the current velocity, kept on screen. (x, y) itself while prediction
+    /// is off or the cursor is nearly still.
+    fn predict_cursor(&self, x: i32, y: i32) -> (i32, i32) {
+        let (vx, vy) = self.velocity;
+        if self.cursor_prediction.is_zero() || vx.hypot(vy) < PREDICTION_MIN_SPEED {
+            return (x, y);
+        }
+        let ahead = self.cursor_prediction.as_secs_f64();
+        let predicted_x = (x as f64 + vx * ahead).round() as i32;
+        let predicted_y = (y as f64 + vy * ahead).round() as i32;
+        self.monitors.clamp(predicted_x, predicted_y)
+    }
+
+    /// True once the pointer has been still for a CURSOR_TICK after a
+    /// predicted report. The velocity is then zeroed, so the next
+    /// `emit_cursor` reports the real position.
+    fn take_prediction_settle(&mut self, now: Instant) -> bool {
+        let still = self
+            .last_velocity_sample
+            .map_or(true, |(at, _, _)| now.duration_since(at) >= CURSOR_TICK);
+        if !self.prediction_unsettled || !still {
+            return false;
+        }
+        self.prediction_unsettled = false;
+        self.velocity = (0.0, 0.0);
+        self.last_velocity_sample = None;
+        true
+    }
+
+    /// Index of the interactive rect under the cursor. The rects are in the
+    /// window's CSS pixels, so the cursor is mapped into window space first.
+    fn region_under_cursor(&self) -> Option<usize> {
+        let (window_x, window_y) = self.window_position?;
+        let scale = self.window_scale_factor;
+        // Windows and macOS report the cursor in logical pixels already
+        let cursor_scale = if cfg!(target_os = "linux") {
+            1.0
+        } else {
+            scale
+        };
+        let local_x = ((self.cursor_x as f64 * cursor_scale - window_x as f64) / scale) as i32;
+        let local_y = ((self.cursor_y as f64 * cursor_scale - window_y as f64) / scale) as i32;
+        self.interactive_rects
+            .iter()
+            .position(|rect| rect.contains(local_x, local_y))
+    }
+
+    /// The character's on-screen bounds in physical pixels: the box around
+    /// the interactive rects, which are in the window's CSS pixels
+    fn character_screen_rect(&self) -> Option<Rect> {
+        let (window_x, window_y) = self.window_position?;
+        let first = self.interactive_rects.first()?;
+        let (mut left, mut top) = (first.x, first.y);
+        let (mut right, mut bottom) = (first.x + first.width, first.y + first.height);
+        for rect in &self.interactive_rects[1..] {
+            left = left.min(rect.x);
+            top = top.min(rect.y);
+            right = right.max(rect.x + rect.width);
+            bottom = bottom.max(rect.y + rect.height);
+        }
+        let scale = self.window_scale_factor;
+        let to_screen = |v: i32| (v as f64 * scale).round() as i32;
+        Some(Rect {
+            x: window_x + to_screen(left),
+            y: window_y + to_screen(top),
+            width: to_screen(right - left),
+            height: to_screen(bottom - top),
+        })
+    }
+
+    /// Where to move the window so the character sits against the edges of
+    /// its monitor it was dropped within `snap_threshold` of, or None if it
+    /// isn't near any enabled edge
+    fn edge_snap_position(&self) -> Option<(i32, i32)> {
+        if self.snap_threshold == 0 {
+            return None;
+        }
+        let (window_x, window_y) = self.window_position?;
+        let character = self.character_screen_rect()?;
+        let center = self.monitors.clamp(
+            character.x + character.width / 2,
+            character.y + character.height / 2,
+        );
+        let monitor = self
+            .monitors
+            .rects
+            .iter()
+            .find(|rect| rect.contains(center.0, center.1))?;
+
+        // Offset to the nearer snapping edge along one axis, if within range
+        let threshold = self.snap_threshold as i32;
+        let snap_axis = |start: i32, end: i32, low: (bool, i32), high: (bool, i32)| {
+            let to_low = low.1 - start;
+            let to_high = high.1 - end;
+            [(low.0, to_low), (high.0, to_high)]
+                .into_iter()
+                .filter(|&(enabled, offset)| enabled && offset.abs() <= threshold)
+                .min_by_key(|&(_, offset)| offset.abs())
+                .map(|(_, offset)| offset)
+        };
+        let dx = snap_axis(
+            character.x,
+            character.x + character.width,
+            (self.snap_edges.left, monitor.x),
+            (self.snap_edges.right, monitor.x + monitor.width),
+        );
+        let dy = snap_axis(
+            character.y,
+            character.y + character.height,
+            (self.snap_edges.top, monitor.y),
+            (self.snap_edges.bottom, monitor.y + monitor.height),
+        );
+        if dx.is_none() && dy.is_none() {
+            return None;
+        }
+        Some((window_x + dx.unwrap_or(0), window_y + dy.unwrap_or(0)))
+    }
+
+    /// Moves the trail one step toward the cursor and returns the new
+    /// position, or None if the trail is off or already at the cursor
+    fn next_trail_sample(&mut self) -> Option<(i32, i32)> {
+        if self.cursor_trail_interval.is_zero() {
+            self.trail_position = None;
+            return None;
+        }
+
+        let target = (self.cursor_x as f64, self.cursor_y as f64);
+        let Some((x, y)) = self.trail_position else {
+            // Start at the cursor rather than sweeping in from elsewhere
+            self.trail_position = Some(target);
+            return None;
+        };
+
+        let mut next = (
+            x + (target.0 - x) * TRAIL_EASING,
+            y + (target.1 - y) * TRAIL_EASING,
+        );
+        if (target.0 - next.0).abs() < 0.5 && (target.1 - next.1).abs() < 0.5 {
+            next = target;
+        }
+        self.trail_position = Some(next);
+
+        let previous = (x.round() as i32, y.round() as i32);
+        let sample = (next.0.round() as i32, next.1.round() as i32);
+        (sample != previous).then_some(sample)
+    }
+
+    /// Whether a cursor move to (x, y) should be reported given the
+    /// attention radius: moves inside it, plus the first one outside so the
+    /// frontend sees the pointer leave
+    fn should_report_cursor(&mut self, x: i32, y: i32) -> bool {
+        let center = match self.character_position {
+            Some(center) if self.attention_radius > 0 && !self.full_tracking => center,
+            _ => {
+                self.cursor_attending = true;
+                return true;
+            }
+        };
+        let (dx, dy) = ((x - center.0) as i64, (y - center.1) as i64);
+        let radius = self.attention_radius as i64;
+        let inside = dx * dx + dy * dy <= radius * radius;
+        let was_attending = std::mem::replace(&mut self.cursor_attending, inside);
+        inside || was_attending
+    }
+
+    /// Enables or disables cursor smoothing. Disabling it drops any
+    /// in-progress easing so the next move reports the real position.
+    pub fn set_cursor_smoothing(&mut self, enabled: bool) {
+        self.cursor_smoothing = enabled;
+        if !enabled {
+            self.smoothed_cursor = None;
+        }
+    }
+
+    /// True while smoothing still has distance to cover, i.e. the input
+    /// loop should keep calling `tick_smoothing` every `CURSOR_TICK`
+    #[allow(dead_code)] // Only the Linux backend reports relative movement
+    pub fn is_smoothing(&self) -> bool {
+        self.smoothed_cursor.is_some()
+    }
+
+    /// True while a smoothed or throttled move still needs reporting, so
+    /// the input loop should wake up every `CURSOR_TICK`
+    #[allow(dead_code)] // Only the Linux backend reports relative movement
+    pub fn needs_cursor_tick(&self) -> bool {
+        self.is_smoothing() || self.cursor_emit_pending
+    }
+
+    /// Advances the smoothed cursor one step toward the real position.
+    /// Returns the new position to report, or None if nothing changed.
+    #[allow(dead_code)] // Only the Linux backend reports relative movement
+    pub fn tick_smoothing(&mut self) -> Option<(i32, i32)> {
+        let (x, y) = self.smoothed_cursor?;
+        let target_x = self.cursor_x as f64;
+        let target_y = self.cursor_y as f64;
+        let x = x + (target_x - x) * SMOOTHING_FACTOR;
+        let y = y + (target_y - y) * SMOOTHING_FACTOR;
+
+        // Snap once within half a pixel; the rounded position can't change
+        let (report_x, report_y) = if (target_x - x).abs() < 0.5 && (target_y - y).abs() < 0.5 {
+            self.smoothed_cursor = None;
+            (self.cursor_x, self.cursor_y)
+        } else {
+            self.smoothed_cursor = Some((x, y));
+            (x.round() as i32, y.round() as i32)
+        };
+
+        self.mark_reported(report_x, report_y)
+            .then_some((report_x, report_y))
+    }
+
+    /// Caps a single-axis delta to `max_delta_per_event`, discarding the
+    /// excess as likely hardware noise
+    pub fn filter_delta(&self, delta: i32) -> i32 {
+        if self.max_delta_per_event > 0 {
+            delta.clamp(-self.max_delta_per_event, self.max_delta_per_event)
+        } else {
+            delta
+        }
+    }
+
+    /// Scales a relative delta by `sensitivity`, keeping the fractional part
+    /// for the next call
+    fn scale_delta(&mut self, delta_x: i32, delta_y: i32) -> (i32, i32) {
+        if self.sensitivity == 1.0 {
+            return (delta_x, delta_y);
+        }
+        let x = delta_x as f32 * self.sensitivity + self.sensitivity_remainder.0;
+        let y = delta_y as f32 * self.sensitivity + self.sensitivity_remainder.1;
+        self.sensitivity_remainder = (x.fract(), y.fract());
+        (x.trunc() as i32, y.trunc() as i32)
+    }
+
+    /// Moves the cursor by a delta and clamps it to the monitors, without
+    /// marking the new position as reported (for moves that won't be emitted).
+    pub fn apply_cursor_delta(&mut self, delta_x: i32, delta_y: i32) {
+        let (delta_x, delta_y) = self.scale_delta(delta_x, delta_y);
+        match self.lock_axis {
+            Some(Axis::X) => self.cursor_y += delta_y,
+            Some(Axis::Y) => self.cursor_x += delta_x,
+            None => {
+                self.cursor_x += delta_x;
+                self.cursor_y += delta_y;
+            }
+        }
+
+        // Clamp to the monitors
+        (self.cursor_x, self.cursor_y) = self.monitors.clamp(self.cursor_x, self.cursor_y);
+    }
+
+    /// Updates the monitor layout (at startup, after a resume or monitor
+    /// change) and pulls the cursor back onto a monitor.
+    pub fn set_monitors(&mut self, monitors: Monitors) {
+        let bounds = monitors.bounds();
+        self.screen_width = bounds.width;
+        self.screen_height = bounds.height;
+        self.monitors = monitors;
+        (self.cursor_x, self.cursor_y) = self.monitors.clamp(self.cursor_x, self.cursor_y);
+    }
+
+    /// Arms a one-shot capture of the next click and returns its id
+    pub fn arm_calibration(&mut self) -> u64 {
+        self.calibration_counter += 1;
+        self.calibration_armed = Some(self.calibration_counter);
+        self.calibration_counter
+    }
+
+    /// Disarms the capture with the given id.
+    /// Returns false if it was already consumed or superseded.
+    pub fn cancel_calibration(&mut self, id: u64) -> bool {
+        if self.calibration_armed == Some(id) {
+            self.calibration_armed = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes the armed capture, returning true if this click should be
+    /// reported as a calibration click.
+    pub fn take_calibration(&mut self) -> bool {
+        self.calibration_armed.take().is_some()
+    }
+
+    /// Records a left button press. If it lands close enough (in time and
+    /// space) to the previous press, it becomes the second half of a
+    /// double-click and stays "double-click held" until released.
+    pub fn left_button_down(&mut self, now: Instant) {
+        self.left_button_held = true;
+        self.set_mouse_button(MOUSE_BUTTON_LEFT, true);
+        self.drag_origin = Some((self.cursor_x, self.cursor_y));
+        self.dragging = false;
+
+        let is_double = match self.last_left_press {
+            Some((at, x, y)) => {
+                now.duration_since(at) <= DOUBLE_CLICK_INTERVAL
+                    && (self.cursor_x - x).abs() <= DOUBLE_CLICK_SLOP
+                    && (self.cursor_y - y).abs() <= DOUBLE_CLICK_SLOP
+            }
+            None => false,
+        };
+
+        if is_double {
+            self.double_click_held = true;
+            // Don't let a third press chain onto this double-click
+            self.last_left_press = None;
+        } else {
+            self.last_left_press = Some((now, self.cursor_x, self.cursor_y));
+        }
+    }
+
+    /// Records a left button release, ending any double-click hold.
+    /// Returns true if the release ended a drag.
+    pub fn left_button_up(&mut self) -> bool {
+        self.left_button_held = false;
+        self.set_mouse_button(MOUSE_BUTTON_LEFT, false);
+        self.double_click_held = false;
+        self.drag_origin = None;
+        std::mem::replace(&mut self.dragging, false)
+    }
+
+    /// Records a mouse button (a MOUSE_BUTTON_* bit) being pressed or
+    /// released. The left button goes through left_button_down/up instead,
+    /// which also track drags and double-clicks.
+    pub fn set_mouse_button(&mut self, button: u8, held: bool) {
+        if held {
+            self.mouse_buttons |= button;
+        } else {
+            self.mouse_buttons &= !button;
+        }
+    }
+
+    /// Advances drag tracking after a cursor move
+    fn drag_step(&mut self) -> Option<DragStep> {
+        let (x, y) = self.drag_origin?;
+        if self.dragging {
+            return Some(DragStep::Move);
+        }
+        if (self.cursor_x - x).abs() <= DRAG_THRESHOLD
+            && (self.cursor_y - y).abs() <= DRAG_THRESHOLD
+        {
+            return None;
+        }
+        self.dragging = true;
+        Some(DragStep::Start(x, y))
+    }
+
+    /// True while the second press of a double-click is held, meaning cursor
+    /// movement should be reported as a DoubleClickDrag
+    pub fn is_double_click_dragging(&self) -> bool {
+        self.double_click_held && self.left_button_held
+    }
+
+    /// Names of the modifier keys currently held, sorted for stable output
+    pub fn held_modifier_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .held_modifiers
+            .iter()
+            .filter_map(|key| key.modifier_name())
+            .map(String::from)
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Checks if a specific modifier key is currently held down
+    pub fn is_modifier_held(&self, key: KeyCode) -> bool {
+        self.held_modifiers.contains(&key)
+    }
+
+    /// Forgets held keys that `is_down` says are no longer pressed and
+    /// returns them. A key-up missed during a device rescan or suspend would
+    /// otherwise leave e.g. Shift stuck, so shortcuts fire on their own.
+    pub fn drop_released_keys(&mut self, is_down: impl Fn(KeyCode) -> bool) -> Vec<KeyCode> {
+        let stale: Vec<KeyCode> = self
+            .held_modifiers
+            .iter()
+            .copied()
+            .filter(|&key| !is_down(key))
+            .collect();
+        for &key in &stale {
+            self.held_modifiers.remove(&key);
+            self.shortcut_key_released(key);
+        }
+        stale
+    }
+
+    /// Records whether a lock key (Caps Lock, Num Lock) is on
+    pub fn set_lock(&mut self, key: KeyCode, on: bool) {
+        if on {
+            self.locks_on.insert(key);
+        } else {
+            self.locks_on.remove(&key);
+        }
+    }
+
+    /// False if a profile for the foreground app disables `action`
+    fn is_action_enabled(&self, action: &str) -> bool {
+        let Some(app) = &self.foreground_app else {
+            return true;
+        };
+        !self
+            .app_profiles
+            .iter()
+            .any(|profile| profile.matches(app) && profile.disables(action))
+    }
+
+    /// Checks if a key press triggers a global shortcut.
+    /// Returns the action of the first binding whose key matches and whose
+    /// modifiers are all held, or None.
+    pub fn check_shortcut(&self, trigger_key: KeyCode) -> Option<&str> {
+        self.shortcuts
+            .iter()
+            .find(|binding| {
+                !binding.double_tap
+                    && binding.key_code() == Some(trigger_key)
+                    && self.is_action_enabled(&binding.action)
+                    && binding.modifiers.iter().all(|name| {
+                        KeyCode::from_modifier_name(name).iter().any(|key| {
+                            if key.is_lock() {
+                                self.locks_on.contains(key)
+                            } else {
+                                self.is_modifier_held(*key)
+                            }
+                        })
+                    })
+            })
+            .map(|binding| binding.action.as_str())
+    }
+
+    /// The key a physical key press counts as for shortcuts, which in
+    /// logical mode is whatever the keyboard layout types with it
+    fn shortcut_key(&self, key: KeyCode) -> KeyCode {
+        match self.shortcut_key_mode {
+            ShortcutKeyMode::Physical => key,
+            ShortcutKeyMode::Logical => self.keyboard_layout.get(&key).copied().unwrap_or(key),
+        }
+    }
+
+    /// Like `check_shortcut`, for backends to call on every key press
+    /// (including auto-repeats) with the physical key. Fires at most once
+    /// per press of the trigger key; call `shortcut_key_released` when keys
+    /// are released.
+    pub fn press_shortcut_key(&mut self, trigger_key: KeyCode) -> Option<String> {
+        let trigger_key = self.shortcut_key(trigger_key);
+        if self.last_fired_shortcut == Some(trigger_key) {
+            return None;
+        }
+        let action = self.check_shortcut(trigger_key)?.to_string();
+        self.last_fired_shortcut = Some(trigger_key);
+        Some(action)
+    }
+
+    /// True while `key` is held after firing a shortcut
+    #[allow(dead_code)] // Only the Linux backend consumes shortcut keys
+    pub fn is_shortcut_trigger_held(&self, key: KeyCode) -> bool {
+        self.last_fired_shortcut == Some(self.shortcut_key(key))
+    }
+
+    /// Re-arms shortcuts triggered by `key` once it is released
+    pub fn shortcut_key_released(&mut self, key: KeyCode) {
+        if self.last_fired_shortcut == Some(self.shortcut_key(key)) {
+            self.last_fired_shortcut = None;
+        }
+    }
+
+    /// Tracks modifier taps for double-tap bindings. Call on every key press
+    /// and release after `held_modifiers` is updated (with KeyCode::Unknown
+    /// for unmapped keys). Returns the action of a double-tap binding when
+    /// its modifier is tapped twice in quick succession.
+    ///
+    /// A tap is a lone modifier press and release with nothing pressed in
+    /// between, so chords like Shift+A (typing a capital) never count.
+    pub fn track_modifier_tap(
+        &mut self,
+        key: KeyCode,
+        pressed: bool,
+        now: Instant,
+    ) -> Option<String> {
+        if pressed {
+            let lone_modifier = key.modifier_name().is_some()
+                && self.held_modifiers.len() == 1
+                && self.held_modifiers.contains(&key);
+            if !lone_modifier {
+                self.modifier_tap_start = None;
+                self.last_modifier_tap = None;
+            } else if self.modifier_tap_start.is_none() {
+                self.modifier_tap_start = Some((key, now));
+            }
+            return None;
+        }
+
+        let (tapped, pressed_at) = self.modifier_tap_start.take()?;
+        if tapped != key || now.duration_since(pressed_at) > MODIFIER_TAP_MAX_HOLD {
+            self.last_modifier_tap = None;
+            return None;
+        }
+
+        match self.last_modifier_tap.take() {
+            Some((previous, at))
+                if previous == key && now.duration_since(at) <= DOUBLE_TAP_INTERVAL =>
+            {
+                self.shortcuts
+                    .iter()
+                    .find(|binding| {
+                        binding.double_tap
+                            && KeyCode::from_modifier_name(&binding.key).contains(&key)
+                            && self.is_action_enabled(&binding.action)
+                    })
+                    .map(|binding| binding.action.clone())
+            }
+            _ => {
+                self.last_modifier_tap = Some((key, now));
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spurious_huge_delta_is_clamped() {
+        let mut state = InputState::new(1920, 1080);
+        state.max_delta_per_event = 100;
+
+        state.update_cursor(30000, -30000);
+
+        // Started at screen center (960, 540)
+        assert_eq!(state.cursor_x, 1060);
+        assert_eq!(state.cursor_y, 440);
+    }
+
+    #[test]
+    fn delta_filter_can_be_disabled() {
+        let mut state = InputState::new(1920, 1080);
+        state.max_delta_per_event = 0;
+
+        state.update_cursor(30000, 0);
+
+        assert_eq!(state.cursor_x, 1919);
+    }
+
+    #[test]
+    fn cursor_clamps_to_screen_edges() {
+        let mut state = InputState::new(1920, 1080);
+        state.max_delta_per_event = 0;
+
+        state.update_cursor(-5000, -5000);
+        assert_eq!((state.cursor_x, state.cursor_y), (0, 0));
+
+        state.update_cursor(5000, 5000);
+        assert_eq!((state.cursor_x, state.cursor_y), (1919, 1079));
+    }
+
+    #[test]
+    fn update_cursor_reports_only_real_changes() {
+        let mut state = InputState::new(1920, 1080);
+        // Without the throttle, so a false can only mean "didn't move"
+        state.cursor_emit_interval = Duration::ZERO;
+
+        assert!(state.update_cursor(10, 0));
+        assert!(!state.update_cursor(0, 0));
+
+        // Pushing against the edge doesn't move the cursor either
+        state.max_delta_per_event = 0;
+        state.update_cursor(5000, 0);
+        assert!(!state.update_cursor(10, 0));
+    }
+
+    #[test]
+    fn meta_shift_f_toggles_chat() {
+        let mut state = InputState::new(1920, 1080);
+        state.held_modifiers.insert(KeyCode::LeftMeta);
+        state.held_modifiers.insert(KeyCode::LeftShift);
+
+        assert_eq!(state.check_shortcut(KeyCode::F), Some("toggle_chat"));
+    }
+
+    #[test]
+    fn far_cursor_moves_are_reported_only_on_leaving() {
+        let mut state = InputState::new(1920, 1080);
+        state.character_position = Some((100, 100));
+        state.attention_radius = 50;
+
+        assert!(state.should_report_cursor(120, 110));
+        // Leaving the radius is reported once, then nothing until it returns
+        assert!(state.should_report_cursor(300, 100));
+        assert!(!state.should_report_cursor(400, 100));
+        assert!(state.should_report_cursor(140, 100));
+
+        state.full_tracking = true;
+        assert!(state.should_report_cursor(1000, 1000));
+    }
+
+    #[test]
+    fn hover_region_is_reported_only_on_change() {
+        let shared = SharedState::new(InputState::new(1920, 1080));
+        shared.set_debug_events(true);
+        let mut state = shared.input_state.lock().unwrap();
+        state.window_position = Some((1000, 500));
+        state.interactive_rects = vec![Rect {
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 100,
+        }];
+
+        (state.cursor_x, state.cursor_y) = (1050, 550);
+        shared.update_hover(&NullSink, &mut state);
+        shared.update_hover(&NullSink, &mut state);
+        (state.cursor_x, state.cursor_y) = (10, 10);
+        shared.update_hover(&NullSink, &mut state);
+        drop(state);
+
+        let events: Vec<_> = shared
+            .recent_events(10)
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect();
+        assert!(matches!(
+            events[..],
+            [
+                OutputEvent::HoverRegion { index: Some(0) },
+                OutputEvent::HoverRegion { index: None }
+            ]
+        ));
+    }
+
+    #[test]
+    fn character_dropped_near_an_edge_snaps_to_it() {
+        let mut state = InputState::new(1920, 1080);
+        state.snap_threshold = 20;
+        state.window_scale_factor = 1.0;
+        state.window_position = Some((1000, 500));
+        state.interactive_rects = vec![Rect {
+            x: 50,
+            y: 50,
+            width: 100,
+            height: 100,
+        }];
+        // Nowhere near an edge
+        assert_eq!(state.edge_snap_position(), None);
+
+        // Character's right edge 10px short of the screen's
+        state.window_position = Some((1920 - 150 - 10, 500));
+        assert_eq!(state.edge_snap_position(), Some((1920 - 150, 500)));
+
+        // Corners snap on both axes, and disabled edges don't snap
+        state.window_position = Some((-40, 1080 - 150 + 5));
+        assert_eq!(state.edge_snap_position(), Some((-50, 1080 - 150)));
+        state.snap_edges.bottom = false;
+        assert_eq!(state.edge_snap_position(), Some((-50, 1080 - 150 + 5)));
+
+        state.snap_threshold = 0;
+        assert_eq!(state.edge_snap_position(), None);
+    }
+
+    #[test]
+    fn recorded_events_read_back_in_order() {
+        let path = std::env::temp_dir().join(format!("recording-{}.jsonl", std::process::id()));
+        let sink = RecordingSink::create(NullSink, &path).unwrap();
+        let shared = SharedState::new(InputState::new(1920, 1080));
+        {
+            let state = shared.input_state.lock().unwrap();
+            shared.emit_shortcut(&sink, &state, "toggle_chat");
+        }
+        shared.set_foreground_app(&sink, Some("firefox".to_string()));
+        // A line cut short by a crash is skipped
+        writeln!(sink.file.lock().unwrap(), "{{\"ms\": 12, \"chan").unwrap();
+
+        let events = read_recording(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let channels: Vec<_> = events.iter().map(|e| e.channel.as_str()).collect();
+        assert_eq!(channels, ["shortcut", "foreground-app"]);
+        assert!(events[0].ms <= events[1].ms);
+        assert_eq!(events[1].event["name"], "firefox");
+    }
+
+    #[test]
+    fn pause_silences_everything_but_its_own_toggle() {
+        let shared = SharedState::new(InputState::new(1920, 1080));
+        shared.set_debug_events(true);
+        {
+            let state = shared.input_state.lock().unwrap();
+            shared.emit_shortcut(&NullSink, &state, "toggle_pause");
+            let cursor = OutputEvent::Cursor {
+                x: 1,
+                y: 2,
+                buttons: 0,
+                at_edge: false,
+            };
+            shared.emit(&NullSink, "cursor-pos", cursor);
+            shared.emit_shortcut(&NullSink, &state, "toggle_chat");
+            shared.emit_shortcut(&NullSink, &state, "toggle_pause");
+        }
+        assert!(!shared.is_paused());
+
+        let events: Vec<_> = shared
+            .recent_events(10)
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect();
+        assert!(matches!(
+            events[..],
+            [OutputEvent::Paused, OutputEvent::Resumed]
+        ));
+    }
+
+    #[test]
+    fn stats_count_delivered_and_coalesced_events() {
+        let mut input_state = InputState::new(1920, 1080);
+        input_state.cursor_emit_interval = Duration::from_secs(60);
+        input_state.startup_cursor_mode = StartupCursorMode::Emit;
+        let shared = SharedState::new(input_state);
+        {
+            let mut state = shared.input_state.lock().unwrap();
+            for _ in 0..3 {
+                if state.update_cursor(5, 0) {
+                    shared.emit_cursor(&NullSink, &mut state);
+                }
+            }
+            shared.emit_click(&NullSink, &mut state, "click", "left");
+            shared.emit_shortcut(&NullSink, &state, "toggle_chat");
+        }
+        shared.count_flood_dropped(40);
+
+        let stats = shared.stats();
+        assert_eq!(stats.cursor_events, 1);
+        assert_eq!(stats.coalesced_cursor_moves, 1);
+        assert_eq!((stats.clicks, stats.shortcuts), (1, 1));
+        assert_eq!(stats.flood_dropped_events, 40);
+
+        shared.reset_stats();
+        let stats = shared.stats();
+        assert_eq!((stats.cursor_events, stats.coalesced_cursor_moves), (0, 0));
+        assert_eq!(stats.flood_dropped_events, 0);
+    }
+
+    #[test]
+    fn events_round_trip_through_message_pack() {
+        let events = [
+            OutputEvent::Scroll { dx: -1, dy: 200 },
+            OutputEvent::Cursor {
+                x: 812,
+                y: 440,
+                buttons: 0,
+                at_edge: false,
+            },
+        ];
+        for event in &events {
+            let packed = rmp_serde::to_vec_named(event).unwrap();
+            let decoded: serde_json::Value = rmp_serde::from_slice(&packed).unwrap();
+            assert_eq!(decoded, serde_json::to_value(event).unwrap());
+        }
+    }
+
+    #[test]
+    fn socket_clients_get_the_encoding_of_their_socket() {
+        use std::io::Read;
+        use std::os::unix::net::UnixStream;
+
+        let dir = std::env::temp_dir();
+        let json_path = dir.join(format!("socket-sink-{}.sock", std::process::id()));
+        let msgpack_path = dir.join(format!("socket-sink-{}.msgpack.sock", std::process::id()));
+        let sink = SocketSink::new();
+        sink.listen(&json_path, SocketEncoding::Json).unwrap();
+        sink.listen(&msgpack_path, SocketEncoding::MessagePack)
+            .unwrap();
+        let mut json = UnixStream::connect(&json_path).unwrap();
+        let mut msgpack = UnixStream::connect(&msgpack_path).unwrap();
+        while sink.clients.lock().unwrap().len() < 2 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        let event = OutputEvent::Scroll { dx: 0, dy: 1 };
+        sink.send("scroll", &event);
+
+        let mut line = serde_json::to_vec(&event).unwrap();
+        line.push(b'\n');
+        let mut received = vec![0; line.len()];
+        json.read_exact(&mut received).unwrap();
+        assert_eq!(received, line);
+
+        let packed = rmp_serde::to_vec_named(&event).unwrap();
+        let mut received = vec![0; packed.len()];
+        msgpack.read_exact(&mut received).unwrap();
+        assert_eq!(received, packed);
+        let _ = std::fs::remove_file(&json_path);
+        let _ = std::fs::remove_file(&msgpack_path);
+    }
+
+    /// Size and encoding time of a cursor event in each socket encoding.
+    /// Run with `cargo test --release socket_encoding_benchmark -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn socket_encoding_benchmark() {
+        const ROUNDS: u32 = 1_000_000;
+        let cursor = OutputEvent::Cursor {
+            x: 812,
+            y: 440,
+            buttons: 0,
+            at_edge: false,
+        };
+        let measure = |name: &str, encode: &dyn Fn(&OutputEvent) -> Vec<u8>| {
+            let size = encode(&cursor).len();
+            let started = Instant::now();
+            for _ in 0..ROUNDS {
+                std::hint::black_box(encode(std::hint::black_box(&cursor)));
+            }
+            println!(
+                "{:<11} {:>3} bytes/event {:>6.1} ns/event",
+                name,
+                size,
+                started.elapsed().as_nanos() as f64 / ROUNDS as f64
+            );
+        };
+        measure("json", &|event| {
+            let mut line = serde_json::to_vec(event).unwrap();
+            line.push(b'\n');
+            line
+        });
+        measure("messagepack", &|event| {
+            rmp_serde::to_vec_named(event).unwrap()
+        });
+    }
+
+    #[test]
+    fn state_is_usable_after_a_panic_while_locked() {
+        let shared = Arc::new(SharedState::new(InputState::new(1920, 1080)));
+        let crashing = shared.clone();
+        let result = std::thread::spawn(move || {
+            let _state = crashing.input_state.lock().unwrap();
+            let _modifiers = crashing.modifier_state.lock().unwrap();
+            panic!("malformed event");
+        })
+        .join();
+        assert!(result.is_err());
+        assert!(shared.input_state.is_poisoned());
+        assert!(shared.modifier_state.is_poisoned());
+
+        shared.recover_from_panic();
+        assert!(shared.input_state.lock().is_ok());
+        assert!(shared.modifier_state.lock().is_ok());
+    }
+
+    #[test]
+    fn logical_shortcut_keys_follow_the_keyboard_layout() {
+        let mut state = InputState::new(1920, 1080);
+        // AZERTY swaps A and Q
+        state.keyboard_layout = HashMap::from([(KeyCode::Q, KeyCode::A), (KeyCode::A, KeyCode::Q)]);
+        state.shortcuts = vec![ShortcutBinding::new(&[], "A", "wave")];
+
+        assert_eq!(
+            state.press_shortcut_key(KeyCode::A).as_deref(),
+            Some("wave")
+        );
+        state.shortcut_key_released(KeyCode::A);
+        assert_eq!(state.press_shortcut_key(KeyCode::Q), None);
+
+        state.shortcut_key_mode = ShortcutKeyMode::Logical;
+        assert_eq!(state.press_shortcut_key(KeyCode::A), None);
+        assert_eq!(
+            state.press_shortcut_key(KeyCode::Q).as_deref(),
+            Some("wave")
+        );
+        assert!(state.is_shortcut_trigger_held(KeyCode::Q));
+        state.shortcut_key_released(KeyCode::Q);
+        assert!(!state.is_shortcut_trigger_held(KeyCode::Q));
+    }
+
+    #[test]
+    fn modifier_state_is_sent_only_when_it_changes() {
+        let shared = SharedState::new(InputState::new(1920, 1080));
+        shared.set_debug_events(true);
+        {
+            let mut state = shared.input_state.lock().unwrap();
+            for (key, down) in [
+                (KeyCode::LeftShift, true),
+                (KeyCode::RightShift, true),
+                (KeyCode::LeftShift, false),
+                (KeyCode::RightShift, false),
+            ] {
+                if down {
+                    state.held_modifiers.insert(key);
+                } else {
+                    state.held_modifiers.remove(&key);
+                }
+                shared.emit_key(&NullSink, &state, key, down);
+            }
+        }
+
+        let shifts: Vec<_> = shared
+            .recent_events(10)
+            .into_iter()
+            .map(|(_, event)| match event {
+                OutputEvent::ModifierState { shift, .. } => shift,
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(shifts, [true, false]);
+    }
+
+    #[test]
+    fn held_buttons_are_tracked_as_a_bitmask() {
+        let mut state = InputState::new(1920, 1080);
+        state.left_button_down(Instant::now());
+        state.set_mouse_button(MOUSE_BUTTON_MIDDLE, true);
+        assert_eq!(state.mouse_buttons, MOUSE_BUTTON_LEFT | MOUSE_BUTTON_MIDDLE);
+
+        state.left_button_up();
+        state.set_mouse_button(MOUSE_BUTTON_RIGHT, true);
+        state.set_mouse_button(MOUSE_BUTTON_MIDDLE, false);
+        assert_eq!(state.mouse_buttons, MOUSE_BUTTON_RIGHT);
+    }
+
+    #[test]
+    fn poll_timeout_backs_off_while_idle_but_not_past_idle() {
+        let (base, max) = (Duration::from_secs(1), Duration::from_secs(5));
+        let timeout = |since_input_secs, until_idle| {
+            adaptive_poll_timeout(Duration::from_secs(since_input_secs), base, max, until_idle)
+        };
+        assert_eq!(timeout(0, None), base);
+        assert_eq!(timeout(30, None), Duration::from_secs(3));
+        assert_eq!(timeout(600, None), max);
+        assert_eq!(
+            timeout(600, Some(Duration::from_secs(2))),
+            Duration::from_secs(2)
+        );
+        assert_eq!(timeout(600, Some(Duration::ZERO)), CURSOR_TICK);
+    }
+
+    #[test]
+    fn app_profiles_disable_shortcuts_in_their_apps() {
+        let mut state = InputState::new(1920, 1080);
+        state.app_profiles = vec![AppProfile {
+            apps: vec!["steam_app_1245620".to_string()],
+            disabled_actions: vec!["*".to_string()],
+        }];
+        state.held_modifiers.insert(KeyCode::LeftMeta);
+        state.held_modifiers.insert(KeyCode::LeftShift);
+        assert_eq!(state.check_shortcut(KeyCode::F), Some("toggle_chat"));
+
+        state.foreground_app = Some("Steam_App_1245620".to_string());
+        assert_eq!(state.check_shortcut(KeyCode::F), None);
+        // The kill switch keeps working everywhere
+        assert_eq!(state.check_shortcut(KeyCode::P), Some("toggle_pause"));
+
+        state.foreground_app = Some("firefox".to_string());
+        assert_eq!(state.check_shortcut(KeyCode::F), Some("toggle_chat"));
+    }
+
+    #[test]
+    fn missed_key_ups_are_reconciled() {
+        let mut state = InputState::new(1920, 1080);
+        state.held_modifiers.insert(KeyCode::LeftShift);
+        state.held_modifiers.insert(KeyCode::LeftMeta);
+
+        let dropped = state.drop_released_keys(|key| key == KeyCode::LeftMeta);
+        assert_eq!(dropped, vec![KeyCode::LeftShift]);
+        assert!(!state.is_modifier_held(KeyCode::LeftShift));
+        assert!(state.is_modifier_held(KeyCode::LeftMeta));
+    }
+
+    #[test]
+    fn trail_eases_toward_the_cursor_and_stops_there() {
+        let mut state = InputState::new(1920, 1080);
+        state.cursor_x = 100;
+        state.cursor_y = 100;
+        assert_eq!(state.next_trail_sample(), None, "trail is off");
+
+        state.cursor_trail_interval = Duration::from_millis(16);
+        assert_eq!(state.next_trail_sample(), None, "starts at the cursor");
+
+        state.cursor_x = 200;
+        assert_eq!(state.next_trail_sample(), Some((150, 100)));
+        assert_eq!(state.next_trail_sample(), Some((175, 100)));
+
+        // Catches up in finitely many steps, then goes quiet
+        let mut last = None;
+        while let Some(sample) = state.next_trail_sample() {
+            last = Some(sample);
+        }
+        assert_eq!(last, Some((200, 100)));
+        assert_eq!(state.next_trail_sample(), None);
+    }
+
+    #[test]
+    fn velocity_is_smoothed_and_resets_after_a_pause() {
+        let mut state = InputState::new(1920, 1080);
+        let t0 = Instant::now();
+        let ms = Duration::from_millis;
+
+        state.track_velocity(0, 0, t0);
+        let (vx, vy) = state.track_velocity(10, 0, t0 + ms(10));
+        // 1000 px/s, blended with the initial rest
+        assert!((vx - 1000.0 * VELOCITY_SMOOTHING).abs() < 1e-6);
+        assert_eq!(vy, 0.0);
+
+        // One jittery report backwards doesn't reverse the direction
+        let (vx, _) = state.track_velocity(9, 0, t0 + ms(20));
+        assert!(vx > 0.0);
+
+        // After a pause the old motion is forgotten
+        let (vx, vy) = state.track_velocity(9, 5, t0 + ms(1000));
+        assert_eq!(vx, 0.0);
+        assert!(vy > 0.0);
+    }
+
+    #[test]
+    fn prediction_leads_a_moving_cursor_and_stays_on_screen() {
+        let mut state = InputState::new(1920, 1080);
+        let t0 = Instant::now();
+        state.track_velocity(1000, 500, t0);
+        state.track_velocity(1010, 500, t0 + Duration::from_millis(10));
+        assert_eq!(state.predict_cursor(1010, 500), (1010, 500));
+
+        // 400 px/s for 50 ms ahead
+        state.cursor_prediction = Duration::from_millis(50);
+        assert_eq!(state.predict_cursor(1010, 500), (1030, 500));
+        assert_eq!(state.predict_cursor(1915, 500), (1919, 500));
+
+        // A crawling cursor is left where it is
+        state.velocity = (30.0, 0.0);
+        assert_eq!(state.predict_cursor(1010, 500), (1010, 500));
+    }
+
+    #[test]
+    fn real_position_is_resent_when_a_predicted_cursor_stops() {
+        let mut state = InputState::new(1920, 1080);
+        state.startup_cursor_mode = StartupCursorMode::Emit;
+        state.cursor_prediction = Duration::from_millis(50);
+        let shared = SharedState::new(state);
+        shared.set_debug_events(true);
+        let last_cursor_x = || {
+            shared
+                .recent_events(10)
+                .into_iter()
+                .rev()
+                .find_map(|(_, event)| match event {
+                    OutputEvent::Cursor { x, .. } => Some(x),
+                    _ => None,
+                })
+        };
+
+        let mut state = shared.input_state.lock().unwrap();
+        state.set_cursor_absolute(1000, 500);
+        shared.emit_cursor(&NullSink, &mut state);
+        std::thread::sleep(Duration::from_millis(10));
+        state.set_cursor_absolute(1010, 500);
+        shared.emit_cursor(&NullSink, &mut state);
+        assert!(last_cursor_x().unwrap() > 1010);
+
+        // Within a tick of the last move it may still be moving
+        let (moved_at, _, _) = state.last_velocity_sample.unwrap();
+        assert!(!state.take_prediction_settle(moved_at + Duration::from_millis(5)));
+        assert!(state.take_prediction_settle(moved_at + CURSOR_TICK));
+        shared.emit_cursor(&NullSink, &mut state);
+        assert_eq!(last_cursor_x(), Some(1010));
+        assert!(!state.take_prediction_settle(Instant::now() + CURSOR_TICK));
+    }
+
+    #[test]
+    fn anchors_place_the_window_inside_the_monitor() {
+        // A second monitor to the right of a 1920-wide primary
+        let monitor = Rect {
+            x: 1920,
+            y: 0,
+            width: 2560,
+            height: 1440,
+        };
+
+        assert_eq!(Anchor::TopLeft.place(monitor, 200, 300), (1920, 0));
+        assert_eq!(Anchor::Center.place(monitor, 200, 300), (3100, 570));
+        assert_eq!(
+            Anchor::from_name("Bottom-Right")
+                .unwrap()
+                .place(monitor, 200, 300),
+            (4280, 1140)
+        );
+        assert_eq!(Anchor::from_name("middle"), None);
+    }
+
+    #[test]
+    fn held_trigger_key_fires_once() {
+        let mut state = InputState::new(1920, 1080);
+        state.held_modifiers.insert(KeyCode::LeftMeta);
+        state.held_modifiers.insert(KeyCode::LeftShift);
+
+        assert_eq!(
+            state.press_shortcut_key(KeyCode::F).as_deref(),
+            Some("toggle_chat")
+        );
+        // Auto-repeat of the still-held F
+        assert_eq!(state.press_shortcut_key(KeyCode::F), None);
+
+        state.shortcut_key_released(KeyCode::F);
+        assert_eq!(
+            state.press_shortcut_key(KeyCode::F).as_deref(),
+            Some("toggle_chat")
+        );
+    }
+
+    #[test]
+    fn shortcut_bindings_may_not_share_a_combo() {
+        let mut bindings = ShortcutBinding::defaults();
+        assert_eq!(ShortcutBinding::check_all(&bindings), Ok(()));
+
+        bindings.push(ShortcutBinding::new(&["Shift", "super"], "f", "open_notes"));
+        assert_eq!(
+            ShortcutBinding::check_all(&bindings),
+            Err("toggle_chat and open_notes are both bound to Shift+super+f".to_string())
+        );
+
+        bindings.pop();
+        bindings.push(ShortcutBinding::new(&["meta"], "F", "open_notes"));
+        assert_eq!(ShortcutBinding::check_all(&bindings), Ok(()));
+
+        bindings.push(ShortcutBinding::new(&["hyper"], "F", "broken"));
+        assert!(ShortcutBinding::check_all(&bindings).is_err());
+    }
+
+    #[test]
+    fn shortcut_needs_every_modifier() {
+        let mut state = InputState::new(1920, 1080);
+        state.held_modifiers.insert(KeyCode::LeftMeta);
+
+        assert_eq!(state.check_shortcut(KeyCode::F), None);
+    }
+
+    #[test]
+    fn caps_lock_binding_follows_the_lock_not_the_key() {
+        let mut state = InputState::new(1920, 1080);
+        state.shortcuts = vec![ShortcutBinding::new(&["caps_lock"], "F1", "shout")];
+
+        // Holding the key without the lock on doesn't count
+        state.held_modifiers.insert(KeyCode::CapsLock);
+        assert_eq!(state.check_shortcut(KeyCode::F1), None);
+
+        // The lock being on does, after the key is released
+        state.held_modifiers.remove(&KeyCode::CapsLock);
+        state.set_lock(KeyCode::CapsLock, true);
+        assert_eq!(state.check_shortcut(KeyCode::F1), Some("shout"));
+    }
+
+    /// Presses and releases `key` at `at`, holding it for 50 ms
+    fn tap(state: &mut InputState, key: KeyCode, at: Instant) -> Option<String> {
+        state.held_modifiers.insert(key);
+        state.track_modifier_tap(key, true, at);
+        state.held_modifiers.remove(&key);
+        state.track_modifier_tap(key, false, at + Duration::from_millis(50))
+    }
+
+    fn double_tap_shift_state() -> InputState {
+        let mut state = InputState::new(1920, 1080);
+        state.shortcuts.push(ShortcutBinding {
+            double_tap: true,
+            ..ShortcutBinding::new(&[], "shift", "toggle_chat")
+        });
+        state
+    }
+
+    #[test]
+    fn double_tapping_shift_fires_its_binding() {
+        let mut state = double_tap_shift_state();
+        let t0 = Instant::now();
+
+        assert_eq!(tap(&mut state, KeyCode::LeftShift, t0), None);
+        assert_eq!(
+            tap(
+                &mut state,
+                KeyCode::LeftShift,
+                t0 + Duration::from_millis(200)
+            ),
+            Some("toggle_chat".to_string())
+        );
+    }
+
+    #[test]
+    fn typing_capitals_never_counts_as_a_tap() {
+        let mut state = double_tap_shift_state();
+        let t0 = Instant::now();
+
+        for i in 0..2 {
+            let at = t0 + Duration::from_millis(i * 150);
+            state.held_modifiers.insert(KeyCode::LeftShift);
+            state.track_modifier_tap(KeyCode::LeftShift, true, at);
+            state.track_modifier_tap(KeyCode::A, true, at + Duration::from_millis(20));
+            state.track_modifier_tap(KeyCode::A, false, at + Duration::from_millis(40));
+            state.held_modifiers.remove(&KeyCode::LeftShift);
+            assert_eq!(
+                state.track_modifier_tap(KeyCode::LeftShift, false, at + Duration::from_millis(60)),
+                None
+            );
+        }
+
+        // Nor does a long hold, or two taps too far apart
+        state.held_modifiers.insert(KeyCode::LeftShift);
+        state.track_modifier_tap(KeyCode::LeftShift, true, t0);
+        state.held_modifiers.remove(&KeyCode::LeftShift);
+        state.track_modifier_tap(KeyCode::LeftShift, false, t0 + Duration::from_secs(1));
+        assert_eq!(
+            tap(
+                &mut state,
+                KeyCode::LeftShift,
+                t0 + Duration::from_millis(1100)
+            ),
+            None
+        );
+        assert_eq!(
+            tap(&mut state, KeyCode::LeftShift, t0 + Duration::from_secs(3)),
+            None
+        );
+    }
+
+    #[test]
+    fn locked_axis_ignores_relative_and_absolute_moves() {
+        let mut state = InputState::new(1920, 1080);
+        state.cursor_synced = true;
+        state.set_cursor_absolute(100, 100);
+        state.lock_axis = Some(Axis::X);
+
+        state.update_cursor(50, 20);
+        assert_eq!((state.cursor_x, state.cursor_y), (100, 120));
+
+        state.set_cursor_absolute(700, 300);
+        assert_eq!((state.cursor_x, state.cursor_y), (100, 300));
+
+        state.lock_axis = Some(Axis::Y);
+        state.set_cursor_absolute(700, 900);
+        assert_eq!((state.cursor_x, state.cursor_y), (700, 300));
+    }
+
+    #[test]
+    fn low_sensitivity_keeps_sub_pixel_movement() {
+        let mut state = InputState::new(1920, 1080);
+        state.sensitivity = 0.25;
+        let start_x = state.cursor_x;
+
+        // Each 1px move scales to 0.25px; four of them add up to one pixel
+        for _ in 0..4 {
+            state.update_cursor(1, 0);
+        }
+        assert_eq!(state.cursor_x, start_x + 1);
+
+        state.update_cursor(-8, 0);
+        assert_eq!(state.cursor_x, start_x - 1);
+    }
+
+    #[test]
+    fn smoothing_eases_toward_the_real_cursor() {
+        let mut state = InputState::new(1920, 1080);
+        state.cursor_synced = true;
+        state.set_cursor_absolute(0, 0);
+        state.set_cursor_smoothing(true);
+
+        assert!(!state.update_cursor(100, 0));
+        assert!(state.is_smoothing());
+        assert_eq!(state.tick_smoothing(), Some((30, 0)));
+
+        let mut ticks = 1;
+        while state.is_smoothing() {
+            state.tick_smoothing();
+            ticks += 1;
+        }
+        assert!(ticks < 30);
+        assert_eq!((state.last_reported_x, state.last_reported_y), (100, 0));
+    }
+
+    #[test]
+    fn absolute_positions_bypass_smoothing() {
+        let mut state = InputState::new(1920, 1080);
+        state.cursor_synced = true;
+        state.set_cursor_smoothing(true);
+        state.update_cursor(100, 0);
+
+        assert!(state.set_cursor_absolute(500, 500));
+        assert!(!state.is_smoothing());
+        assert_eq!(state.tick_smoothing(), None);
+    }
+
+    #[test]
+    fn rapid_moves_are_coalesced_into_throttled_reports() {
+        let mut state = InputState::new(1920, 1080);
+        state.cursor_emit_interval = Duration::from_millis(50);
+
+        let emits = (0..100).filter(|_| state.update_cursor(1, 1)).count();
+        assert!(emits <= 2, "{} emits", emits);
+        assert!(state.needs_cursor_tick());
+
+        // The held-back position is reported once the interval has passed
+        std::thread::sleep(state.cursor_emit_interval);
+        assert!(state.take_throttled_cursor());
+        assert_eq!((state.last_reported_x, state.last_reported_y), (1060, 640));
+        assert!(!state.needs_cursor_tick());
+    }
+
+    #[test]
+    fn cursor_in_dead_zone_snaps_to_nearest_monitor() {
+        // 2560x1440 primary with a shorter 1920x1080 monitor to its right
+        let mut state = InputState::new(2560, 1440);
+        state.set_monitors(Monitors {
+            rects: vec![
+                Rect {
+                    x: 0,
+                    y: 0,
+                    width: 2560,
+                    height: 1440,
+                },
+                Rect {
+                    x: 2560,
+                    y: 0,
+                    width: 1920,
+                    height: 1080,
+                },
+            ],
+        });
+        assert_eq!((state.screen_width, state.screen_height), (4480, 1440));
+
+        // Below the right monitor is outside both displays
+        state.set_cursor_absolute(3000, 1300);
+
+        assert_eq!((state.cursor_x, state.cursor_y), (3000, 1079));
+    }
+
+    #[test]
+    fn cursor_near_an_outer_edge_snaps_onto_it() {
+        // Two 1920x1080 monitors side by side
+        let monitors = Monitors {
+            rects: vec![
+                Rect {
+                    x: 0,
+                    y: 0,
+                    width: 1920,
+                    height: 1080,
+                },
+                Rect {
+                    x: 1920,
+                    y: 0,
+                    width: 1920,
+                    height: 1080,
+                },
+            ],
+        };
+
+        assert_eq!(monitors.snap_to_edge(3, 1077, 4), (0, 1079, true));
+        assert_eq!(monitors.snap_to_edge(3837, 500, 4), (3839, 500, true));
+        assert_eq!(monitors.snap_to_edge(0, 500, 0), (0, 500, true));
+        assert_eq!(monitors.snap_to_edge(3, 500, 0), (3, 500, false));
+        // The edge between the two monitors isn't a screen edge
+        assert_eq!(monitors.snap_to_edge(1918, 500, 4), (1918, 500, false));
+        assert_eq!(monitors.snap_to_edge(1921, 500, 4), (1921, 500, false));
+    }
+
+    #[test]
+    fn scaled_monitors_snap_in_logical_pixels() {
+        // A 4K monitor at 150% beside a 1080p one at 100%, as physical rects
+        let monitors = Monitors {
+            rects: vec![
+                Rect {
+                    x: 0,
+                    y: 0,
+                    width: 3840,
+                    height: 2160,
+                },
+                Rect {
+                    x: 3840,
+                    y: 0,
+                    width: 1920,
+                    height: 1080,
+                },
+            ],
+        };
+        let logical = monitors.to_logical(1.5);
+        assert_eq!(
+            logical.rects[0],
+            Rect {
+                x: 0,
+                y: 0,
+                width: 2560,
+                height: 1440,
+            }
+        );
+        assert_eq!(logical.rects[1].x, 2560);
+
+        // The bottom edge is reachable in logical pixels
+        assert_eq!(logical.snap_to_edge(100, 1437, 4), (100, 1439, true));
+        assert_eq!(monitors.snap_to_edge(100, 1437, 4), (100, 1437, false));
+        // The monitors still meet, so the shared edge isn't a screen edge
+        assert_eq!(logical.snap_to_edge(2558, 500, 4), (2558, 500, false));
+        assert_eq!(logical.snap_to_edge(3838, 500, 4), (3839, 500, true));
+    }
+
+    #[test]
+    fn double_click_then_hold_and_move_is_a_double_click_drag() {
+        let mut state = InputState::new(1920, 1080);
+        let t0 = Instant::now();
+
+        state.left_button_down(t0);
+        state.left_button_up();
+        state.left_button_down(t0 + Duration::from_millis(150));
+        state.update_cursor(25, 10);
+
+        assert!(state.is_double_click_dragging());
+    }
+
+    #[test]
+    fn quick_double_click_release_is_not_a_drag() {
+        let mut state = InputState::new(1920, 1080);
+        let t0 = Instant::now();
+
+        state.left_button_down(t0);
+        state.left_button_up();
+        state.left_button_down(t0 + Duration::from_millis(150));
+        state.left_button_up();
+        state.update_cursor(25, 10);
+
+        assert!(!state.is_double_click_dragging());
+    }
+
+    #[test]
+    fn single_press_drag_is_not_a_double_click_drag() {
+        let mut state = InputState::new(1920, 1080);
+
+        state.left_button_down(Instant::now());
+        state.update_cursor(25, 10);
+
+        assert!(state.left_button_held);
+        assert!(!state.is_double_click_dragging());
+    }
+
+    #[test]
+    fn press_move_release_is_a_drag() {
+        let mut state = InputState::new(1920, 1080);
+
+        state.left_button_down(Instant::now());
+        state.update_cursor(20, 0);
+        assert_eq!(state.drag_step(), Some(DragStep::Start(960, 540)));
+        state.update_cursor(20, 0);
+        assert_eq!(state.drag_step(), Some(DragStep::Move));
+
+        assert!(state.left_button_up());
+    }
+
+    #[test]
+    fn click_without_movement_is_not_a_drag() {
+        let mut state = InputState::new(1920, 1080);
+
+        state.left_button_down(Instant::now());
+        // A little jitter stays below the drag threshold
+        state.update_cursor(2, -1);
+        assert_eq!(state.drag_step(), None);
+
+        assert!(!state.left_button_up());
+    }
+
+    #[test]
+    fn slow_second_press_starts_a_new_click() {
+        let mut state = InputState::new(1920, 1080);
+        let t0 = Instant::now();
+
+        state.left_button_down(t0);
+        state.left_button_up();
+        state.left_button_down(t0 + DOUBLE_CLICK_INTERVAL + Duration::from_millis(1));
+        state.update_cursor(25, 10);
+
+        assert!(!state.is_double_click_dragging());
+    }
+
+    struct NullSink;
+
+    impl EventSink for NullSink {
+        fn send(&self, _channel: &str, _event: &OutputEvent) {}
+    }
+
+    #[test]
+    fn activity_is_coalesced() {
+        let shared = SharedState::new(InputState::new(1920, 1080));
+        shared.set_debug_events(true);
+        for _ in 0..10 {
+            shared.emit(&NullSink, "activity", OutputEvent::Activity);
+        }
+        assert_eq!(shared.recent_events(usize::MAX).len(), 1);
+    }
+
+    #[test]
+    fn recent_events_keeps_only_the_newest() {
+        let shared = SharedState::new(InputState::new(1920, 1080));
+        shared.emit(&NullSink, "activity", OutputEvent::Activity);
+        assert!(shared.recent_events(10).is_empty());
+
+        shared.set_debug_events(true);
+        shared.set_activity_interval(Duration::ZERO);
+        for _ in 0..RECENT_EVENTS_CAPACITY + 5 {
+            shared.emit(&NullSink, "activity", OutputEvent::Activity);
+        }
+        shared.emit(&NullSink, "active", OutputEvent::Active);
+
+        assert_eq!(
+            shared.recent_events(usize::MAX).len(),
+            RECENT_EVENTS_CAPACITY
+        );
+        let last_two = shared.recent_events(2);
+        assert!(matches!(last_two[0].1, OutputEvent::Activity));
+        assert!(matches!(last_two[1].1, OutputEvent::Active));
     }
 }
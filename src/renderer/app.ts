@@ -25,11 +25,11 @@
 // =============================================================================
 
 import { invoke } from '@tauri-apps/api/core';
-import { getCurrentWindow, PhysicalPosition, currentMonitor } from '@tauri-apps/api/window';
+import { getCurrentWindow, PhysicalPosition } from '@tauri-apps/api/window';
 import { setupClickThrough } from './modules/interactions';
 import { initCharacter } from './modules/character';
 import { initChat } from './modules/chat';
-import { initSettings, applyConfig } from './modules/settings';
+import { initSettings, applyConfig, openSettings } from './modules/settings';
 import { initLighting } from './modules/lighting';
 import { initScreensaver } from './modules/screensaver';
 import { unregisterAll } from '@tauri-apps/plugin-global-shortcut';
@@ -50,13 +50,14 @@ const appWindow = getCurrentWindow();
 // =============================================================================
 
 /**
- * Saves the current window position into the persisted config.
+ * Saves the window position (the current one unless `pos` is given) into
+ * the persisted config.
  * Merges windowX/windowY into the existing config so other settings
  * are preserved. Called after drags, on recentering, and before close.
  */
-export async function saveWindowPosition() {
+export async function saveWindowPosition(pos?: { x: number; y: number }) {
     try {
-        const pos = await appWindow.outerPosition();
+        pos = pos ?? await appWindow.outerPosition();
         // Merge position into the existing config object
         const updatedConfig = { ...state.config, windowX: pos.x, windowY: pos.y };
         await invoke('save_config', { config: updatedConfig });
@@ -67,27 +68,14 @@ export async function saveWindowPosition() {
 }
 
 /**
- * Centers the character window on the current monitor.
- * Calculates the centre based on monitor size and current window dimensions.
+ * Centers the character window on the monitor it is currently on.
+ * The backend does the monitor math; this backs the center_character shortcut
+ * only when it is frontend-only.
  */
 async function centerCharacterOnScreen() {
     try {
-        const monitor = await currentMonitor();
-        if (!monitor) {
-            console.warn('[Renderer] Could not detect current monitor for centering');
-            return;
-        }
-
-        const windowSize = await appWindow.outerSize();
-        const monitorPos = monitor.position;   // Top-left corner of the monitor
-        const monitorSize = monitor.size;       // Monitor dimensions
-
-        // Calculate centre position, accounting for monitor offset (multi-monitor setups)
-        const centerX = monitorPos.x + Math.round((monitorSize.width - windowSize.width) / 2);
-        const centerY = monitorPos.y + Math.round((monitorSize.height - windowSize.height) / 2);
-
-        await appWindow.setPosition(new PhysicalPosition(centerX, centerY));
-        console.log(`[Renderer] Centered character at (${centerX}, ${centerY})`);
+        await invoke('center_character');
+        console.log('[Renderer] Centered character');
 
         // Save the new centred position to config
         await saveWindowPosition();
@@ -214,7 +202,18 @@ async function init() {
         if (name === 'toggle_chat') activateChat();
         if (name === 'toggle_drag') toggleDragMode();
         if (name === 'toggle_screensaver') toggleScreensaver();
-        if (name === 'center_character') centerCharacterOnScreen();
+        // The backend centres the window itself unless the action is
+        // frontend-only, and reports the result as character-centered
+        if (name === 'center_character' && state.config?.frontendOnlyActions?.includes('center_character')) {
+            centerCharacterOnScreen();
+        }
+        if (name === 'open_settings') openSettings();
+    });
+
+    // Save where the backend's center_character action put the window
+    listen('character-centered', (event: any) => {
+        const { x, y } = event.payload;
+        saveWindowPosition({ x, y });
     });
 
     // -------------------------------------------------------------------------
@@ -353,7 +353,7 @@ import { LogicalSize } from '@tauri-apps/api/window';
 import { updateWindowSize } from './chat';
 import { DEFAULT_GEMINI_MODEL } from '../constants';
 
-async function openSettings() {
+export async function openSettings() {
     settingsPanel.classList.remove('hidden');
     setInteractionOverride(true);
     // Resize window to fit settings comfortably